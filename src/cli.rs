@@ -0,0 +1,75 @@
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use std::path::PathBuf;
+
+/// 机器人实验平台命令行
+#[derive(Parser)]
+#[command(name = "robot_platform", version, about = "机器人实验平台")]
+pub struct Cli {
+    /// 日志文件所在的目录
+    #[arg(long, global = true, default_value = "logs")]
+    pub log_dir: PathBuf,
+
+    /// 日志文件格式
+    #[arg(long, global = true, value_enum, default_value_t = LogFormat::Both)]
+    pub log_format: LogFormat,
+
+    #[command(subcommand)]
+    pub command: Command,
+}
+
+#[derive(Subcommand)]
+pub enum Command {
+    /// 加载配置文件与任务文件并开始实验
+    Run(RunArgs),
+    /// 解析配置文件与任务文件，并检查节点类型是否已经注册
+    Validate(ExpArgs),
+    /// 列出所有已经注册的节点类型及其端口
+    ListNodes,
+    /// 列出支持的机器人类型，指定配置文件时列出配置文件中的机器人
+    ListRobots {
+        /// 实验配置文件路径
+        #[arg(short, long)]
+        config: Option<String>,
+    },
+    /// 构建任务依赖图并推演任务的执行顺序，不启动任何线程
+    DryRun(ExpArgs),
+}
+
+#[derive(Args)]
+pub struct ExpArgs {
+    /// 实验配置文件路径
+    #[arg(short, long, default_value = "./config/config.json")]
+    pub config: String,
+
+    /// 任务文件路径
+    #[arg(short, long, default_value = "./config/task.json")]
+    pub task: String,
+}
+
+#[derive(Args)]
+pub struct RunArgs {
+    #[command(flatten)]
+    pub exp: ExpArgs,
+
+    /// 收到 SIGINT/SIGTERM 后等待所有节点退出的最长时间，单位为秒
+    #[arg(long, default_value_t = 5.0)]
+    pub shutdown_timeout: f64,
+
+    /// 使用单线程的锁步执行器，所有节点按拓扑顺序在模拟时钟下执行，值为基础节拍，单位为秒
+    #[arg(long, value_name = "BASE_TICK")]
+    pub lockstep: Option<f64>,
+
+    /// 锁步模式下模拟时钟的最长运行时间，单位为秒，缺省时运行到没有节点或收到停止信号为止
+    #[arg(long, requires = "lockstep")]
+    pub sim_duration: Option<f64>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum)]
+pub enum LogFormat {
+    /// 仅输出文本日志 info.log
+    Text,
+    /// 仅输出 json 日志 info.json
+    Json,
+    /// 同时输出文本日志与 json 日志
+    Both,
+}
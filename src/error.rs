@@ -0,0 +1,127 @@
+use std::{fmt, path::PathBuf};
+
+use manager::{LockstepError, ManagerError, TaskError};
+use node::NodeError;
+use robot::RobotError;
+use sensor::SensorError;
+
+/// 实验过程中的错误，汇总各个模块的错误并附带出错位置，便于命令行给出可读的诊断信息
+#[derive(Debug)]
+pub enum ExpError {
+    /// 配置文件或任务文件加载失败
+    Manager(ManagerError),
+    /// 配置文件中的机器人无法创建
+    Robot {
+        path: PathBuf,
+        robot: String,
+        source: RobotError,
+    },
+    /// 配置文件中的传感器无法创建
+    Sensor {
+        path: PathBuf,
+        sensor: String,
+        source: SensorError,
+    },
+    /// 任务中的边无法解析
+    Task { path: PathBuf, source: TaskError },
+    /// 任务中的节点无法创建，节点序号与 edges 一致从 1 开始计数，NodeError 较大，装箱存放
+    Node {
+        path: PathBuf,
+        task: usize,
+        node: usize,
+        source: Box<NodeError>,
+    },
+    /// 任务中需要指令权的节点所用的机器人没有被该任务租用，holder 为当前持有租约的任务
+    Lease {
+        path: PathBuf,
+        task: usize,
+        robot: String,
+        holder: Option<usize>,
+    },
+    /// 任务中的节点无法加入锁步执行器
+    Lockstep {
+        path: PathBuf,
+        task: usize,
+        source: LockstepError,
+    },
+}
+
+impl fmt::Display for ExpError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ExpError::Manager(error) => write!(f, "{}", error),
+            ExpError::Robot {
+                path,
+                robot,
+                source,
+            } => write!(f, "{}: robot {}: {}", path.display(), robot, source),
+            ExpError::Sensor {
+                path,
+                sensor,
+                source,
+            } => write!(f, "{}: sensor {}: {}", path.display(), sensor, source),
+            ExpError::Task { path, source } => write!(f, "{}: {}", path.display(), source),
+            ExpError::Node {
+                path,
+                task,
+                node,
+                source,
+            } => write!(
+                f,
+                "{}: task {} node {}: {}",
+                path.display(),
+                task,
+                node,
+                source
+            ),
+            ExpError::Lease {
+                path,
+                task,
+                robot,
+                holder: Some(holder),
+            } => write!(
+                f,
+                "{}: task {}: robot {} is leased to task {}",
+                path.display(),
+                task,
+                robot,
+                holder
+            ),
+            ExpError::Lease {
+                path,
+                task,
+                robot,
+                holder: None,
+            } => write!(
+                f,
+                "{}: task {}: robot {} is not leased to this task",
+                path.display(),
+                task,
+                robot
+            ),
+            ExpError::Lockstep { path, task, source } => {
+                write!(f, "{}: task {}: {}", path.display(), task, source)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ExpError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ExpError::Manager(error) => Some(error),
+            ExpError::Robot { source, .. } => Some(source),
+            ExpError::Sensor { source, .. } => Some(source),
+            ExpError::Task { source, .. } => Some(source),
+            ExpError::Node { source, .. } => Some(source.as_ref()),
+            ExpError::Lease { .. } => None,
+            ExpError::Lockstep { source, .. } => Some(source),
+        }
+    }
+}
+
+impl From<ManagerError> for ExpError {
+    fn from(error: ManagerError) -> Self {
+        ExpError::Manager(error)
+    }
+}
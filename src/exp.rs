@@ -1,26 +1,46 @@
 use chrono::Local;
-use crossbeam::queue::SegQueue;
 use message::TaskState;
-use serde_json::from_reader;
 use std::{
-    fs,
+    path::PathBuf,
     sync::{Arc, RwLock, mpsc},
+    time::Duration,
 };
 
-use manager::{Config, Task, TaskManager, ThreadManager};
-use node::{NodeBehavior, factory};
+use manager::{Config, LockstepExecutor, Schedule, Task, TaskEvent, TaskManager, ThreadManager};
+use node::{NodeBehavior, NodeError, NodeHandle, NodeState, connect, create_node};
 use robot::{self, RobotType};
 use sensor::Sensor;
 
+use crate::error::ExpError;
+
 #[derive(Default)]
 pub struct Exp {
     pub state: ExpState,
 
     pub thread_manager: ThreadManager,
     pub task_manager: TaskManager,
+    /// 锁步执行器，存在时节点不再由线程管理器开辟线程，而是在实验线程中按模拟时钟执行
+    pub lockstep: Option<LockstepExecutor>,
 
     pub robot_pool: Vec<RobotType>,
     pub sensor_pool: Vec<Arc<RwLock<Sensor>>>,
+
+    /// 收到停止信号后等待所有节点退出的最长时间
+    pub shutdown_timeout: Duration,
+    /// 任务文件路径，用于在错误信息中指明出错位置
+    pub task_path: PathBuf,
+    /// 锁步模式下模拟时钟的最长运行时间，到达后实验结束
+    pub sim_duration: Option<Duration>,
+}
+
+/// 节点的执行方式，同一个任务文件可以选择任一种方式运行
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum Executor {
+    /// 每个节点一个线程，按照墙上时钟周期执行
+    #[default]
+    Threaded,
+    /// 所有节点在实验线程中按拓扑顺序锁步执行，节点周期必须是 base_tick 的整数倍
+    Lockstep { base_tick: Duration },
 }
 
 #[derive(Default, PartialEq)]
@@ -29,53 +49,87 @@ pub enum ExpState {
     Init,
     Running,
     TaskSorting,
+    Stopping,
+    Finished,
 }
 
 impl Exp {
-    pub fn from_json(config: &str, task: &str) -> Self {
+    pub fn from_file(config_path: &str, task: &str, executor: Executor) -> Result<Self, ExpError> {
         // 加载配置文件
-        let config_file = fs::File::open(config).expect("Failed to open config file");
-        let config: Config = from_reader(config_file).expect("Failed to parse config file");
+        let config = Config::from_file(config_path)?;
         // 根据配置开始初始化，关键在于搭建通讯
         let (sender, receiver) = mpsc::channel();
 
         // 创建线程管理器，线程管理器向任务管理器汇报任务完成情况,汇报内容为一个枚举类型
+        let lockstep = match executor {
+            Executor::Threaded => None,
+            Executor::Lockstep { base_tick } => {
+                Some(LockstepExecutor::new(sender.clone(), base_tick))
+            }
+        };
         let thread_manager = ThreadManager::new(sender);
         // 创建任务管理器，任务管理器接受线程管理器的汇报内容
-        let task_manager = TaskManager::from_json(receiver, task);
+        let task_manager = TaskManager::from_file(receiver, task, &config)?;
         // 创建实验状态机，实验状态机负责管理实验的整个过程
         let state = ExpState::Init;
 
         // 根据配置文件创建机器人池和传感器池，这些机器人和传感器将伴随整个实验过程，当有节点需要时从池中取出
-        // TODO 设置使用状态，避免多个任务同时发布至同一个机器人
+        // 任务管理器为每个任务分配机器人租约，避免多个任务同时向同一个机器人下发指令
         let mut robot_pool = Vec::new();
         let mut sensor_pool = Vec::new();
         for robot_config in config.robots {
-            let robot = robot::from_config(&robot_config);
+            let robot = robot::from_config(&robot_config).map_err(|source| ExpError::Robot {
+                path: PathBuf::from(config_path),
+                robot: robot_config.name.clone(),
+                source,
+            })?;
             robot_pool.push(robot);
         }
-        for sensor in config.sensors {
-            sensor_pool.push(sensor::from_config(&sensor));
+        for sensor_config in config.sensors {
+            let sensor =
+                sensor::from_config(&sensor_config).map_err(|source| ExpError::Sensor {
+                    path: PathBuf::from(config_path),
+                    sensor: sensor_config.name.clone(),
+                    source,
+                })?;
+            sensor_pool.push(sensor);
         }
 
-        Exp {
+        Ok(Exp {
             state,
             thread_manager,
             task_manager,
+            lockstep,
             robot_pool,
             sensor_pool,
-        }
+            shutdown_timeout: Duration::from_secs(5),
+            task_path: PathBuf::from(task),
+            sim_duration: None,
+        })
     }
 
-    /// 从机器人池中抓取机器人
-    /// TODO 需要做机器人状态管理，避免多个任务
-    pub fn get_robot_from_name(&self, name: &str) -> Option<RobotType> {
-        for robot in &self.robot_pool {
-            if robot.name() == name {
-                return Some(robot.clone());
+    /// 从机器人池中抓取机器人，task_id 为需要指令权的节点所属的任务，只读节点与仿真器传入 None
+    /// 需要指令权的节点只能抓取其任务持有租约的机器人
+    pub fn get_robot_from_name(
+        &self,
+        name: &str,
+        task_id: Option<usize>,
+    ) -> Result<Option<RobotType>, ExpError> {
+        let Some(robot) = self.robot_pool.iter().find(|robot| robot.name() == name) else {
+            return Ok(None);
+        };
+        if let Some(task_id) = task_id {
+            let holder = self.task_manager.lease_holder(name);
+            if holder != Some(task_id) {
+                return Err(ExpError::Lease {
+                    path: self.task_path.clone(),
+                    task: task_id,
+                    robot: name.to_string(),
+                    holder,
+                });
             }
         }
-        None
+        Ok(Some(robot.clone()))
     }
 
     pub fn get_sensor_from_name(&self, name: &str) -> Option<Arc<RwLock<Sensor>>> {
@@ -89,20 +143,44 @@ impl Exp {
 
     /// 根据机器人类型创建对应的节点
     /// TODO 当前对应节点只是对单一机器人新建节点，完整形态应当是根据机器人名称新建节点
-    pub fn create_nodes(&mut self, task: &Task) {
+    /// 任一节点创建失败时不会启动该任务的任何节点
+    pub fn create_nodes(&mut self, task: &Task) -> Result<(), ExpError> {
+        // 将按名称书写的边解析为节点序号
+        let edges = task.resolve_edges().map_err(|source| ExpError::Task {
+            path: self.task_path.clone(),
+            source,
+        })?;
+
         let mut node_list = Vec::new();
+        let mut schedules = Vec::new();
         // 创建节点
-        for node_config in task.nodes.clone() {
+        for (index, node_config) in task.nodes.clone().into_iter().enumerate() {
+            // 节点参数中可选的实时调度参数
+            // 节点名称附带任务 id 与节点序号，同一机器人上先后执行的任务不会混淆节点归属
+            let label = task.node_label(index);
+            let schedule = Schedule::from_params(&node_config.params).map_err(|source| {
+                let name = format!("{}:{}", node_config.node_type, label);
+                self.node_error(task.id, index, NodeError::Params { name, source })
+            })?;
+            schedules.push(schedule);
+            // 需要指令权的节点所属的任务，只读节点与仿真器为 None
+            let lease = node_config.needs_lease().then_some(task.id);
             // 创建节点
-            let mut node = factory(&node_config.0, &node_config.1.join("+"), node_config.3);
-            // 为新创建的节点赋予机器人
-            for robot_name in node_config.1 {
-                if let Some(robot) = self.get_robot_from_name(&robot_name) {
+            let mut node = create_node(
+                &node_config.node_type,
+                &label,
+                node_config.params,
+                node_config.message,
+            )
+            .map_err(|source| self.node_error(task.id, index, source))?;
+            // 为新创建的节点赋予机器人，需要指令权的节点必须持有机器人的租约
+            for robot_name in node_config.robots {
+                if let Some(robot) = self.get_robot_from_name(&robot_name, lease)? {
                     node.set_robot(robot);
                 }
             }
             // 为新创建的节点赋予传感器
-            for sensor_name in node_config.2 {
+            for sensor_name in node_config.sensors {
                 if let Some(sensor) = self.get_sensor_from_name(&sensor_name) {
                     node.set_sensor(sensor);
                 }
@@ -110,15 +188,15 @@ impl Exp {
             // 将新创建的节点加入节点列表
             node_list.push(node);
         }
-        // 创建边
-        for edge_config in task.edges.clone() {
-            let queue = Arc::new(SegQueue::new());
+        // 创建边，边两端节点的消息类型必须一致，同一个输出端口的多条边广播，同一个输入端口的多条边合并
+        for (&edge_config, edge) in edges.iter().zip(&task.edges) {
+            let (from_port, to_port) = task.edge_ports(edge);
             if edge_config.0 == 0 {
                 // 如果是起始节点，就狠狠注入任务目标
-                node_list[edge_config.1 - 1].set_input_queue(queue.clone());
-                for target in task.target.clone() {
-                    queue.push(target);
-                }
+                let index = edge_config.1 - 1;
+                node_list[index]
+                    .push_targets(to_port, &task.target)
+                    .map_err(|source| self.node_error(task.id, index, source))?;
                 continue;
             }
             if edge_config.1 == 0 {
@@ -128,14 +206,93 @@ impl Exp {
                 continue;
             }
             // 如果是中间节点，就将彼此连接起来
-            node_list[edge_config.0 - 1].set_output_queue(queue.clone());
-            node_list[edge_config.1 - 1].set_input_queue(queue.clone());
+            connect(
+                &mut node_list,
+                (edge_config.0 - 1, from_port),
+                (edge_config.1 - 1, to_port),
+            )
+            .map_err(|source| self.node_error(task.id, edge_config.0 - 1, source))?;
+        }
+
+        // 在任务管理器中登记节点归属，标志节点全部结束后任务才算完成
+        let finish_nodes = task.finish_nodes();
+        for (index, node) in node_list.iter().enumerate() {
+            self.task_manager.register_node(
+                task.id,
+                node.node_name(),
+                finish_nodes.contains(&index),
+            );
+        }
+        if node_list.is_empty() {
+            self.task_manager.complete_task(task.id);
+        }
+
+        // 锁步模式下节点交给锁步执行器，实时调度参数不生效
+        if let Some(lockstep) = &mut self.lockstep {
+            let nodes: Vec<Box<dyn NodeBehavior>> = node_list
+                .into_iter()
+                .map(|node| -> Box<dyn NodeBehavior> {
+                    match node {
+                        NodeHandle::DVector(node) => node,
+                        NodeHandle::SVector7(node) => node,
+                        NodeHandle::F64(node) => node,
+                    }
+                })
+                .collect();
+            let edges: Vec<(usize, usize)> = edges
+                .iter()
+                .filter(|&&(from, to)| from != 0 && to != 0)
+                .map(|&(from, to)| (from - 1, to - 1))
+                .collect();
+            return lockstep
+                .add_nodes(nodes, &edges)
+                .map_err(|source| ExpError::Lockstep {
+                    path: self.task_path.clone(),
+                    task: task.id,
+                    source,
+                });
         }
 
         // 将节点加入线程管理器
         // 你已经是一个成熟的节点了，该去自己打拼生活了
-        for node in node_list {
-            self.thread_manager.add_node(node);
+        for (node, schedule) in node_list.into_iter().zip(schedules) {
+            match node {
+                NodeHandle::DVector(node) => self.thread_manager.add_scheduled_node(node, schedule),
+                NodeHandle::SVector7(node) => {
+                    self.thread_manager.add_scheduled_node(node, schedule)
+                }
+                NodeHandle::F64(node) => self.thread_manager.add_scheduled_node(node, schedule),
+            }
+        }
+        Ok(())
+    }
+
+    /// 处理节点汇报的任务反馈，任务完成或者租约释放时需要重新整理任务
+    fn handle_task_state(&mut self, task_state: TaskState) {
+        match &task_state {
+            TaskState::RelyRelease(name) => println!("{} 释放约束节点", name),
+            TaskState::PlanEnd(name) | TaskState::NodeEnd(name) => {
+                println!("{} 已结束", name)
+            }
+            TaskState::NodeStop(name) => println!("{} 已停止", name),
+            _ => (),
+        }
+        match self.task_manager.handle_task_state(task_state) {
+            Some(TaskEvent::Completed(task_id)) => println!("任务 {} 已完成", task_id),
+            Some(TaskEvent::LeaseReleased(task_id)) => {
+                println!("任务 {} 释放机器人租约", task_id)
+            }
+            None => return,
+        }
+        self.state = ExpState::TaskSorting;
+    }
+
+    fn node_error(&self, task: usize, index: usize, source: NodeError) -> ExpError {
+        ExpError::Node {
+            path: self.task_path.clone(),
+            task,
+            node: index + 1,
+            source: Box::new(source),
         }
     }
 }
@@ -157,31 +314,107 @@ impl NodeBehavior for Exp {
     /// 2. 新建节点的过程中首先需要从机器人池子里面找到对应的机器人，然后针对任务描述新建规划器节点以及控制器节点
     ///    一般来说规划器节点直接对应任务，可以以规划器生命的结束作为任务的结束，而控制器更多的是针对机器人的控制
     fn update(&mut self) {
-        let tasks: Vec<Task> = self.task_manager.get_open_tasks();
-
         match self.state {
             ExpState::TaskSorting => {
+                // 已完成任务中仍在运行的节点占用了排队任务所需的机器人时，停止这些节点以释放租约
+                for name in self.task_manager.preempted_nodes() {
+                    println!("{} 让出机器人指令权", name);
+                    match &mut self.lockstep {
+                        Some(lockstep) => lockstep.stop_node(&name),
+                        None => self.thread_manager.stop_node(&name),
+                    }
+                }
+                // 只有能够获取机器人租约的任务才会被取出，其余任务继续排队
+                let tasks: Vec<Task> = self.task_manager.get_open_tasks();
+                // 所有任务均已完成，或者锁步模式下既没有运行中的节点也没有可以启动的任务时，
+                // 实验不会再有进展
+                if tasks.is_empty()
+                    && (self.task_manager.is_all_finished()
+                        || self.lockstep.as_ref().is_some_and(|l| l.is_idle()))
+                {
+                    self.state = ExpState::Stopping;
+                    return;
+                }
                 // 整理任务,检查当前任务森林的开放节点，及时更新任务节点
+                // 节点创建失败时停止整个实验，已经启动的节点交由退出流程回收
                 for task in tasks {
-                    self.create_nodes(&task);
+                    if let Err(error) = self.create_nodes(&task) {
+                        eprintln!("error: {}", error);
+                        self.state = ExpState::Stopping;
+                        return;
+                    }
                 }
                 self.state = ExpState::Running;
             }
             ExpState::Running => {
                 // 任务执行中，一般来说什么都不做，只是等待线程管理器汇报任务完成情况。
+                // 等待的同时需要定期检查停止信号，以便及时进入退出流程
+                if self.thread_manager.is_stopping() {
+                    self.state = ExpState::Stopping;
+                    return;
+                }
                 let receiver = self.task_manager.receiver.clone().unwrap();
                 let receiver_lock = receiver.lock().unwrap();
-                let task_state = receiver_lock.recv().unwrap();
-                drop(receiver_lock);
+                let Some(lockstep) = &mut self.lockstep else {
+                    let task_state = receiver_lock.recv_timeout(Duration::from_millis(100));
+                    drop(receiver_lock);
+                    if let Ok(task_state) = task_state {
+                        self.handle_task_state(task_state);
+                    }
+                    return;
+                };
 
-                // 接收到任务反馈
-                if let TaskState::RelyRelease(name) = task_state {
-                    println!("{} 释放约束节点", name);
-                    self.task_manager.remove_task(0);
+                // 锁步模式下每次推进一个节拍，再处理这一节拍内产生的全部任务反馈
+                if self
+                    .sim_duration
+                    .is_some_and(|duration| lockstep.clock() >= duration)
+                {
+                    self.state = ExpState::Stopping;
+                    return;
+                }
+                lockstep.step();
+                let is_idle = lockstep.is_idle();
+                let task_states: Vec<TaskState> = receiver_lock.try_iter().collect();
+                drop(receiver_lock);
+                for task_state in task_states {
+                    self.handle_task_state(task_state);
+                }
+                if is_idle {
+                    self.state = ExpState::TaskSorting;
                 }
-                self.state = ExpState::TaskSorting;
+            }
+            ExpState::Stopping => {
+                // 通知所有节点停止，并在超时时间内回收线程
+                println!("收到停止信号，正在等待所有节点退出");
+                if let Some(lockstep) = &mut self.lockstep {
+                    lockstep.stop_all();
+                    lockstep.step();
+                    println!("模拟时钟停止于 {:?}", lockstep.clock());
+                }
+                self.thread_manager.stop_all();
+                let report = self.thread_manager.join_all(self.shutdown_timeout);
+                for name in &report.finished {
+                    println!("{} 已正常退出", name);
+                }
+                for name in &report.panicked {
+                    println!("{} 发生 panic", name);
+                }
+                for name in &report.timeout {
+                    println!("{} 在 {:?} 内未能退出", name, self.shutdown_timeout);
+                }
+                for (name, metrics) in self.thread_manager.metrics() {
+                    println!("{}: {}", name, metrics);
+                }
+                self.state = ExpState::Finished;
             }
             _ => (),
         }
     }
+
+    fn state(&mut self) -> NodeState {
+        match self.state {
+            ExpState::Finished => NodeState::Finished,
+            _ => NodeState::Running,
+        }
+    }
 }
@@ -1,11 +1,42 @@
 use proc_macro::TokenStream;
 use quote::quote;
-use syn::{GenericArgument, ItemType, PathArguments, Type, parse_macro_input};
+use syn::{
+    Expr, GenericArgument, Ident, ItemType, LitStr, PathArguments, Token, Type,
+    parse::{Parse, ParseStream},
+    parse_macro_input,
+};
+
+/// 属性参数：`"node_type"` 或 `"node_type", ports = EXPR`
+struct NodeRegistration {
+    node_type: LitStr,
+    ports: Option<Expr>,
+}
+
+impl Parse for NodeRegistration {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let node_type = input.parse()?;
+        let mut ports = None;
+        if input.parse::<Option<Token![,]>>()?.is_some() && !input.is_empty() {
+            let key: Ident = input.parse()?;
+            if key != "ports" {
+                return Err(syn::Error::new_spanned(key, "Expected `ports = ...`."));
+            }
+            input.parse::<Token![=]>()?;
+            ports = Some(input.parse()?);
+        }
+        Ok(NodeRegistration { node_type, ports })
+    }
+}
 
 #[proc_macro_attribute]
 pub fn node_registration(attr: TokenStream, item: TokenStream) -> TokenStream {
     // 解析属性参数
-    let node_type = attr.to_string().replace("\"", "");
+    let NodeRegistration { node_type, ports } = parse_macro_input!(attr as NodeRegistration);
+    // 未声明端口的节点使用默认的 "in" 与 "out"
+    let ports = match ports {
+        Some(ports) => quote! { #ports },
+        None => quote! { crate::NodePorts::DEFAULT },
+    };
     let input = parse_macro_input!(item as ItemType);
     let type_name = &input.ident;
 
@@ -51,9 +82,10 @@ pub fn node_registration(attr: TokenStream, item: TokenStream) -> TokenStream {
         inventory::submit! {
             NodeRegister::<#last_generic_param> {
                 node_type: #node_type,
-                node_creator: |name: String, params: serde_json::Value| -> Box<dyn NodeExtBehavior<#last_generic_param>> {
-                    Box::new(#type_name::from_params(name, params))
-                }
+                node_creator: |name: String, params: serde_json::Value| -> Result<Box<dyn NodeExtBehavior<#last_generic_param>>, NodeError> {
+                    Ok(Box::new(#type_name::from_params(name, params)?))
+                },
+                ports: #ports,
             }
         }
     };
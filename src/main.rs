@@ -3,44 +3,105 @@
 #![feature(more_float_constants)]
 #![feature(box_patterns)]
 
+mod cli;
 mod config;
+mod error;
 mod exp;
 
-use tracing_appender::{non_blocking, rolling};
+use clap::Parser;
+use std::{
+    fs,
+    path::Path,
+    process,
+    sync::{atomic::Ordering, mpsc},
+    time::Duration,
+};
+use tracing_appender::{non_blocking, non_blocking::WorkerGuard, rolling};
 use tracing_subscriber::{Registry, fmt, layer::SubscriberExt, util::SubscriberInitExt};
 
-use exp::Exp;
+use cli::{Cli, Command, ExpArgs, LogFormat, RunArgs};
+use exp::{Executor, Exp};
+use manager::{Config, ManagerError, TaskManager};
 use node::NodeBehavior;
 
 // 正式运行时使用
-// const CONFIG_PATH: &str = "./config/config.json";
-// const TASK_PATH: &str = "./config/task.json";
+// robot_platform run -c ./config/config.json -t ./config/task.json
 
 // 非实时指令样例
-const CONFIG_PATH: &str = "./example/explanner_plant_config.json";
-const TASK_PATH: &str = "./example/explanner_plant_task.json";
+// robot_platform run -c ./example/explanner_plant_config.json -t ./example/explanner_plant_task.json
 // 实时指令样例
-// const CONFIG_PATH: &str = "./example/explanner_interp_excontroller_plant_config.json";
-// const TASK_PATH: &str = "./example/explanner_interp_excontroller_plant_task.json";
+// robot_platform run -c ./example/explanner_interp_excontroller_plant_config.json -t ./example/explanner_interp_excontroller_plant_task.json
+// 内置仿真器样例
+// robot_platform run -c ./example/explanner_interp_sim_config.json -t ./example/explanner_interp_sim_task.json
 
 fn main() {
+    let cli = Cli::parse();
+
+    match cli.command {
+        Command::Run(args) => {
+            let _guards = init_log(&cli.log_dir, cli.log_format);
+            run(&args);
+        }
+        Command::Validate(args) => {
+            if !validate(&args) {
+                process::exit(1);
+            }
+        }
+        Command::ListNodes => {
+            for message_type in node::MessageType::ALL {
+                for node_type in node::node_types(message_type) {
+                    println!("{} ({})", node_type, message_type);
+                    if let Some(ports) = node::node_ports(node_type, message_type) {
+                        for port in ports.inputs {
+                            println!("  输入 {}", port);
+                        }
+                        for port in ports.outputs {
+                            println!("  输出 {}", port);
+                        }
+                    }
+                }
+            }
+        }
+        Command::ListRobots { config } => list_robots(config.as_deref()),
+        Command::DryRun(args) => dry_run(&args),
+    }
+}
+
+/// 初始化日志，返回的 guard 需要存活至实验结束，否则日志无法写入文件
+fn init_log(log_dir: &Path, log_format: LogFormat) -> Vec<WorkerGuard> {
+    let mut guards = Vec::new();
+
     // 删除已有的日志文件
-    std::fs::remove_file("./logs/info.log").unwrap_or_default();
-    std::fs::remove_file("./logs/info.json").unwrap_or_default();
+    fs::remove_file(log_dir.join("info.log")).unwrap_or_default();
+    fs::remove_file(log_dir.join("info.json")).unwrap_or_default();
 
     // 初始化日志
-    let file_appender_log = rolling::never("logs", "info.log");
-    let (non_blocking_appender_log, _guard_log) = non_blocking(file_appender_log);
-    let file_layer_log = fmt::layer()
-        .with_ansi(false)
-        .with_writer(non_blocking_appender_log);
-
-    let file_appender_json = rolling::never("logs", "info.json");
-    let (non_blocking_appender_json, _guard_json) = non_blocking(file_appender_json);
-    let file_layer_json = fmt::layer()
-        .json()
-        .with_ansi(false)
-        .with_writer(non_blocking_appender_json);
+    let file_layer_log = if log_format != LogFormat::Json {
+        let file_appender_log = rolling::never(log_dir, "info.log");
+        let (non_blocking_appender_log, guard_log) = non_blocking(file_appender_log);
+        guards.push(guard_log);
+        Some(
+            fmt::layer()
+                .with_ansi(false)
+                .with_writer(non_blocking_appender_log),
+        )
+    } else {
+        None
+    };
+
+    let file_layer_json = if log_format != LogFormat::Text {
+        let file_appender_json = rolling::never(log_dir, "info.json");
+        let (non_blocking_appender_json, guard_json) = non_blocking(file_appender_json);
+        guards.push(guard_json);
+        Some(
+            fmt::layer()
+                .json()
+                .with_ansi(false)
+                .with_writer(non_blocking_appender_json),
+        )
+    } else {
+        None
+    };
 
     // 注册
     Registry::default()
@@ -48,7 +109,28 @@ fn main() {
         .with(file_layer_json)
         .init();
 
-    let mut exp = Exp::from_json(CONFIG_PATH, TASK_PATH);
+    guards
+}
+
+fn run(args: &RunArgs) {
+    let executor = match args.lockstep {
+        Some(base_tick) => Executor::Lockstep {
+            base_tick: Duration::from_secs_f64(base_tick),
+        },
+        None => Executor::Threaded,
+    };
+    let mut exp =
+        Exp::from_file(&args.exp.config, &args.exp.task, executor).unwrap_or_else(|error| {
+            eprintln!("error: {}", error);
+            process::exit(1);
+        });
+    exp.shutdown_timeout = Duration::from_secs_f64(args.shutdown_timeout);
+    exp.sim_duration = args.sim_duration.map(Duration::from_secs_f64);
+
+    // SIGINT/SIGTERM 到来时通知所有节点停止，由实验状态机负责回收线程
+    let stop_signal = exp.thread_manager.stop_signal();
+    ctrlc::set_handler(move || stop_signal.store(true, Ordering::Release))
+        .expect("Failed to set signal handler");
 
     exp.init();
 
@@ -56,3 +138,105 @@ fn main() {
         exp.update();
     }
 }
+
+/// 检查配置文件与任务文件，所有问题都会被打印出来，全部通过时返回 true
+fn validate(args: &ExpArgs) -> bool {
+    let (config, task_manager) = match load(args) {
+        Ok(loaded) => loaded,
+        Err(error) => {
+            println!("error: {}", error);
+            return false;
+        }
+    };
+
+    let mut is_valid = true;
+    for robot_config in &config.robots {
+        if robot_config.urdf_path.is_none()
+            && !robot::ROBOT_TYPES.contains(&robot_config.robot_type.as_str())
+        {
+            println!(
+                "机器人 {} 的类型 {} 不受支持",
+                robot_config.name, robot_config.robot_type
+            );
+            is_valid = false;
+        }
+    }
+    for task in task_manager.tasks() {
+        for (index, node_config) in task.nodes.iter().enumerate() {
+            if !node::is_registered(&node_config.node_type, node_config.message) {
+                println!(
+                    "任务 {} 的第 {} 个节点类型 {} 未在 {} 消息类型下注册",
+                    task.id,
+                    index + 1,
+                    node_config.node_type,
+                    node_config.message
+                );
+                is_valid = false;
+            }
+        }
+    }
+
+    if is_valid {
+        println!("{} 与 {} 检查通过", args.config, args.task);
+    }
+    is_valid
+}
+
+/// 加载配置文件与任务文件，但不创建机器人、传感器与节点
+fn load(args: &ExpArgs) -> Result<(Config, TaskManager), ManagerError> {
+    let config = Config::from_file(&args.config)?;
+    let (_, receiver) = mpsc::channel();
+    let task_manager = TaskManager::from_file(receiver, &args.task, &config)?;
+    Ok((config, task_manager))
+}
+
+fn list_robots(config: Option<&str>) {
+    match config {
+        Some(config) => {
+            let config = Config::from_file(config).unwrap_or_else(|error| {
+                eprintln!("error: {}", error);
+                process::exit(1);
+            });
+            for robot_config in config.robots {
+                println!("{}: {}", robot_config.name, robot_config.robot_type);
+            }
+        }
+        None => {
+            for robot_type in robot::ROBOT_TYPES {
+                println!("{}", robot_type);
+            }
+        }
+    }
+}
+
+/// 仅在任务管理器中构建任务依赖图，推演任务的执行顺序而不启动任何节点
+fn dry_run(args: &ExpArgs) {
+    let (_, task_manager) = load(args).unwrap_or_else(|error| {
+        eprintln!("error: {}", error);
+        process::exit(1);
+    });
+
+    let stages = task_manager.stages();
+    for (index, stage) in stages.iter().enumerate() {
+        println!("阶段 {}: {:?}", index, stage);
+    }
+    for task in task_manager.tasks() {
+        if !stages.iter().flatten().any(|id| *id == task.id) {
+            println!("任务 {} 的依赖永远无法被满足", task.id);
+        }
+        println!("任务 {} 依赖于 {:?}", task.id, task.rely);
+        for (index, node_config) in task.nodes.iter().enumerate() {
+            println!(
+                "  节点 {}: {} ({}) {:?} {:?}",
+                index + 1,
+                node_config.node_type,
+                node_config.message,
+                node_config.robots,
+                node_config.sensors
+            );
+        }
+        for edge in &task.edges {
+            println!("  边 {} -> {}", edge.0, edge.1);
+        }
+    }
+}
@@ -2,9 +2,18 @@ use robot::RobotConfig;
 use sensor::SensorConfig;
 use serde::Deserialize;
 
+use crate::{from_file, ManagerError};
+
 /// 设置文件对应的结构体，主要用于反序列化得到机器人池及传感器池的配置
 #[derive(Debug, Deserialize)]
 pub struct Config {
     pub robots: Vec<RobotConfig>,
     pub sensors: Vec<SensorConfig>,
 }
+
+impl Config {
+    /// 从 JSON、YAML 或 TOML 文件读取配置，格式由文件扩展名决定
+    pub fn from_file(file_path: &str) -> Result<Self, ManagerError> {
+        from_file(file_path)
+    }
+}
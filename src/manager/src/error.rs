@@ -0,0 +1,82 @@
+use std::{fmt, io, path::PathBuf};
+
+use crate::TaskError;
+
+/// 配置文件与任务文件加载过程中的错误，均带有出错的文件路径
+#[derive(Debug)]
+pub enum ManagerError {
+    /// 文件扩展名不是 json、yaml、yml 或 toml
+    UnknownFormat { path: PathBuf },
+    /// 文件无法读取
+    Io { path: PathBuf, source: io::Error },
+    /// 文件内容无法被反序列化，各格式的错误中带有出错的位置
+    Parse { path: PathBuf, source: ParseError },
+    /// 任务文件未通过校验
+    InvalidTasks {
+        path: PathBuf,
+        errors: Vec<TaskError>,
+    },
+}
+
+impl fmt::Display for ManagerError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ManagerError::UnknownFormat { path } => write!(
+                f,
+                "unknown file format of {}, expected json, yaml or toml",
+                path.display()
+            ),
+            ManagerError::Io { path, source } => {
+                write!(f, "failed to read {}: {}", path.display(), source)
+            }
+            ManagerError::Parse { path, source } => {
+                write!(f, "failed to parse {}: {}", path.display(), source)
+            }
+            ManagerError::InvalidTasks { path, errors } => {
+                write!(f, "invalid task file {}:", path.display())?;
+                for error in errors {
+                    write!(f, "\n  {}", error)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl std::error::Error for ManagerError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ManagerError::Io { source, .. } => Some(source),
+            ManagerError::Parse { source, .. } => Some(source),
+            ManagerError::UnknownFormat { .. } | ManagerError::InvalidTasks { .. } => None,
+        }
+    }
+}
+
+/// 各个文件格式的反序列化错误
+#[derive(Debug)]
+pub enum ParseError {
+    Json(serde_json::Error),
+    Yaml(serde_yaml::Error),
+    Toml(toml::de::Error),
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseError::Json(error) => write!(f, "{}", error),
+            ParseError::Yaml(error) => write!(f, "{}", error),
+            ParseError::Toml(error) => write!(f, "{}", error),
+        }
+    }
+}
+
+impl std::error::Error for ParseError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ParseError::Json(error) => Some(error),
+            ParseError::Yaml(error) => Some(error),
+            ParseError::Toml(error) => Some(error),
+        }
+    }
+}
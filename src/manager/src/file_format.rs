@@ -0,0 +1,51 @@
+use serde::de::DeserializeOwned;
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use crate::{ManagerError, ParseError};
+
+/// 配置文件与任务文件支持的格式，根据文件扩展名判断
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FileFormat {
+    Json,
+    Yaml,
+    Toml,
+}
+
+impl FileFormat {
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let extension = path.extension()?.to_str()?.to_ascii_lowercase();
+        match extension.as_str() {
+            "json" => Some(FileFormat::Json),
+            "yaml" | "yml" => Some(FileFormat::Yaml),
+            "toml" => Some(FileFormat::Toml),
+            _ => None,
+        }
+    }
+
+    /// 按照当前格式反序列化文本
+    pub fn parse<T: DeserializeOwned>(&self, content: &str) -> Result<T, ParseError> {
+        match self {
+            FileFormat::Json => serde_json::from_str(content).map_err(ParseError::Json),
+            FileFormat::Yaml => serde_yaml::from_str(content).map_err(ParseError::Yaml),
+            FileFormat::Toml => toml::from_str(content).map_err(ParseError::Toml),
+        }
+    }
+}
+
+/// 读取文件并根据扩展名选择反序列化格式
+pub fn from_file<T: DeserializeOwned>(file_path: &str) -> Result<T, ManagerError> {
+    let path = PathBuf::from(file_path);
+    let Some(format) = FileFormat::from_path(&path) else {
+        return Err(ManagerError::UnknownFormat { path });
+    };
+    let content = match fs::read_to_string(&path) {
+        Ok(content) => content,
+        Err(source) => return Err(ManagerError::Io { path, source }),
+    };
+    format
+        .parse(&content)
+        .map_err(|source| ManagerError::Parse { path, source })
+}
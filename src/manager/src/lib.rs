@@ -1,9 +1,21 @@
 mod config;
+mod error;
+mod file_format;
+mod lockstep;
 mod post_office;
+mod robot_lease;
+mod schedule;
 mod task_manager;
+mod task_validation;
 mod thread_manager;
 
 pub use config::*;
+pub use error::*;
+pub use file_format::*;
+pub use lockstep::{LockstepError, LockstepExecutor};
 pub use post_office::*;
+pub use robot_lease::*;
+pub use schedule::*;
 pub use task_manager::*;
-pub use thread_manager::ThreadManager;
+pub use task_validation::*;
+pub use thread_manager::{JoinReport, ThreadManager};
@@ -0,0 +1,245 @@
+use std::collections::BTreeSet;
+use std::fmt;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::Sender;
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::info;
+
+use message::TaskState;
+use node::{NodeBehavior, NodeState};
+
+/// 锁步执行器的错误
+#[derive(Debug, Clone, PartialEq)]
+pub enum LockstepError {
+    /// 节点周期不是基础节拍的整数倍
+    PeriodNotMultiple {
+        node: String,
+        period: Duration,
+        base_tick: Duration,
+    },
+}
+
+impl fmt::Display for LockstepError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LockstepError::PeriodNotMultiple {
+                node,
+                period,
+                base_tick,
+            } => write!(
+                f,
+                "period {:?} of node {} is not a multiple of the base tick {:?}",
+                period, node, base_tick
+            ),
+        }
+    }
+}
+
+impl std::error::Error for LockstepError {}
+
+struct LockstepNode {
+    name: String,
+    node: Box<dyn NodeBehavior>,
+    /// 每隔多少个基础节拍执行一次
+    every: u64,
+    /// 是否已经汇报过释放约束，每个节点只汇报一次
+    released: bool,
+    /// 是否被单独要求停止，在下一个节拍结束
+    stopped: bool,
+    finished: bool,
+}
+
+/// 单线程的锁步执行器，所有节点在同一个线程中按照任务图的拓扑顺序依次执行，时间由模拟时钟推进
+/// 节点的周期必须是基础节拍的整数倍，周期为 0 的节点每个节拍都执行。执行顺序只取决于任务文件，
+/// 因此同一个任务文件的每次运行都会得到相同的结果，适用于仿真与集成测试
+pub struct LockstepExecutor {
+    base_tick: Duration,
+    tick: u64,
+    nodes: Vec<LockstepNode>,
+
+    /// 与 taskmanager 通信的通道，汇报内容与线程管理器一致
+    sender: Option<Sender<TaskState>>,
+
+    /// 停止信号，置位后执行器在下一个节拍结束所有节点
+    stop: Arc<AtomicBool>,
+}
+
+impl LockstepExecutor {
+    pub fn new(sender: Sender<TaskState>, base_tick: Duration) -> Self {
+        LockstepExecutor {
+            base_tick,
+            tick: 0,
+            nodes: Vec::new(),
+            sender: Some(sender),
+            stop: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    pub fn base_tick(&self) -> Duration {
+        self.base_tick
+    }
+
+    /// 已经执行的节拍数
+    pub fn tick(&self) -> u64 {
+        self.tick
+    }
+
+    /// 模拟时钟的当前时刻
+    pub fn clock(&self) -> Duration {
+        Duration::from_nanos((self.base_tick.as_nanos() * self.tick as u128) as u64)
+    }
+
+    pub fn stop_signal(&self) -> Arc<AtomicBool> {
+        self.stop.clone()
+    }
+
+    pub fn stop_all(&self) {
+        self.stop.store(true, Ordering::Release);
+    }
+
+    pub fn is_stopping(&self) -> bool {
+        self.stop.load(Ordering::Acquire)
+    }
+
+    /// 通知单个节点停止，节点在下一个节拍结束并汇报 NodeStop，不存在的节点会被忽略
+    pub fn stop_node(&mut self, name: &str) {
+        for node in self.nodes.iter_mut().filter(|node| node.name == name) {
+            node.stopped = true;
+        }
+    }
+
+    /// 是否已经没有仍在运行的节点
+    pub fn is_idle(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    /// 加入一个任务的全部节点，edges 为节点之间的连接，使用 nodes 中从 0 开始的下标
+    /// 节点按照拓扑顺序排列，上游节点先于下游节点执行，成环的节点保持其在 nodes 中的顺序
+    /// 任一节点的周期不是基础节拍的整数倍时不会加入任何节点
+    pub fn add_nodes(
+        &mut self,
+        nodes: Vec<Box<dyn NodeBehavior>>,
+        edges: &[(usize, usize)],
+    ) -> Result<(), LockstepError> {
+        let mut periods = Vec::with_capacity(nodes.len());
+        for node in &nodes {
+            periods.push(self.ticks_of(node.as_ref())?);
+        }
+
+        let order = topological_order(nodes.len(), edges);
+        let mut nodes: Vec<Option<Box<dyn NodeBehavior>>> = nodes.into_iter().map(Some).collect();
+        for index in order {
+            let mut node = nodes[index].take().unwrap();
+            let name = node.node_name();
+            println!("{} 向您问好. {} says hello.", name, name);
+            node.init();
+            self.nodes.push(LockstepNode {
+                name,
+                node,
+                every: periods[index],
+                released: false,
+                stopped: false,
+                finished: false,
+            });
+        }
+        Ok(())
+    }
+
+    fn ticks_of(&self, node: &dyn NodeBehavior) -> Result<u64, LockstepError> {
+        let period = node.period();
+        if period.is_zero() {
+            return Ok(1);
+        }
+        let ticks = period.as_secs_f64() / self.base_tick.as_secs_f64();
+        let rounded = ticks.round();
+        if !ticks.is_finite() || rounded < 1.0 || (ticks - rounded).abs() > 1e-6 * rounded {
+            return Err(LockstepError::PeriodNotMultiple {
+                node: node.node_name(),
+                period,
+                base_tick: self.base_tick,
+            });
+        }
+        Ok(rounded as u64)
+    }
+
+    /// 推进一个基础节拍，依次执行这一节拍上到期的节点，结束的节点会被 finalize 并移出执行器
+    pub fn step(&mut self) {
+        let stopping = self.is_stopping();
+        let sender = self.sender.clone().unwrap();
+        info!(tick = self.tick, clock = ?self.clock());
+        for node in self.nodes.iter_mut() {
+            if stopping || node.stopped || node.node.state() == NodeState::Finished {
+                finish(node, &sender);
+                continue;
+            }
+            if !self.tick.is_multiple_of(node.every) {
+                continue;
+            }
+            let name = node.name.as_str();
+            info!(node = name, begin = name);
+            if !node.released && node.node.state() == NodeState::RelyRelease {
+                node.released = true;
+                sender
+                    .send(TaskState::RelyRelease(name.to_string()))
+                    .unwrap_or_default();
+            }
+            node.node.update();
+            info!(node = name, end = name);
+        }
+        self.nodes.retain(|node| !node.finished);
+        self.tick += 1;
+    }
+
+    /// 连续推进直到模拟时钟到达 duration 或者没有仍在运行的节点
+    pub fn run_for(&mut self, duration: Duration) {
+        while !self.is_idle() && self.clock() < duration {
+            self.step();
+        }
+    }
+}
+
+/// 结束节点，与线程管理器一致，自然结束的节点汇报 NodeEnd，其余节点汇报 NodeStop
+fn finish(node: &mut LockstepNode, sender: &Sender<TaskState>) {
+    let name = node.name.as_str();
+    node.node.finalize();
+    if "planner" == node.node.node_type().as_str() {
+        sender
+            .send(TaskState::PlanEnd(name.to_string()))
+            .unwrap_or_default();
+    }
+    let task_state = if node.node.state() == NodeState::Finished {
+        TaskState::NodeEnd(name.to_string())
+    } else {
+        TaskState::NodeStop(name.to_string())
+    };
+    sender.send(task_state).unwrap_or_default();
+    println!("{} 向您道别. {} says goodbye.", name, name);
+    node.finished = true;
+}
+
+/// Kahn 算法求拓扑顺序，入度为 0 的节点中下标小的优先，保证顺序唯一；成环的节点按下标追加在最后
+fn topological_order(len: usize, edges: &[(usize, usize)]) -> Vec<usize> {
+    let mut in_degree = vec![0; len];
+    for &(from, to) in edges {
+        if from != to {
+            in_degree[to] += 1;
+        }
+    }
+    let mut ready: BTreeSet<usize> = (0..len).filter(|&i| in_degree[i] == 0).collect();
+    let mut order = Vec::with_capacity(len);
+    while let Some(index) = ready.pop_first() {
+        order.push(index);
+        for &(from, to) in edges {
+            if from == index && from != to {
+                in_degree[to] -= 1;
+                if in_degree[to] == 0 {
+                    ready.insert(to);
+                }
+            }
+        }
+    }
+    let mut remaining: Vec<usize> = (0..len).filter(|i| !order.contains(i)).collect();
+    order.append(&mut remaining);
+    order
+}
@@ -0,0 +1,50 @@
+use std::collections::HashMap;
+
+type TaskId = usize;
+
+/// 机器人租约表，记录每个机器人的指令权当前归属于哪个任务
+/// 同一时刻只有一个任务可以向机器人下发指令，只读的监控节点不需要租约
+#[derive(Default, Debug)]
+pub struct RobotLeases {
+    holders: HashMap<String, TaskId>,
+}
+
+impl RobotLeases {
+    /// 当前持有机器人指令权的任务
+    pub fn holder(&self, robot: &str) -> Option<TaskId> {
+        self.holders.get(robot).copied()
+    }
+
+    /// 机器人是否均未被其他任务占用
+    pub fn is_available(&self, task_id: TaskId, robots: &[String]) -> bool {
+        robots
+            .iter()
+            .all(|robot| self.holder(robot).is_none_or(|holder| holder == task_id))
+    }
+
+    /// 为任务获取全部机器人的指令权，只要有一个机器人被其他任务占用就不会获取任何租约
+    pub fn acquire(&mut self, task_id: TaskId, robots: &[String]) -> bool {
+        if !self.is_available(task_id, robots) {
+            return false;
+        }
+        for robot in robots {
+            self.holders.insert(robot.clone(), task_id);
+        }
+        true
+    }
+
+    /// 释放任务持有的全部租约，返回被释放的机器人
+    pub fn release(&mut self, task_id: TaskId) -> Vec<String> {
+        let mut released: Vec<String> = self
+            .holders
+            .iter()
+            .filter(|(_, &holder)| holder == task_id)
+            .map(|(robot, _)| robot.clone())
+            .collect();
+        released.sort_unstable();
+        for robot in &released {
+            self.holders.remove(robot);
+        }
+        released
+    }
+}
@@ -0,0 +1,163 @@
+use serde::Deserialize;
+use serde_json::Value;
+use std::fmt;
+use std::io;
+use std::time::Duration;
+
+/// 节点线程的调度参数，从任务文件中节点的 params 读取，缺省时使用系统的默认调度
+/// `priority` 为 SCHED_FIFO 优先级（1-99），`cpu_affinity` 为允许运行的 CPU 编号
+#[derive(Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct Schedule {
+    #[serde(default)]
+    pub priority: Option<i32>,
+    #[serde(default)]
+    pub cpu_affinity: Vec<usize>,
+}
+
+impl Schedule {
+    /// 从节点参数中读取调度参数，参数中的其他字段会被忽略
+    pub fn from_params(params: &Value) -> Result<Self, serde_json::Error> {
+        match params {
+            Value::Object(_) => Schedule::deserialize(params),
+            _ => Ok(Schedule::default()),
+        }
+    }
+
+    pub fn is_default(&self) -> bool {
+        self.priority.is_none() && self.cpu_affinity.is_empty()
+    }
+
+    /// 将调度参数应用到当前线程，需要在节点线程内部调用
+    /// 设置 SCHED_FIFO 通常需要 root 权限或 CAP_SYS_NICE
+    pub fn apply(&self) -> io::Result<()> {
+        if let Some(priority) = self.priority {
+            set_fifo_priority(priority)?;
+        }
+        if !self.cpu_affinity.is_empty() {
+            set_cpu_affinity(&self.cpu_affinity)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn set_fifo_priority(priority: i32) -> io::Result<()> {
+    let param = libc::sched_param {
+        sched_priority: priority,
+    };
+    // SAFETY: pid 0 表示当前线程，param 在调用期间有效
+    match unsafe { libc::sched_setscheduler(0, libc::SCHED_FIFO, &param) } {
+        0 => Ok(()),
+        _ => Err(io::Error::last_os_error()),
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn set_cpu_affinity(cpus: &[usize]) -> io::Result<()> {
+    // SAFETY: cpu_set_t 是普通的位图，全零即空集合
+    let mut set: libc::cpu_set_t = unsafe { std::mem::zeroed() };
+    for &cpu in cpus {
+        if cpu >= libc::CPU_SETSIZE as usize {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("cpu {} is out of range", cpu),
+            ));
+        }
+        // SAFETY: CPU_SET 只写入 set 内部的位
+        unsafe { libc::CPU_SET(cpu, &mut set) };
+    }
+    // SAFETY: pid 0 表示当前线程，set 在调用期间有效
+    match unsafe { libc::sched_setaffinity(0, std::mem::size_of::<libc::cpu_set_t>(), &set) } {
+        0 => Ok(()),
+        _ => Err(io::Error::last_os_error()),
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+fn set_fifo_priority(_priority: i32) -> io::Result<()> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "SCHED_FIFO is only supported on linux",
+    ))
+}
+
+#[cfg(not(target_os = "linux"))]
+fn set_cpu_affinity(_cpus: &[usize]) -> io::Result<()> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "cpu affinity is only supported on linux",
+    ))
+}
+
+/// 抖动直方图各个区间的上界，最后一个区间收集超出所有上界的抖动
+pub const JITTER_BOUNDS: [Duration; 6] = [
+    Duration::from_micros(10),
+    Duration::from_micros(50),
+    Duration::from_micros(100),
+    Duration::from_micros(500),
+    Duration::from_millis(1),
+    Duration::from_millis(5),
+];
+
+/// 节点线程的运行统计，抖动为实际开始执行的时刻相对于计划时刻的延迟
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct NodeMetrics {
+    /// 已经执行的周期数
+    pub cycles: u64,
+    /// update 的执行超出周期截止时间的次数
+    pub overruns: u64,
+    /// 因超时而被跳过的周期数
+    pub skipped: u64,
+    /// 最坏执行时间
+    pub wcet: Duration,
+    /// 累计执行时间，用于计算平均执行时间
+    pub total_execution: Duration,
+    /// 最大抖动
+    pub max_jitter: Duration,
+    /// 抖动直方图，第 i 个区间统计小于 JITTER_BOUNDS[i] 的抖动，最后一个区间统计其余的抖动
+    pub jitter_histogram: [u64; JITTER_BOUNDS.len() + 1],
+}
+
+impl NodeMetrics {
+    /// 记录一个周期的抖动与执行时间
+    pub fn record(&mut self, jitter: Duration, execution: Duration) {
+        self.cycles += 1;
+        self.wcet = self.wcet.max(execution);
+        self.total_execution += execution;
+        self.max_jitter = self.max_jitter.max(jitter);
+        let bucket = JITTER_BOUNDS
+            .iter()
+            .position(|&bound| jitter < bound)
+            .unwrap_or(JITTER_BOUNDS.len());
+        self.jitter_histogram[bucket] += 1;
+    }
+
+    /// 记录一次超时以及因此跳过的周期数
+    pub fn record_overrun(&mut self, skipped: u64) {
+        self.overruns += 1;
+        self.skipped += skipped;
+    }
+
+    pub fn mean_execution(&self) -> Duration {
+        match self.cycles {
+            0 => Duration::ZERO,
+            cycles => Duration::from_secs_f64(self.total_execution.as_secs_f64() / cycles as f64),
+        }
+    }
+}
+
+impl fmt::Display for NodeMetrics {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "cycles {}, overruns {}, skipped {}, wcet {:?}, mean {:?}, max jitter {:?}, jitter histogram {:?}",
+            self.cycles,
+            self.overruns,
+            self.skipped,
+            self.wcet,
+            self.mean_execution(),
+            self.max_jitter,
+            self.jitter_histogram
+        )
+    }
+}
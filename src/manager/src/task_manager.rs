@@ -1,15 +1,28 @@
 use serde::Deserialize;
 use serde_json::Value;
 use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::path::PathBuf;
 use std::sync::mpsc::Receiver;
 use std::sync::{Arc, Mutex};
 
+use crate::{from_file, validate_tasks, Config, FileFormat, ManagerError, RobotLeases, TaskError};
 use message::{Target, TaskState};
+use node::{MessageType, DEFAULT_INPUT, DEFAULT_OUTPUT, SIMULATOR_TYPES};
 
 type TaskId = usize;
 
+/// 节点状态汇报引起的任务变化，两种变化都可能让排队的任务启动，需要重新整理任务
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TaskEvent {
+    /// 任务完成，其后继任务被解锁
+    Completed(TaskId),
+    /// 已完成任务的最后一个节点退出，其机器人租约被释放
+    LeaseReleased(TaskId),
+}
+
 /// 任务管理器，负责任务的调度和依赖关系
-/// TODO 任务执行完成后会通过
+/// 任务执行过程中线程管理器会汇报节点状态，任务管理器据此判断任务是否完成，并只解锁该任务的后继任务
 #[derive(Default)]
 #[allow(dead_code)]
 pub struct TaskManager {
@@ -23,6 +36,16 @@ pub struct TaskManager {
     open_tasks: HashSet<TaskId>,
     /// 执行任务列表，执行新任务时要从开放任务列表中减去执行中的任务列表
     running_tasks: HashSet<TaskId>,
+    /// 完成任务列表，完成的任务已经解锁了其后继任务，但其节点可能仍在运行
+    finished_tasks: HashSet<TaskId>,
+    /// 节点归属表：记录每个节点属于哪个任务
+    node_owner: HashMap<String, TaskId>,
+    /// 每个任务中尚未结束的标志节点，全部结束后任务即完成
+    pending_nodes: HashMap<TaskId, HashSet<String>>,
+    /// 每个任务中仍在运行的节点，任务完成且这些节点全部结束或停止后才释放机器人租约
+    live_nodes: HashMap<TaskId, HashSet<String>>,
+    /// 机器人租约：任务执行期间独占机器人的指令权，与之冲突的任务将排队等待
+    leases: RobotLeases,
 
     /// 与线程管理器通信的接收器
     pub receiver: Option<Arc<Mutex<Receiver<TaskState>>>>,
@@ -34,16 +57,303 @@ pub struct Task {
     pub rely: Vec<TaskId>,
     pub target: Vec<Target>,
 
-    pub nodes: Vec<(String, Vec<String>, Vec<String>, Value)>,
-    pub edges: Vec<(usize, usize)>,
+    pub nodes: Vec<NodeConfig>,
+    pub edges: Vec<(NodeRef, NodeRef)>,
+
+    /// 标志任务完成的节点，与 edges 一样可以使用序号或 id，这些节点全部结束或释放约束后任务即完成
+    /// 缺省时使用接收任务目标的节点，即 edges 中起点为任务目标的节点，若不存在这样的节点则使用全部节点
+    #[serde(default)]
+    pub finish: Vec<NodeRef>,
+}
+
+/// 边的起点，表示该节点接收任务目标，只能出现在边的起点
+pub const TARGET_NODE: &str = "$target";
+/// 边的终点，表示该节点是系统末端，只能出现在边的终点或 finish 中
+pub const END_NODE: &str = "$end";
+
+/// 任务中对节点的引用，可以是从 1 开始的节点序号（0 表示任务目标或系统末端），也可以是节点的 id。
+/// 字符串形式的引用可以写作 "节点.端口" 来指定端口，例如 "cfs.out"、"2.force"，缺省时使用默认端口
+#[derive(Deserialize, Clone, Debug, PartialEq)]
+#[serde(untagged)]
+pub enum NodeRef {
+    Index(usize),
+    Name(String),
+}
+
+impl From<usize> for NodeRef {
+    fn from(index: usize) -> Self {
+        NodeRef::Index(index)
+    }
+}
+
+impl From<&str> for NodeRef {
+    fn from(name: &str) -> Self {
+        NodeRef::Name(name.to_string())
+    }
+}
+
+impl NodeRef {
+    /// 引用中的节点部分，即去掉端口后的序号或 id
+    fn node(&self) -> NodeRef {
+        match self {
+            NodeRef::Index(index) => NodeRef::Index(*index),
+            NodeRef::Name(name) => {
+                let node = name.split_once('.').map_or(name.as_str(), |(node, _)| node);
+                match node.parse() {
+                    Ok(index) => NodeRef::Index(index),
+                    Err(_) => NodeRef::Name(node.to_string()),
+                }
+            }
+        }
+    }
+
+    /// 引用是否为 TARGET_NODE 或 END_NODE 等具名的特殊节点
+    pub(crate) fn is(&self, name: &str) -> bool {
+        self.node() == NodeRef::Name(name.to_string())
+    }
+
+    /// 引用中的端口部分，未指定端口时为 None
+    pub fn port(&self) -> Option<&str> {
+        match self {
+            NodeRef::Index(_) => None,
+            NodeRef::Name(name) => name.split_once('.').map(|(_, port)| port),
+        }
+    }
+}
+
+impl fmt::Display for NodeRef {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            NodeRef::Index(index) => write!(f, "{}", index),
+            NodeRef::Name(name) => write!(f, "{}", name),
+        }
+    }
+}
+
+/// TOML 的顶层必须是表，因此任务列表写作 `[[tasks]]`
+#[derive(Deserialize)]
+struct TomlTasks {
+    tasks: Vec<Task>,
+}
+
+/// 任务中的节点配置，既可以写作具名字段 `{type, robots, sensors, params, id, readonly, message}`，
+/// 也可以写作旧版的元组 `[type, robots, sensors, params]`
+#[derive(Deserialize, Default, Clone, Debug, PartialEq)]
+#[serde(from = "NodeConfigRepr")]
+pub struct NodeConfig {
+    pub node_type: String,
+    pub robots: Vec<String>,
+    pub sensors: Vec<String>,
+    pub params: Value,
+    /// 节点在任务中的名称，可选
+    pub id: Option<String>,
+    /// 只读节点仅读取机器人状态而不下发指令，不需要获取机器人的租约
+    pub readonly: bool,
+    /// 节点之间传递的消息类型，缺省为 dvector
+    pub message: MessageType,
+}
+
+#[derive(Deserialize)]
+#[serde(
+    untagged,
+    expecting = "a node as [type, robots, sensors, params] or {type, robots, sensors, params, ...}"
+)]
+enum NodeConfigRepr {
+    Tuple(String, Vec<String>, Vec<String>, Value),
+    Named {
+        #[serde(rename = "type")]
+        node_type: String,
+        #[serde(default)]
+        robots: Vec<String>,
+        #[serde(default)]
+        sensors: Vec<String>,
+        #[serde(default)]
+        params: Value,
+        #[serde(default)]
+        id: Option<String>,
+        #[serde(default)]
+        readonly: bool,
+        #[serde(default)]
+        message: MessageType,
+    },
+}
+
+impl NodeConfig {
+    /// 节点是否需要获取其机器人的租约。只读节点不下发指令；仿真器虽然写入机器人状态，
+    /// 但只是代替真实机器人执行其他节点的指令，与真实机器人一样不参与指令权的分配
+    pub fn needs_lease(&self) -> bool {
+        !self.readonly && !SIMULATOR_TYPES.contains(&self.node_type.as_str())
+    }
+}
+
+impl From<NodeConfigRepr> for NodeConfig {
+    fn from(repr: NodeConfigRepr) -> Self {
+        match repr {
+            NodeConfigRepr::Tuple(node_type, robots, sensors, params) => NodeConfig {
+                node_type,
+                robots,
+                sensors,
+                params,
+                id: None,
+                readonly: false,
+                message: MessageType::default(),
+            },
+            NodeConfigRepr::Named {
+                node_type,
+                robots,
+                sensors,
+                params,
+                id,
+                readonly,
+                message,
+            } => NodeConfig {
+                node_type,
+                robots,
+                sensors,
+                params,
+                id,
+                readonly,
+                message,
+            },
+        }
+    }
+}
+
+impl Task {
+    /// 将节点引用解析为从 1 开始的节点序号，0 表示任务目标或系统末端
+    pub fn resolve(&self, node: &NodeRef) -> Result<usize, TaskError> {
+        match node.node() {
+            NodeRef::Index(index) => Ok(index),
+            NodeRef::Name(name) if name == TARGET_NODE || name == END_NODE => Ok(0),
+            NodeRef::Name(name) => self
+                .nodes
+                .iter()
+                .position(|node| node.id.as_deref() == Some(name.as_str()))
+                .map(|index| index + 1)
+                .ok_or(TaskError::UnknownNode {
+                    task: self.id,
+                    node: name,
+                }),
+        }
+    }
+
+    /// 将边解析为节点序号，并检查序号是否超出节点数量。
+    /// 二者都解析为 0，因此任务目标只能作为边的起点，系统末端只能作为边的终点
+    pub fn resolve_edge(&self, edge: &(NodeRef, NodeRef)) -> Result<(usize, usize), TaskError> {
+        for (node, misplaced) in [(&edge.0, END_NODE), (&edge.1, TARGET_NODE)] {
+            if node.is(misplaced) {
+                return Err(TaskError::MisplacedNode {
+                    task: self.id,
+                    node: misplaced.to_string(),
+                });
+            }
+        }
+        let resolved = (self.resolve(&edge.0)?, self.resolve(&edge.1)?);
+        if resolved.0 > self.nodes.len() || resolved.1 > self.nodes.len() {
+            return Err(TaskError::EdgeOutOfRange {
+                task: self.id,
+                edge: resolved,
+                nodes: self.nodes.len(),
+            });
+        }
+        Ok(resolved)
+    }
+
+    /// 边两端使用的端口，起点缺省为输出端口 "out"，终点缺省为输入端口 "in"
+    pub fn edge_ports<'a>(&self, edge: &'a (NodeRef, NodeRef)) -> (&'a str, &'a str) {
+        (
+            edge.0.port().unwrap_or(DEFAULT_OUTPUT),
+            edge.1.port().unwrap_or(DEFAULT_INPUT),
+        )
+    }
+
+    /// 将所有的边解析为节点序号，遇到第一个错误时返回
+    pub fn resolve_edges(&self) -> Result<Vec<(usize, usize)>, TaskError> {
+        self.edges
+            .iter()
+            .map(|edge| self.resolve_edge(edge))
+            .collect()
+    }
+
+    /// 节点名称中机器人的部分，附带任务 id 与从 1 开始的节点序号，
+    /// 使不同任务中同类型、同机器人的节点名称互不相同，任务管理器按名称判断节点归属
+    pub fn node_label(&self, index: usize) -> String {
+        format!(
+            "{}@{}.{}",
+            self.nodes[index].robots.join("+"),
+            self.id,
+            index + 1
+        )
+    }
+
+    /// 任务执行期间需要独占指令权的机器人，即所有需要租约的节点使用的机器人
+    pub fn leased_robots(&self) -> Vec<String> {
+        let mut robots: Vec<String> = self
+            .nodes
+            .iter()
+            .filter(|node| node.needs_lease())
+            .flat_map(|node| node.robots.iter().cloned())
+            .collect();
+        robots.sort_unstable();
+        robots.dedup();
+        robots
+    }
+
+    /// 连接到系统末端的节点在 nodes 中的下标
+    pub fn end_nodes(&self) -> Vec<usize> {
+        self.edges
+            .iter()
+            .filter_map(|edge| self.resolve_edge(edge).ok())
+            .filter(|edge| edge.0 != 0 && edge.1 == 0)
+            .map(|edge| edge.0 - 1)
+            .collect()
+    }
+
+    /// 标志任务完成的节点在 nodes 中的下标，finish 中的系统末端表示连接到系统末端的节点，
+    /// 无法解析的节点引用会被忽略
+    pub fn finish_nodes(&self) -> Vec<usize> {
+        if !self.finish.is_empty() {
+            let mut finish_nodes = Vec::new();
+            for node in &self.finish {
+                if node.is(END_NODE) {
+                    finish_nodes.extend(self.end_nodes());
+                } else if let Ok(i) = self.resolve(node) {
+                    finish_nodes.extend(i.checked_sub(1));
+                }
+            }
+            finish_nodes.sort_unstable();
+            finish_nodes.dedup();
+            return finish_nodes;
+        }
+        let target_nodes: Vec<usize> = self
+            .edges
+            .iter()
+            .filter_map(|edge| self.resolve_edge(edge).ok())
+            .filter(|edge| edge.0 == 0 && edge.1 != 0)
+            .map(|edge| edge.1 - 1)
+            .collect();
+        if !target_nodes.is_empty() {
+            return target_nodes;
+        }
+        (0..self.nodes.len()).collect()
+    }
 }
 
 impl TaskManager {
-    /// 从 JSON 文件读取任务并构建 DAG
-    pub fn from_json(receiver: Receiver<TaskState>, file_path: &str) -> Self {
-        let file_content = std::fs::read_to_string(file_path).expect("Failed to read JSON file");
-        let task_list: Vec<Task> =
-            serde_json::from_str(&file_content).expect("Invalid JSON format");
+    /// 从 JSON、YAML 或 TOML 文件读取任务并构建 DAG，构建前会对照配置文件校验任务，返回发现的全部错误
+    pub fn from_file(
+        receiver: Receiver<TaskState>,
+        file_path: &str,
+        config: &Config,
+    ) -> Result<Self, ManagerError> {
+        let path = PathBuf::from(file_path);
+        let task_list: Vec<Task> = match FileFormat::from_path(&path) {
+            Some(FileFormat::Toml) => from_file::<TomlTasks>(file_path)?.tasks,
+            _ => from_file(file_path)?,
+        };
+        if let Err(errors) = validate_tasks(&task_list, config) {
+            return Err(ManagerError::InvalidTasks { path, errors });
+        }
 
         let mut task_manager = TaskManager {
             receiver: Some(Arc::new(Mutex::new(receiver))),
@@ -52,7 +362,7 @@ impl TaskManager {
         for task in task_list {
             task_manager.add_task(task);
         }
-        task_manager
+        Ok(task_manager)
     }
 
     /// 添加任务并处理其依赖关系
@@ -76,19 +386,193 @@ impl TaskManager {
         }
     }
 
-    /// 获取所有入度为 0 的任务
+    /// 获取所有任务，按照任务 id 排序
+    pub fn tasks(&self) -> Vec<&Task> {
+        let mut tasks: Vec<&Task> = self.tasks.values().collect();
+        tasks.sort_by_key(|task| task.id);
+        tasks
+    }
+
+    /// 不启动任何线程，按照依赖关系推演任务的执行顺序，返回每一阶段中可以并行执行的任务
+    /// 未出现在任何阶段中的任务说明其依赖永远无法被满足
+    pub fn stages(&self) -> Vec<Vec<TaskId>> {
+        let mut in_degree = self.in_degree.clone();
+        let mut stage: Vec<TaskId> = in_degree
+            .iter()
+            .filter(|(_, &degree)| degree == 0)
+            .map(|(&id, _)| id)
+            .collect();
+
+        let mut stages = Vec::new();
+        while !stage.is_empty() {
+            stage.sort_unstable();
+            let mut next_stage = Vec::new();
+            for id in &stage {
+                for neighbor in self.adj_list.get(id).into_iter().flatten() {
+                    if let Some(degree) = in_degree.get_mut(neighbor) {
+                        *degree -= 1;
+                        if *degree == 0 {
+                            next_stage.push(*neighbor);
+                        }
+                    }
+                }
+            }
+            stages.push(stage);
+            stage = next_stage;
+        }
+        stages
+    }
+
+    /// 获取所有入度为 0 且能够获取机器人租约的任务，按照任务 id 依次分配租约
+    /// 机器人被其他任务占用的任务留在开放列表中排队，待占用的任务完成后再次尝试
     pub fn get_open_tasks(&mut self) -> Vec<Task> {
-        self.open_tasks
+        let mut open_tasks: Vec<TaskId> = self
+            .open_tasks
+            .difference(&self.running_tasks)
+            .copied()
+            .collect();
+        open_tasks.sort_unstable();
+
+        let mut tasks = Vec::new();
+        for id in open_tasks {
+            let task = &self.tasks[&id];
+            if self.leases.acquire(id, &task.leased_robots()) {
+                self.running_tasks.insert(id);
+                tasks.push(task.clone());
+            }
+        }
+        tasks
+    }
+
+    /// 当前持有机器人指令权的任务
+    pub fn lease_holder(&self, robot: &str) -> Option<TaskId> {
+        self.leases.holder(robot)
+    }
+
+    /// 登记任务所创建的节点，designated 为 true 的节点全部结束或释放约束后任务才算完成
+    pub fn register_node(&mut self, task_id: TaskId, node_name: String, designated: bool) {
+        if designated {
+            self.pending_nodes
+                .entry(task_id)
+                .or_default()
+                .insert(node_name.clone());
+        }
+        self.live_nodes
+            .entry(task_id)
+            .or_default()
+            .insert(node_name.clone());
+        self.node_owner.insert(node_name, task_id);
+    }
+
+    /// 处理线程管理器汇报的节点状态，返回因此完成的任务或者因此释放的租约
+    /// 结束或停止的节点不再下发指令，任务完成后其最后一个节点退出时释放机器人租约
+    pub fn handle_task_state(&mut self, task_state: TaskState) -> Option<TaskEvent> {
+        let (name, exited) = match task_state {
+            TaskState::RelyRelease(name)
+            | TaskState::PlanEnd(name)
+            | TaskState::ControlEnd(name) => (name, false),
+            TaskState::NodeEnd(name) => (name, true),
+            TaskState::NodeStop(name) => {
+                let task_id = *self.node_owner.get(&name)?;
+                return self
+                    .exit_node(task_id, &name)
+                    .then_some(TaskEvent::LeaseReleased(task_id));
+            }
+        };
+
+        let task_id = *self.node_owner.get(&name)?;
+        // 已完成任务的节点结束时只可能释放租约
+        let released =
+            (exited && self.exit_node(task_id, &name)).then_some(TaskEvent::LeaseReleased(task_id));
+        let Some(pending) = self.pending_nodes.get_mut(&task_id) else {
+            return released;
+        };
+        pending.remove(&name);
+        if !pending.is_empty() {
+            return released;
+        }
+
+        self.pending_nodes.remove(&task_id);
+        self.complete_task(task_id);
+        Some(TaskEvent::Completed(task_id))
+    }
+
+    /// 需要让出机器人指令权的节点：已完成的任务中仍在运行、且其租约正被排队任务等待的节点
+    /// 永不结束的节点（例如持续跟踪轨迹的控制器）在任务完成后继续控制机器人，
+    /// 直到有其他任务需要同一机器人时才被停止，停止后释放租约
+    pub fn preempted_nodes(&self) -> Vec<String> {
+        let mut waiting: Vec<TaskId> = self
+            .open_tasks
+            .difference(&self.running_tasks)
+            .copied()
+            .collect();
+        waiting.sort_unstable();
+
+        let mut holders: Vec<TaskId> = waiting
             .iter()
-            .filter_map(|id| {
-                if self.running_tasks.contains(id) {
-                    None
-                } else {
-                    self.running_tasks.insert(*id);
-                    Some(self.tasks.get(id).unwrap().clone())
+            .flat_map(|id| self.tasks[id].leased_robots())
+            .filter_map(|robot| self.leases.holder(&robot))
+            .filter(|holder| self.finished_tasks.contains(holder))
+            .collect();
+        holders.sort_unstable();
+        holders.dedup();
+
+        let mut nodes: Vec<String> = holders
+            .iter()
+            .filter_map(|holder| self.live_nodes.get(holder))
+            .flatten()
+            .cloned()
+            .collect();
+        nodes.sort_unstable();
+        nodes
+    }
+
+    /// 节点退出，已完成任务的节点全部退出后释放该任务的机器人租约，返回是否释放了租约
+    fn exit_node(&mut self, task_id: TaskId, name: &str) -> bool {
+        if let Some(live) = self.live_nodes.get_mut(&task_id) {
+            live.remove(name);
+        }
+        self.release_leases(task_id)
+    }
+
+    /// 任务已完成且没有仍在运行的节点时释放其机器人租约，返回是否释放了租约
+    fn release_leases(&mut self, task_id: TaskId) -> bool {
+        if !self.finished_tasks.contains(&task_id)
+            || self
+                .live_nodes
+                .get(&task_id)
+                .is_some_and(|live| !live.is_empty())
+        {
+            return false;
+        }
+        self.live_nodes.remove(&task_id);
+        !self.leases.release(task_id).is_empty()
+    }
+
+    /// 完成任务，只解锁依赖于该任务的后继任务，任务中仍在运行的节点不受影响，
+    /// 机器人租约保留到这些节点全部结束或停止
+    pub fn complete_task(&mut self, task_id: TaskId) {
+        if !self.finished_tasks.insert(task_id) {
+            return;
+        }
+        self.open_tasks.remove(&task_id);
+        self.release_leases(task_id);
+
+        if let Some(neighbors) = self.adj_list.get(&task_id) {
+            for &neighbor in neighbors {
+                if let Some(entry) = self.in_degree.get_mut(&neighbor) {
+                    *entry -= 1;
+                    if *entry == 0 {
+                        self.open_tasks.insert(neighbor);
+                    }
                 }
-            })
-            .collect()
+            }
+        }
+    }
+
+    /// 所有任务是否均已完成
+    pub fn is_all_finished(&self) -> bool {
+        self.tasks.keys().all(|id| self.finished_tasks.contains(id))
     }
 
     /// 删除任务，并更新依赖的入度
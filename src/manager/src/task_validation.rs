@@ -0,0 +1,389 @@
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+use crate::{Config, Task, END_NODE, TARGET_NODE};
+use message::MessageKind;
+use node::{node_ports, MessageType, NodePorts};
+
+type TaskId = usize;
+
+/// 任务文件校验过程中发现的错误，节点序号与 edges 一致从 1 开始计数
+#[derive(Debug, Clone, PartialEq)]
+pub enum TaskError {
+    /// 多个任务使用了同一个 id
+    DuplicateId(TaskId),
+    /// 任务依赖了不存在的任务
+    UnknownRely { task: TaskId, rely: TaskId },
+    /// 任务之间的依赖关系成环，环中的任务按依赖顺序排列
+    Cycle(Vec<TaskId>),
+    /// 边引用的节点序号超出了节点数量，或者边的两端都不是节点，即任务目标直接连接到系统末端
+    EdgeOutOfRange {
+        task: TaskId,
+        edge: (usize, usize),
+        nodes: usize,
+    },
+    /// 边或标志任务完成的节点引用了不存在的节点 id
+    UnknownNode { task: TaskId, node: String },
+    /// 任务目标出现在边的终点或 finish 中，或者系统末端出现在边的起点
+    MisplacedNode { task: TaskId, node: String },
+    /// 同一个任务中的多个节点使用了同一个 id
+    DuplicateNodeId { task: TaskId, id: String },
+    /// 边两端节点的消息类型不一致
+    MessageMismatch {
+        task: TaskId,
+        edge: (usize, usize),
+        from: MessageType,
+        to: MessageType,
+    },
+    /// 边引用了节点类型没有声明的端口
+    UnknownPort {
+        task: TaskId,
+        node: usize,
+        port: String,
+    },
+    /// 边起点输出端口的消息种类不被终点输入端口接受
+    PortMismatch {
+        task: TaskId,
+        edge: (usize, usize),
+        from_port: String,
+        to_port: String,
+    },
+    /// 任务目标的消息种类不被接收目标的输入端口接受
+    TargetMismatch {
+        task: TaskId,
+        node: usize,
+        port: String,
+        kind: MessageKind,
+    },
+    /// 标志任务完成的节点序号超出了节点数量
+    FinishOutOfRange {
+        task: TaskId,
+        finish: usize,
+        nodes: usize,
+    },
+    /// 节点使用了配置文件中不存在的机器人
+    UnknownRobot {
+        task: TaskId,
+        node: usize,
+        robot: String,
+    },
+    /// 节点使用了配置文件中不存在的传感器
+    UnknownSensor {
+        task: TaskId,
+        node: usize,
+        sensor: String,
+    },
+}
+
+impl fmt::Display for TaskError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TaskError::DuplicateId(id) => write!(f, "task id {} is used more than once", id),
+            TaskError::UnknownRely { task, rely } => {
+                write!(f, "task {} relies on unknown task {}", task, rely)
+            }
+            TaskError::Cycle(cycle) => {
+                let cycle: Vec<String> = cycle.iter().map(|id| id.to_string()).collect();
+                write!(f, "task dependencies form a cycle: {}", cycle.join(" -> "))
+            }
+            TaskError::EdgeOutOfRange { task, edge: (0, 0), .. } => write!(
+                f,
+                "task {} edge [0, 0] connects {} directly to {} without any node",
+                task, TARGET_NODE, END_NODE
+            ),
+            TaskError::EdgeOutOfRange { task, edge, nodes } => write!(
+                f,
+                "task {} edge [{}, {}] is out of range, the task only has {} nodes",
+                task, edge.0, edge.1, nodes
+            ),
+            TaskError::UnknownNode { task, node } => {
+                write!(f, "task {} has no node with id {}", task, node)
+            }
+            TaskError::MisplacedNode { task, node } => write!(
+                f,
+                "task {} uses {} in the wrong place, {} can only start an edge and {} can only end an edge or mark the finish",
+                task, node, TARGET_NODE, END_NODE
+            ),
+            TaskError::DuplicateNodeId { task, id } => {
+                write!(f, "task {} node id {} is used more than once", task, id)
+            }
+            TaskError::MessageMismatch {
+                task,
+                edge,
+                from,
+                to,
+            } => write!(
+                f,
+                "task {} edge [{}, {}] connects a {} node to a {} node",
+                task, edge.0, edge.1, from, to
+            ),
+            TaskError::UnknownPort { task, node, port } => {
+                write!(f, "task {} node {} has no port named {}", task, node, port)
+            }
+            TaskError::PortMismatch {
+                task,
+                edge,
+                from_port,
+                to_port,
+            } => write!(
+                f,
+                "task {} edge [{}, {}] connects port {} to port {}, the ports share no message kind",
+                task, edge.0, edge.1, from_port, to_port
+            ),
+            TaskError::TargetMismatch {
+                task,
+                node,
+                port,
+                kind,
+            } => write!(
+                f,
+                "task {} target of kind {} is not accepted by port {} of node {}",
+                task, kind, port, node
+            ),
+            TaskError::FinishOutOfRange {
+                task,
+                finish,
+                nodes,
+            } => write!(
+                f,
+                "task {} finish node {} is out of range, the task only has {} nodes",
+                task, finish, nodes
+            ),
+            TaskError::UnknownRobot { task, node, robot } => write!(
+                f,
+                "task {} node {} uses robot {} which is not in the config",
+                task, node, robot
+            ),
+            TaskError::UnknownSensor { task, node, sensor } => write!(
+                f,
+                "task {} node {} uses sensor {} which is not in the config",
+                task, node, sensor
+            ),
+        }
+    }
+}
+
+impl std::error::Error for TaskError {}
+
+/// 校验任务列表，返回发现的全部错误
+pub fn validate_tasks(tasks: &[Task], config: &Config) -> Result<(), Vec<TaskError>> {
+    let mut errors = Vec::new();
+
+    // 重复的任务 id
+    let mut ids = HashSet::new();
+    let mut duplicated = HashSet::new();
+    for task in tasks {
+        if !ids.insert(task.id) && duplicated.insert(task.id) {
+            errors.push(TaskError::DuplicateId(task.id));
+        }
+    }
+
+    // 不存在的依赖
+    for task in tasks {
+        for &rely in &task.rely {
+            if !ids.contains(&rely) {
+                errors.push(TaskError::UnknownRely {
+                    task: task.id,
+                    rely,
+                });
+            }
+        }
+    }
+
+    // 依赖成环
+    errors.extend(find_cycles(tasks).into_iter().map(TaskError::Cycle));
+
+    // 任务内部的节点与边
+    let robots: HashSet<&str> = config.robots.iter().map(|r| r.name.as_str()).collect();
+    let sensors: HashSet<&str> = config.sensors.iter().map(|s| s.name.as_str()).collect();
+    for task in tasks {
+        let nodes = task.nodes.len();
+        let mut node_ids = HashSet::new();
+        for id in task.nodes.iter().filter_map(|node| node.id.as_ref()) {
+            if !node_ids.insert(id) {
+                errors.push(TaskError::DuplicateNodeId {
+                    task: task.id,
+                    id: id.clone(),
+                });
+            }
+        }
+        for edge in &task.edges {
+            let (from_port, to_port) = task.edge_ports(edge);
+            match task.resolve_edge(edge) {
+                Ok((from, to)) if from != 0 && to != 0 => {
+                    let from_type = task.nodes[from - 1].message;
+                    let to_type = task.nodes[to - 1].message;
+                    if from_type != to_type {
+                        errors.push(TaskError::MessageMismatch {
+                            task: task.id,
+                            edge: (from, to),
+                            from: from_type,
+                            to: to_type,
+                        });
+                        continue;
+                    }
+                    let output = ports(task, from).map(|ports| ports.output(from_port).copied());
+                    let input = ports(task, to).map(|ports| ports.input(to_port).copied());
+                    match (output, input) {
+                        (Some(Some(output)), Some(Some(input))) if !output.compatible(&input) => {
+                            errors.push(TaskError::PortMismatch {
+                                task: task.id,
+                                edge: (from, to),
+                                from_port: from_port.to_string(),
+                                to_port: to_port.to_string(),
+                            })
+                        }
+                        (output, input) => {
+                            if output == Some(None) {
+                                errors.push(unknown_port(task, from, from_port));
+                            }
+                            if input == Some(None) {
+                                errors.push(unknown_port(task, to, to_port));
+                            }
+                        }
+                    }
+                }
+                Ok((0, to)) if to != 0 => {
+                    // 任务目标注入节点的输入端口，每个目标都要被端口接受
+                    match ports(task, to).map(|ports| ports.input(to_port).copied()) {
+                        Some(Some(input)) => {
+                            let rejected = task
+                                .target
+                                .iter()
+                                .find(|target| !input.accepts(target.kind()));
+                            if let Some(target) = rejected {
+                                errors.push(TaskError::TargetMismatch {
+                                    task: task.id,
+                                    node: to,
+                                    port: to_port.to_string(),
+                                    kind: target.kind(),
+                                });
+                            }
+                        }
+                        Some(None) => errors.push(unknown_port(task, to, to_port)),
+                        None => (),
+                    }
+                }
+                Ok((0, 0)) => errors.push(TaskError::EdgeOutOfRange {
+                    task: task.id,
+                    edge: (0, 0),
+                    nodes,
+                }),
+                Ok(_) => (),
+                Err(error) => errors.push(error),
+            }
+        }
+        for finish in &task.finish {
+            if finish.is(TARGET_NODE) {
+                errors.push(TaskError::MisplacedNode {
+                    task: task.id,
+                    node: TARGET_NODE.to_string(),
+                });
+                continue;
+            }
+            if finish.is(END_NODE) {
+                // 没有节点连接到系统末端时，finish 中的系统末端不对应任何节点
+                if task.end_nodes().is_empty() {
+                    errors.push(TaskError::FinishOutOfRange {
+                        task: task.id,
+                        finish: 0,
+                        nodes,
+                    });
+                }
+                continue;
+            }
+            match task.resolve(finish) {
+                Ok(finish) if finish == 0 || finish > nodes => {
+                    errors.push(TaskError::FinishOutOfRange {
+                        task: task.id,
+                        finish,
+                        nodes,
+                    })
+                }
+                Ok(_) => (),
+                Err(error) => errors.push(error),
+            }
+        }
+        for (index, node_config) in task.nodes.iter().enumerate() {
+            for robot in &node_config.robots {
+                if !robots.contains(robot.as_str()) {
+                    errors.push(TaskError::UnknownRobot {
+                        task: task.id,
+                        node: index + 1,
+                        robot: robot.clone(),
+                    });
+                }
+            }
+            for sensor in &node_config.sensors {
+                if !sensors.contains(sensor.as_str()) {
+                    errors.push(TaskError::UnknownSensor {
+                        task: task.id,
+                        node: index + 1,
+                        sensor: sensor.clone(),
+                    });
+                }
+            }
+        }
+    }
+
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(errors)
+    }
+}
+
+/// 节点类型声明的端口，节点类型未注册时返回 None，未注册的类型由创建节点时报告
+fn ports(task: &Task, node: usize) -> Option<NodePorts> {
+    let node_config = &task.nodes[node - 1];
+    node_ports(&node_config.node_type, node_config.message)
+}
+
+fn unknown_port(task: &Task, node: usize, port: &str) -> TaskError {
+    TaskError::UnknownPort {
+        task: task.id,
+        node,
+        port: port.to_string(),
+    }
+}
+
+/// 深度优先搜索依赖关系，返回搜索过程中遇到的所有环
+fn find_cycles(tasks: &[Task]) -> Vec<Vec<TaskId>> {
+    let relies: HashMap<TaskId, &Vec<TaskId>> =
+        tasks.iter().map(|task| (task.id, &task.rely)).collect();
+    let mut ids: Vec<TaskId> = relies.keys().copied().collect();
+    ids.sort_unstable();
+
+    let mut visited = HashSet::new();
+    let mut path = Vec::new();
+    let mut cycles = Vec::new();
+    for id in ids {
+        visit(id, &relies, &mut visited, &mut path, &mut cycles);
+    }
+    cycles
+}
+
+fn visit(
+    id: TaskId,
+    relies: &HashMap<TaskId, &Vec<TaskId>>,
+    visited: &mut HashSet<TaskId>,
+    path: &mut Vec<TaskId>,
+    cycles: &mut Vec<Vec<TaskId>>,
+) {
+    if let Some(start) = path.iter().position(|&i| i == id) {
+        // 回到了当前路径上的任务，说明存在环
+        let mut cycle = path[start..].to_vec();
+        cycle.push(id);
+        cycles.push(cycle);
+        return;
+    }
+    if !visited.insert(id) {
+        return;
+    }
+
+    path.push(id);
+    for &rely in relies.get(&id).into_iter().flat_map(|relies| relies.iter()) {
+        visit(rely, relies, visited, path, cycles);
+    }
+    path.pop();
+}
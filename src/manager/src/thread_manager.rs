@@ -1,19 +1,49 @@
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::mpsc::Sender;
 use std::sync::{Arc, Mutex, RwLock};
 use std::thread;
-use std::time::Instant;
-use tracing::info;
+use std::time::{Duration, Instant};
+use tracing::{info, warn};
 
+use crate::{NodeMetrics, Schedule};
 use message::TaskState;
 use node::NodeBehavior;
 
 #[derive(Default)]
 pub struct ThreadManager {
-    threads: Vec<thread::JoinHandle<()>>,
+    threads: Vec<(String, thread::JoinHandle<()>)>,
 
     /// 与 taskmanager 通信的通道,用于报告任务完成情况
     /// 考虑之后将传递的消息改为枚举类型，或许更加有利于管理
     sender: Option<Sender<TaskState>>,
+
+    /// 停止信号，所有节点线程在每个周期都会检查该信号，置位后节点退出循环并执行 finalize
+    stop: Arc<AtomicBool>,
+
+    /// 每个节点线程的运行统计，线程退出之后仍然保留
+    metrics: Vec<(String, Arc<Mutex<NodeMetrics>>)>,
+
+    /// 每个节点各自的停止信号，用于单独停止某个节点而不影响其他节点
+    node_stops: HashMap<String, Arc<AtomicBool>>,
+}
+
+/// 线程回收报告，记录每个线程的退出情况
+#[derive(Debug, Default)]
+pub struct JoinReport {
+    /// 正常退出的线程
+    pub finished: Vec<String>,
+    /// 发生 panic 的线程
+    pub panicked: Vec<String>,
+    /// 超时仍未退出的线程，这些线程将被放弃
+    pub timeout: Vec<String>,
+}
+
+impl JoinReport {
+    /// 所有线程都正常退出
+    pub fn is_clean(&self) -> bool {
+        self.panicked.is_empty() && self.timeout.is_empty()
+    }
 }
 
 impl ThreadManager {
@@ -22,9 +52,76 @@ impl ThreadManager {
         ThreadManager {
             threads: Vec::new(),
             sender: Some(sender),
+            stop: Arc::new(AtomicBool::new(false)),
+            metrics: Vec::new(),
+            node_stops: HashMap::new(),
+        }
+    }
+
+    /// 所有节点线程当前的运行统计
+    pub fn metrics(&self) -> Vec<(String, NodeMetrics)> {
+        self.metrics
+            .iter()
+            .map(|(name, metrics)| (name.clone(), metrics.lock().unwrap().clone()))
+            .collect()
+    }
+
+    fn new_metrics(&mut self, name: &str) -> Arc<Mutex<NodeMetrics>> {
+        let metrics = Arc::new(Mutex::new(NodeMetrics::default()));
+        self.metrics.push((name.to_string(), metrics.clone()));
+        metrics
+    }
+
+    fn new_node_stop(&mut self, name: &str) -> Arc<AtomicBool> {
+        let node_stop = Arc::new(AtomicBool::new(false));
+        self.node_stops.insert(name.to_string(), node_stop.clone());
+        node_stop
+    }
+
+    /// 获取停止信号，可以交给信号处理函数等外部调用者
+    pub fn stop_signal(&self) -> Arc<AtomicBool> {
+        self.stop.clone()
+    }
+
+    /// 通知所有节点停止
+    pub fn stop_all(&self) {
+        self.stop.store(true, Ordering::Release);
+    }
+
+    /// 通知单个节点停止，节点退出后与收到全局停止信号一样汇报 NodeStop，不存在的节点会被忽略
+    pub fn stop_node(&self, name: &str) {
+        if let Some(node_stop) = self.node_stops.get(name) {
+            node_stop.store(true, Ordering::Release);
         }
     }
 
+    /// 是否已经收到停止信号
+    pub fn is_stopping(&self) -> bool {
+        self.stop.load(Ordering::Acquire)
+    }
+
+    /// 在超时时间内等待所有线程退出，并报告每个线程的退出情况
+    /// 超时仍未退出的线程不会被强制结束，只会被记录下来
+    pub fn join_all(&mut self, timeout: Duration) -> JoinReport {
+        let deadline = Instant::now() + timeout;
+        while Instant::now() < deadline && self.threads.iter().any(|(_, t)| !t.is_finished()) {
+            thread::sleep(Duration::from_millis(10));
+        }
+
+        let mut report = JoinReport::default();
+        for (name, thread) in self.threads.drain(..) {
+            if !thread.is_finished() {
+                report.timeout.push(name);
+                continue;
+            }
+            match thread.join() {
+                Ok(_) => report.finished.push(name),
+                Err(_) => report.panicked.push(name),
+            }
+        }
+        report
+    }
+
     // 最自由的线程管理方式，不受线程管理器的内部管理，只是存在一个无穷长声明的线程罢了
     pub fn add_closure<F>(&mut self, closure: F)
     where
@@ -33,47 +130,33 @@ impl ThreadManager {
         let thread = thread::spawn(move || {
             closure();
         });
-        self.threads.push(thread);
+        self.threads.push((String::from("closure"), thread));
     }
 
     /// 为节点开辟线程，节点符合 node 规范，是可以被线程管理器管理的线程
     /// 此时的 node 是裸漏的，不具备任何多线程能力，扔进线程之后不可被外部访问
     /// 我们要组一辈子的线程啊
     pub fn add_node(&mut self, node: Box<dyn NodeBehavior>) {
+        self.add_scheduled_node(node, Schedule::default());
+    }
+
+    /// 为节点开辟线程，并在线程中按照调度参数设置 SCHED_FIFO 优先级与 CPU 亲和性
+    pub fn add_scheduled_node(&mut self, node: Box<dyn NodeBehavior>, schedule: Schedule) {
         let name = node.node_name();
         let sender = self.sender.clone().unwrap();
+        let stop = self.stop.clone();
+        let node_stop = self.new_node_stop(&name);
+        let metrics = self.new_metrics(&name);
+        let thread_name = name.clone();
         let thread = thread::Builder::new()
             .name(name.clone())
             .spawn(move || {
                 let mut node = node; // 将 node 声明为可变的
-                println!("{} 向您问好. {} says hello.", name, name);
-                node.init();
-
-                let period = node.period();
-
-                while node.state() != node::NodeState::Finished {
-                    info!(node = name.as_str(), begin = name.as_str());
-                    if node::NodeState::RelyRelease == node.state() {
-                        sender.send(TaskState::RelyRelease(name.clone())).unwrap();
-                    }
-
-                    let start_time = Instant::now();
-
-                    node.update();
-
-                    info!(node = name.as_str(), end = name.as_str());
-                    let elapsed_time = start_time.elapsed();
-                    if period > elapsed_time {
-                        thread::sleep(period - elapsed_time);
-                    }
-                }
-                node.finalize();
-                if "planner" == node.node_type().as_str() {
-                    sender.send(TaskState::PlanEnd(name)).unwrap();
-                }
+                apply_schedule(&schedule, &name);
+                spin(&mut *node, &name, &sender, [&stop, &node_stop], &metrics);
             })
             .unwrap();
-        self.threads.push(thread);
+        self.threads.push((thread_name, thread));
     }
 
     pub fn add_mutex_node(&mut self, node: Arc<Mutex<dyn NodeBehavior>>) {
@@ -83,34 +166,18 @@ impl ThreadManager {
 
         let node = node.clone();
         let sender = self.sender.clone().unwrap();
+        let stop = self.stop.clone();
+        let node_stop = self.new_node_stop(&name);
+        let metrics = self.new_metrics(&name);
+        let thread_name = name.clone();
         let thread = thread::Builder::new()
             .name(name.clone())
             .spawn(move || {
                 let mut node = node.lock().unwrap();
-                println!("{} 向您问好. {} says hello.", name, name);
-                node.init();
-
-                let period = node.period();
-
-                while node.state() != node::NodeState::Finished {
-                    info!(node = name.as_str(), begin = name.as_str());
-                    let start_time = Instant::now();
-
-                    node.update();
-
-                    info!(node = name.as_str(), end = name.as_str());
-                    let elapsed_time = start_time.elapsed();
-                    if period > elapsed_time {
-                        thread::sleep(period - elapsed_time);
-                    }
-                }
-                node.finalize();
-                if "planner" == node.node_type().as_str() {
-                    sender.send(TaskState::PlanEnd(name)).unwrap();
-                }
+                spin(&mut *node, &name, &sender, [&stop, &node_stop], &metrics);
             })
             .unwrap();
-        self.threads.push(thread);
+        self.threads.push((thread_name, thread));
     }
 
     pub fn add_rwlock_node(&mut self, node: Arc<RwLock<dyn NodeBehavior>>) {
@@ -120,33 +187,98 @@ impl ThreadManager {
 
         let node = node.clone();
         let sender = self.sender.clone().unwrap();
+        let stop = self.stop.clone();
+        let node_stop = self.new_node_stop(&name);
+        let metrics = self.new_metrics(&name);
+        let thread_name = name.clone();
         let thread = thread::Builder::new()
             .name(name.clone())
             .spawn(move || {
                 let mut node = node.write().unwrap();
-                println!("{} 向您问好. {} says hello.", name, name);
-                node.init();
-
-                let period = node.period();
-
-                while node.state() != node::NodeState::Finished {
-                    info!(node = name.as_str(), begin = name.as_str());
-                    let start_time = Instant::now();
-
-                    node.update();
-
-                    info!(node = name.as_str(), end = name.as_str());
-                    let elapsed_time = start_time.elapsed();
-                    if period > elapsed_time {
-                        thread::sleep(period - elapsed_time);
-                    }
-                }
-                node.finalize();
-                if "planner" == node.node_type().as_str() {
-                    sender.send(TaskState::PlanEnd(name)).unwrap();
-                }
+                spin(&mut *node, &name, &sender, [&stop, &node_stop], &metrics);
             })
             .unwrap();
-        self.threads.push(thread);
+        self.threads.push((thread_name, thread));
+    }
+}
+
+fn apply_schedule(schedule: &Schedule, name: &str) {
+    if schedule.is_default() {
+        return;
+    }
+    match schedule.apply() {
+        Ok(()) => info!(node = name, schedule = ?schedule),
+        Err(error) => {
+            warn!(node = name, schedule = ?schedule, "failed to apply schedule: {}", error)
+        }
+    }
+}
+
+/// 节点线程的主循环，节点结束或者收到全局或自身的停止信号时退出，退出前一定会执行 finalize
+/// 每个周期的截止时间按照绝对时刻推进，不会因为执行时间而漂移。update 超出截止时间时记录一次超时，
+/// 并跳过已经错过的周期，从下一个尚未到来的周期重新对齐
+fn spin(
+    node: &mut dyn NodeBehavior,
+    name: &str,
+    sender: &Sender<TaskState>,
+    stops: [&AtomicBool; 2],
+    metrics: &Mutex<NodeMetrics>,
+) {
+    println!("{} 向您问好. {} says hello.", name, name);
+    node.init();
+
+    let period = node.period();
+    let mut next_tick = Instant::now();
+    // 释放约束只汇报一次，避免之后的周期重复汇报
+    let mut released = false;
+
+    let is_stopping = || stops.iter().any(|stop| stop.load(Ordering::Acquire));
+    while node.state() != node::NodeState::Finished && !is_stopping() {
+        info!(node = name, begin = name);
+        if !released && node::NodeState::RelyRelease == node.state() {
+            released = true;
+            sender
+                .send(TaskState::RelyRelease(name.to_string()))
+                .unwrap_or_default();
+        }
+
+        let start_time = Instant::now();
+        let jitter = start_time.saturating_duration_since(next_tick);
+
+        node.update();
+
+        info!(node = name, end = name);
+        let execution = start_time.elapsed();
+        let mut metrics_lock = metrics.lock().unwrap();
+        metrics_lock.record(jitter, execution);
+
+        next_tick += period;
+        let now = Instant::now();
+        if period.is_zero() {
+            // 没有周期的节点连续执行，不统计超时
+            next_tick = now;
+        } else if now > next_tick {
+            let skipped = ((now - next_tick).as_nanos() / period.as_nanos()) as u64 + 1;
+            metrics_lock.record_overrun(skipped);
+            warn!(node = name, overrun = ?(now - next_tick), execution = ?execution, skipped);
+            next_tick += period * skipped as u32;
+        }
+        drop(metrics_lock);
+        thread::sleep(next_tick.saturating_duration_since(Instant::now()));
+    }
+    node.finalize();
+    info!(node = name, metrics = %metrics.lock().unwrap());
+    if "planner" == node.node_type().as_str() {
+        sender
+            .send(TaskState::PlanEnd(name.to_string()))
+            .unwrap_or_default();
     }
+    // 自然结束的节点汇报 NodeEnd，收到停止信号而退出的节点汇报 NodeStop，后者不算完成任务
+    let task_state = if node.state() == node::NodeState::Finished {
+        TaskState::NodeEnd(name.to_string())
+    } else {
+        TaskState::NodeStop(name.to_string())
+    };
+    sender.send(task_state).unwrap_or_default();
+    println!("{} 向您道别. {} says goodbye.", name, name);
 }
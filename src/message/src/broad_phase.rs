@@ -0,0 +1,277 @@
+use nalgebra as na;
+
+/// 动态包围盒树中叶节点包围盒默认的扩张量
+pub const AABB_TREE_MARGIN: f64 = 0.05;
+
+/// 世界坐标系下的轴对齐包围盒
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Aabb {
+    pub mins: na::Point3<f64>,
+    pub maxs: na::Point3<f64>,
+}
+
+impl Aabb {
+    pub fn new(mins: na::Point3<f64>, maxs: na::Point3<f64>) -> Aabb {
+        Aabb { mins, maxs }
+    }
+
+    /// 同时包含两个包围盒的最小包围盒
+    pub fn merge(&self, other: &Aabb) -> Aabb {
+        Aabb::new(self.mins.inf(&other.mins), self.maxs.sup(&other.maxs))
+    }
+
+    /// 各方向向外扩张 margin
+    pub fn loosened(&self, margin: f64) -> Aabb {
+        let margin = na::Vector3::repeat(margin);
+        Aabb::new(self.mins - margin, self.maxs + margin)
+    }
+
+    pub fn contains(&self, other: &Aabb) -> bool {
+        (0..3).all(|i| self.mins[i] <= other.mins[i] && self.maxs[i] >= other.maxs[i])
+    }
+
+    /// 两个包围盒之间的距离，相交时为 0
+    pub fn distance(&self, other: &Aabb) -> f64 {
+        (other.mins - self.maxs)
+            .sup(&(self.mins - other.maxs))
+            .sup(&na::Vector3::zeros())
+            .norm()
+    }
+
+    /// 射线 origin + t * dir 进入包围盒时的 t，起点在包围盒内时为 0，
+    /// 在 [0, max_toi] 内不相交时返回 None
+    pub fn cast_ray(
+        &self,
+        origin: &na::Point3<f64>,
+        dir: &na::Vector3<f64>,
+        max_toi: f64,
+    ) -> Option<f64> {
+        let (mut t_min, mut t_max) = (0.0f64, max_toi);
+        for i in 0..3 {
+            if dir[i].abs() < f64::EPSILON {
+                if origin[i] < self.mins[i] || origin[i] > self.maxs[i] {
+                    return None;
+                }
+            } else {
+                let t1 = (self.mins[i] - origin[i]) / dir[i];
+                let t2 = (self.maxs[i] - origin[i]) / dir[i];
+                t_min = t_min.max(t1.min(t2));
+                t_max = t_max.min(t1.max(t2));
+                if t_min > t_max {
+                    return None;
+                }
+            }
+        }
+        Some(t_min)
+    }
+
+    /// 表面积的一半，插入时以其增量作为代价
+    fn half_area(&self) -> f64 {
+        let extent = self.maxs - self.mins;
+        extent.x * extent.y + extent.y * extent.z + extent.z * extent.x
+    }
+}
+
+/// 动态包围盒树。叶节点保存向外扩张 margin 后的包围盒，
+/// 物体小幅移动时仍在扩张后的包围盒内，无需调整树结构
+#[derive(Debug, Clone)]
+pub struct AabbTree {
+    nodes: Vec<TreeNode>,
+    free: Vec<usize>,
+    root: Option<usize>,
+    margin: f64,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct TreeNode {
+    aabb: Aabb,
+    parent: Option<usize>,
+    kind: NodeKind,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum NodeKind {
+    /// 叶节点，保存插入时的键
+    Leaf(usize),
+    Branch(usize, usize),
+}
+
+impl Default for AabbTree {
+    fn default() -> Self {
+        AabbTree::new(AABB_TREE_MARGIN)
+    }
+}
+
+impl AabbTree {
+    pub fn new(margin: f64) -> AabbTree {
+        AabbTree {
+            nodes: Vec::new(),
+            free: Vec::new(),
+            root: None,
+            margin,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.root.is_none()
+    }
+
+    /// 插入键为 key 的包围盒，返回叶节点编号，用于之后的更新与删除
+    pub fn insert(&mut self, key: usize, aabb: Aabb) -> usize {
+        let leaf = self.allocate(TreeNode {
+            aabb: aabb.loosened(self.margin),
+            parent: None,
+            kind: NodeKind::Leaf(key),
+        });
+        self.insert_leaf(leaf);
+        leaf
+    }
+
+    /// 删除叶节点，之后该编号可能被新插入的节点复用
+    pub fn remove(&mut self, leaf: usize) {
+        self.remove_leaf(leaf);
+        self.free.push(leaf);
+    }
+
+    /// 更新叶节点的包围盒，仍在扩张后的包围盒内时不做调整，返回是否重新插入
+    pub fn update(&mut self, leaf: usize, aabb: Aabb) -> bool {
+        if self.nodes[leaf].aabb.contains(&aabb) {
+            return false;
+        }
+        self.remove_leaf(leaf);
+        self.nodes[leaf].aabb = aabb.loosened(self.margin);
+        self.insert_leaf(leaf);
+        true
+    }
+
+    /// 扩张后的包围盒与 aabb 距离不超过 max_distance 的叶节点的键
+    pub fn query_distance(&self, aabb: &Aabb, max_distance: f64) -> Vec<usize> {
+        let mut keys = Vec::new();
+        let mut stack: Vec<usize> = self.root.into_iter().collect();
+        while let Some(index) = stack.pop() {
+            let node = &self.nodes[index];
+            if node.aabb.distance(aabb) > max_distance {
+                continue;
+            }
+            match node.kind {
+                NodeKind::Leaf(key) => keys.push(key),
+                NodeKind::Branch(left, right) => stack.extend([left, right]),
+            }
+        }
+        keys
+    }
+
+    /// 与射线相交的叶节点的键以及进入其包围盒时的 t，按 t 从小到大排列
+    pub fn cast_ray(
+        &self,
+        origin: &na::Point3<f64>,
+        dir: &na::Vector3<f64>,
+        max_toi: f64,
+    ) -> Vec<(usize, f64)> {
+        let mut hits = Vec::new();
+        let mut stack: Vec<usize> = self.root.into_iter().collect();
+        while let Some(index) = stack.pop() {
+            let node = &self.nodes[index];
+            let Some(toi) = node.aabb.cast_ray(origin, dir, max_toi) else {
+                continue;
+            };
+            match node.kind {
+                NodeKind::Leaf(key) => hits.push((key, toi)),
+                NodeKind::Branch(left, right) => stack.extend([left, right]),
+            }
+        }
+        hits.sort_by(|a, b| a.1.total_cmp(&b.1));
+        hits
+    }
+
+    fn allocate(&mut self, node: TreeNode) -> usize {
+        match self.free.pop() {
+            Some(index) => {
+                self.nodes[index] = node;
+                index
+            }
+            None => {
+                self.nodes.push(node);
+                self.nodes.len() - 1
+            }
+        }
+    }
+
+    fn insert_leaf(&mut self, leaf: usize) {
+        let Some(mut sibling) = self.root else {
+            self.nodes[leaf].parent = None;
+            self.root = Some(leaf);
+            return;
+        };
+
+        // 沿合并后表面积增量较小的分支下降，找到新叶节点的兄弟节点
+        let aabb = self.nodes[leaf].aabb;
+        while let NodeKind::Branch(left, right) = self.nodes[sibling].kind {
+            let cost = |child: usize| {
+                let child = &self.nodes[child].aabb;
+                aabb.merge(child).half_area() - child.half_area()
+            };
+            sibling = if cost(left) <= cost(right) {
+                left
+            } else {
+                right
+            };
+        }
+
+        let old_parent = self.nodes[sibling].parent;
+        let parent = self.allocate(TreeNode {
+            aabb: aabb.merge(&self.nodes[sibling].aabb),
+            parent: old_parent,
+            kind: NodeKind::Branch(sibling, leaf),
+        });
+        self.nodes[sibling].parent = Some(parent);
+        self.nodes[leaf].parent = Some(parent);
+        match old_parent {
+            Some(old_parent) => self.replace_child(old_parent, sibling, parent),
+            None => self.root = Some(parent),
+        }
+        self.refit(old_parent);
+    }
+
+    fn remove_leaf(&mut self, leaf: usize) {
+        let Some(parent) = self.nodes[leaf].parent else {
+            self.root = None;
+            return;
+        };
+        let NodeKind::Branch(left, right) = self.nodes[parent].kind else {
+            unreachable!("the parent of a node is always a branch")
+        };
+        let sibling = if left == leaf { right } else { left };
+
+        // 兄弟节点取代父节点的位置
+        let grandparent = self.nodes[parent].parent;
+        self.nodes[sibling].parent = grandparent;
+        match grandparent {
+            Some(grandparent) => self.replace_child(grandparent, parent, sibling),
+            None => self.root = Some(sibling),
+        }
+        self.nodes[leaf].parent = None;
+        self.free.push(parent);
+        self.refit(grandparent);
+    }
+
+    fn replace_child(&mut self, parent: usize, old: usize, new: usize) {
+        if let NodeKind::Branch(left, right) = &mut self.nodes[parent].kind {
+            if *left == old {
+                *left = new;
+            } else {
+                *right = new;
+            }
+        }
+    }
+
+    /// 自下而上重新计算分支节点的包围盒
+    fn refit(&mut self, mut index: Option<usize>) {
+        while let Some(i) = index {
+            if let NodeKind::Branch(left, right) = self.nodes[i].kind {
+                self.nodes[i].aabb = self.nodes[left].aabb.merge(&self.nodes[right].aabb);
+            }
+            index = self.nodes[i].parent;
+        }
+    }
+}
@@ -1,4 +1,5 @@
-use crate::Pose;
+use crate::gjk::gjk_epa;
+use crate::{Aabb, Pose};
 use nalgebra as na;
 use serde::{Deserialize, Serialize};
 
@@ -18,15 +19,56 @@ pub struct Collision<T> {
     pub params: T,
 }
 
+/// 射线检测中保守推进的最大步数与判定击中的距离
+const RAY_CAST_MAX_STEP: usize = 128;
+const RAY_CAST_TOLERANCE: f64 = 1e-7;
+
 type Radius = f64;
 type Leight = f64;
 
+/// 球体，球心位于 pose 原点
 pub type Sphere = Collision<Radius>;
+/// 圆柱体，底面圆心位于 pose 原点，轴线沿局部 y 轴正方向
 pub type Cylinder = Collision<(Radius, Leight)>;
+/// 圆锥体，底面圆心位于 pose 原点，顶点位于局部 y 轴正方向 Leight 处
 pub type Cone = Collision<(Radius, Leight)>;
+/// 胶囊体，中心线段从 pose 原点沿局部 y 轴正方向延伸 Leight
 pub type Capsule = Collision<(Radius, Leight)>;
+/// 长方体，中心位于 pose 原点，参数为沿局部 x、y、z 轴的边长
 pub type Cuboid = Collision<(Leight, Leight, Leight)>;
 
+/// 两个碰撞体之间的带符号距离，相交时距离为负，其绝对值为穿透深度
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DistanceResult {
+    pub distance: f64,
+    /// a 上距离 b 最近的点，相交时为 a 上最深入 b 的点
+    pub point_a: na::Point3<f64>,
+    /// b 上距离 a 最近的点，相交时为 b 上最深入 a 的点
+    pub point_b: na::Point3<f64>,
+    /// 从 a 指向 b 的单位法向量，满足 point_b = point_a + distance * normal
+    pub normal: na::Vector3<f64>,
+}
+
+impl DistanceResult {
+    /// 交换 a 与 b 的角色
+    pub fn swap(self) -> DistanceResult {
+        DistanceResult {
+            distance: self.distance,
+            point_a: self.point_b,
+            point_b: self.point_a,
+            normal: -self.normal,
+        }
+    }
+}
+
+/// 碰撞体的凸核，碰撞体等于凸核向外扩张 margin 半径，球体与胶囊体的凸核分别为点与线段
+#[derive(Debug, Clone, Copy)]
+enum Core {
+    Point(na::Point3<f64>),
+    Segment(na::Point3<f64>, na::Point3<f64>),
+    Convex(CollisionObject),
+}
+
 impl<T> Collision<T> {
     pub fn new(id: usize, pose: Pose, params: T) -> Collision<T> {
         Collision { id, pose, params }
@@ -82,18 +124,86 @@ impl CollisionObject {
         }
     }
 
+    /// 两个碰撞体之间的带符号距离，相交时为负
     pub fn get_distance(a: &Self, b: &Self) -> f64 {
-        // 计算两个障碍物之间的距离
-        match (a, b) {
-            (Self::Sphere(a), Self::Sphere(b)) => sphere_sphere_distance(a, b),
-            (Self::Capsule(a), Self::Capsule(b)) => capsule_capsule_distance(a, b),
+        Self::get_distance_result(a, b).distance
+    }
+
+    /// 两个碰撞体之间的带符号距离、最近点与法向量。
+    /// 球体与胶囊体之间使用解析解，其余组合在凸核上使用 GJK/EPA 后再减去外扩半径
+    pub fn get_distance_result(a: &Self, b: &Self) -> DistanceResult {
+        let (core_a, margin_a) = a.core();
+        let (core_b, margin_b) = b.core();
+        let result = match (core_a, core_b) {
+            (Core::Convex(_), _) | (_, Core::Convex(_)) => {
+                let dir = b.pose().translation.vector - a.pose().translation.vector;
+                gjk_epa(&|d| core_a.support(d), &|d| core_b.support(d), &dir)
+            }
+            _ => core_distance(&core_a, &core_b),
+        };
+        DistanceResult {
+            distance: result.distance - margin_a - margin_b,
+            point_a: result.point_a + result.normal * margin_a,
+            point_b: result.point_b - result.normal * margin_b,
+            normal: result.normal,
+        }
+    }
+
+    /// 世界坐标系下的轴对齐包围盒，由凸核沿各坐标轴的支撑点求得
+    pub fn aabb(&self) -> Aabb {
+        let (core, margin) = self.core();
+        let mut mins = na::Point3::origin();
+        let mut maxs = na::Point3::origin();
+        for i in 0..3 {
+            let axis = na::Vector3::ith(i, 1.0);
+            mins[i] = core.support(&-axis)[i] - margin;
+            maxs[i] = core.support(&axis)[i] + margin;
+        }
+        Aabb::new(mins, maxs)
+    }
 
-            (Self::Sphere(a), Self::Capsule(b)) | (Self::Capsule(b), Self::Sphere(a)) => {
-                sphere_capsule_distance(a, b)
+    /// 射线 origin + t * dir 首次击中碰撞体表面时的 t，dir 为单位向量。
+    /// 起点在碰撞体内部时为 0，在 [0, max_toi] 内未击中时返回 None
+    pub fn cast_ray(
+        &self,
+        origin: &na::Point3<f64>,
+        dir: &na::Vector3<f64>,
+        max_toi: f64,
+    ) -> Option<f64> {
+        // 保守推进：每次沿射线前进当前点到碰撞体的距离，不会越过表面
+        let mut toi = 0.0;
+        for _ in 0..RAY_CAST_MAX_STEP {
+            let point = origin + dir * toi;
+            let probe = CollisionObject::Sphere(Sphere::new(
+                0,
+                Pose::translation(point.x, point.y, point.z),
+                0.0,
+            ));
+            let distance = Self::get_distance(&probe, self);
+            if distance < RAY_CAST_TOLERANCE {
+                return Some(toi);
             }
+            toi += distance;
+            if toi > max_toi {
+                return None;
+            }
+        }
+        None
+    }
 
-            // 圆柱的暂未实现
-            _ => 0.0,
+    fn core(&self) -> (Core, f64) {
+        match self {
+            Self::Sphere(s) => (Core::Point(s.pose * na::Point3::origin()), s.params),
+            Self::Capsule(c) => {
+                let start = c.pose * na::Point3::origin();
+                let end = c.pose * na::Point3::new(0.0, c.params.1, 0.0);
+                if (end - start).norm() < f64::EPSILON {
+                    (Core::Point(start), c.params.0)
+                } else {
+                    (Core::Segment(start, end), c.params.0)
+                }
+            }
+            _ => (Core::Convex(*self), 0.0),
         }
     }
 
@@ -105,40 +215,102 @@ impl CollisionObject {
     }
 }
 
-fn sphere_sphere_distance(a: &Sphere, b: &Sphere) -> f64 {
-    a.pose.inv_mul(&b.pose).translation.vector.norm() - a.params - b.params
-}
-fn sphere_capsule_distance(sphere: &Sphere, capsule: &Capsule) -> f64 {
-    let sphere_center = sphere.pose.translation.vector;
-    let capsule_start = capsule.pose.translation.vector;
-    let capsule_end = capsule.pose * na::Vector3::new(0.0, capsule.params.1, 0.0);
-
-    let closest_point = get_closest_point_on_line_segment(
-        na::Point3::from(capsule_start),
-        na::Point3::from(capsule_end),
-        na::Point3::from(sphere_center),
-    );
-
-    let center_distance = (na::Point3::from(sphere_center) - closest_point).norm();
-    (center_distance - sphere.params - capsule.params.0).max(0.0)
+impl Core {
+    /// 凸核沿世界坐标系下方向 dir 最远的点
+    fn support(&self, dir: &na::Vector3<f64>) -> na::Point3<f64> {
+        match self {
+            Core::Point(p) => *p,
+            Core::Segment(start, end) => {
+                if (end - start).dot(dir) > 0.0 {
+                    *end
+                } else {
+                    *start
+                }
+            }
+            Core::Convex(object) => {
+                let pose = object.pose();
+                let local = pose.rotation.inverse() * dir;
+                let radial = na::Vector3::new(local.x, 0.0, local.z);
+                let rim = |radius: f64| {
+                    let norm = radial.norm();
+                    if norm > 0.0 {
+                        radial * (radius / norm)
+                    } else {
+                        na::Vector3::zeros()
+                    }
+                };
+                let point = match object {
+                    CollisionObject::Cylinder(c) => {
+                        let height = if local.y > 0.0 { c.params.1 } else { 0.0 };
+                        rim(c.params.0) + na::Vector3::new(0.0, height, 0.0)
+                    }
+                    CollisionObject::Cone(c) => {
+                        let apex = na::Vector3::new(0.0, c.params.1, 0.0);
+                        let base = rim(c.params.0);
+                        if apex.dot(&local) > base.dot(&local) {
+                            apex
+                        } else {
+                            base
+                        }
+                    }
+                    CollisionObject::Cuboid(c) => na::Vector3::new(
+                        0.5 * c.params.0 * local.x.signum(),
+                        0.5 * c.params.1 * local.y.signum(),
+                        0.5 * c.params.2 * local.z.signum(),
+                    ),
+                    _ => unreachable!("spheres and capsules are handled as points and segments"),
+                };
+                pose * na::Point3::from(point)
+            }
+        }
+    }
 }
 
-fn capsule_capsule_distance(a: &Capsule, b: &Capsule) -> f64 {
-    let start1 = a.pose.translation.vector;
-    let end1 = a.pose * na::Vector3::new(0.0, a.params.1, 0.0);
-    let start2 = b.pose.translation.vector;
-    let end2 = b.pose * na::Vector3::new(0.0, b.params.1, 0.0);
-
-    let (_, _, distance_sqr) = get_closest_points_between_lines(
-        na::Point3::from(start1),
-        na::Point3::from(end1),
-        na::Point3::from(start2),
-        na::Point3::from(end2),
-    );
-
-    let distance = distance_sqr.sqrt(); // 对平方距离开平方，得到实际距离
+/// 点与线段凸核之间的解析距离
+fn core_distance(a: &Core, b: &Core) -> DistanceResult {
+    let (point_a, point_b, fallback) = match (a, b) {
+        (Core::Point(p), Core::Point(q)) => (*p, *q, na::Vector3::x()),
+        (Core::Point(p), Core::Segment(start, end)) => (
+            *p,
+            get_closest_point_on_line_segment(*start, *end, *p),
+            any_orthogonal(&(end - start)),
+        ),
+        (Core::Segment(..), Core::Point(_)) => return core_distance(b, a).swap(),
+        (Core::Segment(start1, end1), Core::Segment(start2, end2)) => {
+            let (p, q, _) = get_closest_points_between_lines(*start1, *end1, *start2, *end2);
+            let normal = (end1 - start1).cross(&(end2 - start2));
+            let fallback = if normal.norm() > 1e-9 * (end1 - start1).norm() {
+                normal.normalize()
+            } else {
+                any_orthogonal(&(end1 - start1))
+            };
+            (p, q, fallback)
+        }
+        _ => unreachable!("convex cores are handled by gjk"),
+    };
+    let diff = point_b - point_a;
+    let distance = diff.norm();
+    DistanceResult {
+        distance,
+        point_a,
+        point_b,
+        // 凸核相交时法向量不唯一，取与线段垂直的方向
+        normal: if distance > 1e-12 {
+            diff / distance
+        } else {
+            fallback
+        },
+    }
+}
 
-    (distance - a.params.0 - b.params.0).max(0.0)
+/// 与 v 垂直的任意单位向量
+fn any_orthogonal(v: &na::Vector3<f64>) -> na::Vector3<f64> {
+    let axis = match v.iamin() {
+        0 => na::Vector3::x(),
+        1 => na::Vector3::y(),
+        _ => na::Vector3::z(),
+    };
+    v.cross(&axis).normalize()
 }
 
 fn is_equal(x: f64, y: f64) -> bool {
@@ -13,13 +13,13 @@ pub enum Constraint {
 
     Equared(Vec<f64>),                            // 等式约束：x = b
     AffineSpace,                                  // 仿射空间约束：Ax = b
-    EpigraphSquaredNorm(f64),                     // 二次范数上确界约束：||x||^2 <= b
+    EpigraphSquaredNorm(usize, f64),              // 二次范数上确界约束：||x||^2 <= b，x 为 n 维
     FiniteSet(Vec<Vec<f64>>),                     // 有限集约束：x in {x1, x2, ..., xn}
     Halfspace(Vec<f64>, f64),                     // 半空间约束：ax <= b
     Hyperplane(usize, usize, Vec<f64>, Vec<f64>), // 超平面约束：ax = b
     Rectangle(Vec<f64>, Vec<f64>),                // 矩形约束：a <= x <= b
-    Simplex,                                      // 单纯形约束：x in Δ
-    SecondOrderCone,                              // 二阶锥约束
+    Simplex(usize, f64),                          // 单纯形约束：x >= 0, sum(x) = a，x 为 n 维
+    SecondOrderCone(usize, f64),                  // 二阶锥约束：||x[..n-1]|| <= a x[n-1]
 
     Intersection(usize, usize, Vec<Constraint>), // 交集约束
     Union(usize, usize, Vec<Constraint>),        // 并集约束
@@ -35,6 +35,10 @@ impl Constraint {
             Constraint::Halfspace(_, _) => 1,
             Constraint::Hyperplane(nrows, _, _, _) => *nrows,
             Constraint::Rectangle(a, _) => a.len(),
+            Constraint::EpigraphSquaredNorm(n, _) => *n,
+            Constraint::FiniteSet(points) => points.first().map_or(0, Vec::len),
+            Constraint::Simplex(n, _) => *n,
+            Constraint::SecondOrderCone(n, _) => *n,
 
             Constraint::CartesianProduct(nrows, _, _) => *nrows,
             Constraint::Intersection(nrows, _, _) => *nrows,
@@ -51,6 +55,10 @@ impl Constraint {
             Constraint::Halfspace(a, _) => a.len(),
             Constraint::Hyperplane(_, ncols, _, _) => *ncols,
             Constraint::Rectangle(a, _) => a.len(),
+            Constraint::EpigraphSquaredNorm(n, _) => *n,
+            Constraint::FiniteSet(points) => points.first().map_or(0, Vec::len),
+            Constraint::Simplex(n, _) => *n,
+            Constraint::SecondOrderCone(n, _) => *n,
 
             Constraint::CartesianProduct(_, ncols, _) => *ncols,
             Constraint::Intersection(_, ncols, _) => *ncols,
@@ -76,6 +84,34 @@ impl Constraint {
         }
     }
 
+    /// 按 to_cscmatrix 的行顺序修改线性约束 l <= ax <= u 的上下界，系数矩阵不变
+    pub fn set_bounds(&mut self, l: &[f64], u: &[f64]) {
+        assert_eq!(l.len(), self.nrows());
+        assert_eq!(u.len(), self.nrows());
+        match self {
+            Constraint::NoConstraint => (),
+            Constraint::Halfspace(_, b) => *b = u[0],
+            Constraint::Rectangle(a, b) => {
+                a.copy_from_slice(l);
+                b.copy_from_slice(u);
+            }
+            Constraint::Equared(b) | Constraint::Hyperplane(_, _, _, b) => {
+                assert_eq!(l, u, "Equality constraint needs equal bounds");
+                b.copy_from_slice(u);
+            }
+            Constraint::Intersection(_, _, constraints)
+            | Constraint::CartesianProduct(_, _, constraints) => {
+                let mut offset = 0;
+                for con in constraints {
+                    let nrows = con.nrows();
+                    con.set_bounds(&l[offset..offset + nrows], &u[offset..offset + nrows]);
+                    offset += nrows;
+                }
+            }
+            _ => panic!("This constraint has no bounds to set"),
+        }
+    }
+
     pub fn to_inequation(&self) -> (usize, usize, Vec<f64>, Vec<f64>, Vec<f64>) {
         match self {
             Constraint::NoConstraint => (0, 0, vec![], vec![], vec![]),
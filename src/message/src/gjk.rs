@@ -0,0 +1,378 @@
+use crate::DistanceResult;
+use nalgebra as na;
+
+type Vector = na::Vector3<f64>;
+type Point = na::Point3<f64>;
+
+const MAX_ITERATIONS: usize = 128;
+/// GJK 的相对收敛精度
+const GJK_TOLERANCE: f64 = 1e-12;
+/// 闵可夫斯基差到原点的距离小于该值时认为相交
+const CONTACT_TOLERANCE: f64 = 1e-9;
+const EPA_TOLERANCE: f64 = 1e-9;
+
+/// 闵可夫斯基差 A - B 上的支撑点，同时记录其在 A 与 B 上的来源
+#[derive(Debug, Clone, Copy)]
+struct Vertex {
+    w: Vector,
+    a: Point,
+    b: Point,
+}
+
+fn support<A, B>(support_a: &A, support_b: &B, dir: &Vector) -> Vertex
+where
+    A: Fn(&Vector) -> Point,
+    B: Fn(&Vector) -> Point,
+{
+    let a = support_a(dir);
+    let b = support_b(&-dir);
+    Vertex { w: a - b, a, b }
+}
+
+/// 用 GJK 计算两个凸体之间的带符号距离，相交时用 EPA 求穿透深度。
+/// support_a 与 support_b 给出世界坐标系下沿某方向最远的点，dir 为初始搜索方向
+pub(crate) fn gjk_epa<A, B>(support_a: &A, support_b: &B, dir: &Vector) -> DistanceResult
+where
+    A: Fn(&Vector) -> Point,
+    B: Fn(&Vector) -> Point,
+{
+    let dir = if dir.norm_squared() > 0.0 {
+        *dir
+    } else {
+        Vector::x()
+    };
+    let mut simplex = vec![(support(support_a, support_b, &-dir), 1.0)];
+    let mut v = simplex[0].0.w;
+
+    for _ in 0..MAX_ITERATIONS {
+        let vv = v.norm_squared();
+        if vv < CONTACT_TOLERANCE * CONTACT_TOLERANCE {
+            let vertices = simplex.iter().map(|(vertex, _)| *vertex).collect();
+            return epa(vertices, support_a, support_b);
+        }
+        let vertex = support(support_a, support_b, &-v);
+        if vv - v.dot(&vertex.w) <= GJK_TOLERANCE * vv
+            || simplex
+                .iter()
+                .any(|(s, _)| (s.w - vertex.w).norm_squared() == 0.0)
+        {
+            break;
+        }
+        let mut vertices: Vec<Vertex> = simplex.iter().map(|(vertex, _)| *vertex).collect();
+        vertices.push(vertex);
+        let closest = closest_on_simplex(&vertices);
+        let v_next = closest.iter().fold(Vector::zeros(), |v, (vertex, lambda)| {
+            v + vertex.w * *lambda
+        });
+        if v_next.norm_squared() >= vv && closest.len() < 4 {
+            // 数值上已不再下降
+            break;
+        }
+        simplex = closest;
+        v = v_next;
+    }
+
+    let (a, b) = witness(&simplex);
+    let distance = v.norm();
+    DistanceResult {
+        distance,
+        point_a: a,
+        point_b: b,
+        normal: -v / distance,
+    }
+}
+
+fn witness(simplex: &[(Vertex, f64)]) -> (Point, Point) {
+    simplex.iter().fold(
+        (Point::origin(), Point::origin()),
+        |(a, b), (vertex, lambda)| (a + vertex.a.coords * *lambda, b + vertex.b.coords * *lambda),
+    )
+}
+
+/// 单纯形上距离原点最近的点，返回仍然有贡献的顶点及其重心坐标
+fn closest_on_simplex(vertices: &[Vertex]) -> Vec<(Vertex, f64)> {
+    match vertices.len() {
+        1 => vec![(vertices[0], 1.0)],
+        2 => closest_on_segment(&vertices[0], &vertices[1]),
+        3 => closest_on_triangle(&vertices[0], &vertices[1], &vertices[2]),
+        _ => closest_on_tetrahedron(vertices),
+    }
+}
+
+fn norm_of(simplex: &[(Vertex, f64)]) -> f64 {
+    simplex
+        .iter()
+        .fold(Vector::zeros(), |v, (vertex, lambda)| {
+            v + vertex.w * *lambda
+        })
+        .norm_squared()
+}
+
+fn closest_on_segment(p: &Vertex, q: &Vertex) -> Vec<(Vertex, f64)> {
+    let line = q.w - p.w;
+    let length = line.norm_squared();
+    if length == 0.0 {
+        return vec![(*p, 1.0)];
+    }
+    let t = -p.w.dot(&line) / length;
+    if t <= 0.0 {
+        vec![(*p, 1.0)]
+    } else if t >= 1.0 {
+        vec![(*q, 1.0)]
+    } else {
+        vec![(*p, 1.0 - t), (*q, t)]
+    }
+}
+
+/// ref: Real-Time Collision Detection, 5.1.5 ClosestPtPointTriangle
+fn closest_on_triangle(a: &Vertex, b: &Vertex, c: &Vertex) -> Vec<(Vertex, f64)> {
+    let ab = b.w - a.w;
+    let ac = c.w - a.w;
+
+    let d1 = -ab.dot(&a.w);
+    let d2 = -ac.dot(&a.w);
+    if d1 <= 0.0 && d2 <= 0.0 {
+        return vec![(*a, 1.0)];
+    }
+
+    let d3 = -ab.dot(&b.w);
+    let d4 = -ac.dot(&b.w);
+    if d3 >= 0.0 && d4 <= d3 {
+        return vec![(*b, 1.0)];
+    }
+
+    let vc = d1 * d4 - d3 * d2;
+    if vc <= 0.0 && d1 >= 0.0 && d3 <= 0.0 {
+        let t = d1 / (d1 - d3);
+        return vec![(*a, 1.0 - t), (*b, t)];
+    }
+
+    let d5 = -ab.dot(&c.w);
+    let d6 = -ac.dot(&c.w);
+    if d6 >= 0.0 && d5 <= d6 {
+        return vec![(*c, 1.0)];
+    }
+
+    let vb = d5 * d2 - d1 * d6;
+    if vb <= 0.0 && d2 >= 0.0 && d6 <= 0.0 {
+        let t = d2 / (d2 - d6);
+        return vec![(*a, 1.0 - t), (*c, t)];
+    }
+
+    let va = d3 * d6 - d5 * d4;
+    if va <= 0.0 && (d4 - d3) >= 0.0 && (d5 - d6) >= 0.0 {
+        let t = (d4 - d3) / ((d4 - d3) + (d5 - d6));
+        return vec![(*b, 1.0 - t), (*c, t)];
+    }
+
+    let denom = va + vb + vc;
+    if denom.abs() < f64::EPSILON * ab.cross(&ac).norm().max(1.0) {
+        // 退化为线段的三角形
+        return [
+            closest_on_segment(a, b),
+            closest_on_segment(a, c),
+            closest_on_segment(b, c),
+        ]
+        .into_iter()
+        .min_by(|x, y| norm_of(x).total_cmp(&norm_of(y)))
+        .unwrap();
+    }
+    let v = vb / denom;
+    let w = vc / denom;
+    vec![(*a, 1.0 - v - w), (*b, v), (*c, w)]
+}
+
+fn closest_on_tetrahedron(vertices: &[Vertex]) -> Vec<(Vertex, f64)> {
+    let [a, b, c, d] = [vertices[0], vertices[1], vertices[2], vertices[3]];
+    let m = na::Matrix3::from_columns(&[b.w - a.w, c.w - a.w, d.w - a.w]);
+    if let Some(inverse) = m.try_inverse() {
+        let lambda = inverse * -a.w;
+        if lambda.iter().all(|l| *l >= 0.0) && lambda.sum() <= 1.0 {
+            // 原点位于四面体内部
+            return vec![
+                (a, 1.0 - lambda.sum()),
+                (b, lambda[0]),
+                (c, lambda[1]),
+                (d, lambda[2]),
+            ];
+        }
+    }
+    [
+        closest_on_triangle(&a, &b, &c),
+        closest_on_triangle(&a, &b, &d),
+        closest_on_triangle(&a, &c, &d),
+        closest_on_triangle(&b, &c, &d),
+    ]
+    .into_iter()
+    .min_by(|x, y| norm_of(x).total_cmp(&norm_of(y)))
+    .unwrap()
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Face {
+    index: [usize; 3],
+    normal: Vector,
+    distance: f64,
+}
+
+fn make_face(vertices: &[Vertex], index: [usize; 3], interior: &Vector) -> Option<Face> {
+    let [i, j, k] = index;
+    let normal = (vertices[j].w - vertices[i].w).cross(&(vertices[k].w - vertices[i].w));
+    let norm = normal.norm();
+    if norm < f64::EPSILON {
+        return None;
+    }
+    let mut normal = normal / norm;
+    let mut index = index;
+    if normal.dot(&(vertices[i].w - interior)) < 0.0 {
+        normal = -normal;
+        index = [i, k, j];
+    }
+    Some(Face {
+        index,
+        normal,
+        distance: normal.dot(&vertices[i].w),
+    })
+}
+
+/// 把 GJK 结束时包含原点的单纯形扩充为四面体
+fn blow_up<A, B>(vertices: &mut Vec<Vertex>, support_a: &A, support_b: &B)
+where
+    A: Fn(&Vector) -> Point,
+    B: Fn(&Vector) -> Point,
+{
+    let axes = [
+        Vector::x(),
+        -Vector::x(),
+        Vector::y(),
+        -Vector::y(),
+        Vector::z(),
+        -Vector::z(),
+    ];
+    if vertices.len() == 1 {
+        for axis in axes.iter() {
+            let vertex = support(support_a, support_b, axis);
+            if (vertex.w - vertices[0].w).norm() > CONTACT_TOLERANCE {
+                vertices.push(vertex);
+                break;
+            }
+        }
+    }
+    if vertices.len() == 2 {
+        let line = (vertices[1].w - vertices[0].w).normalize();
+        let orthogonal = line.cross(&axes[line.iamin() * 2]).normalize();
+        for k in 0..6 {
+            let rotation = na::UnitQuaternion::from_axis_angle(
+                &na::Unit::new_unchecked(line),
+                k as f64 * std::f64::consts::FRAC_PI_3,
+            );
+            let vertex = support(support_a, support_b, &(rotation * orthogonal));
+            let offset = vertex.w - vertices[0].w;
+            if (offset - line * offset.dot(&line)).norm() > CONTACT_TOLERANCE {
+                vertices.push(vertex);
+                break;
+            }
+        }
+    }
+    if vertices.len() == 3 {
+        let normal = (vertices[1].w - vertices[0].w)
+            .cross(&(vertices[2].w - vertices[0].w))
+            .normalize();
+        for dir in [normal, -normal] {
+            let vertex = support(support_a, support_b, &dir);
+            if normal.dot(&(vertex.w - vertices[0].w)).abs() > CONTACT_TOLERANCE {
+                vertices.push(vertex);
+                break;
+            }
+        }
+    }
+}
+
+/// EPA 求穿透深度，返回的距离为负
+fn epa<A, B>(mut vertices: Vec<Vertex>, support_a: &A, support_b: &B) -> DistanceResult
+where
+    A: Fn(&Vector) -> Point,
+    B: Fn(&Vector) -> Point,
+{
+    blow_up(&mut vertices, support_a, support_b);
+    if vertices.len() < 4 {
+        // 两个物体都退化为点或线段，只在表面接触
+        let (a, b) = witness(&[(vertices[0], 1.0)]);
+        return DistanceResult {
+            distance: 0.0,
+            point_a: a,
+            point_b: b,
+            normal: Vector::x(),
+        };
+    }
+
+    let interior = vertices
+        .iter()
+        .fold(Vector::zeros(), |v, vertex| v + vertex.w)
+        / 4.0;
+    let mut faces: Vec<Face> = [[0, 1, 2], [0, 1, 3], [0, 2, 3], [1, 2, 3]]
+        .into_iter()
+        .filter_map(|index| make_face(&vertices, index, &interior))
+        .collect();
+
+    let mut closest = nearest_face(&faces);
+    for _ in 0..MAX_ITERATIONS {
+        let vertex = support(support_a, support_b, &closest.normal);
+        if vertex.w.dot(&closest.normal) - closest.distance < EPA_TOLERANCE {
+            break;
+        }
+
+        // 删除新顶点可见的面，并用地平线上的边与新顶点组成新的面
+        let new_index = vertices.len();
+        vertices.push(vertex);
+        let (visible, kept): (Vec<Face>, Vec<Face>) = faces
+            .into_iter()
+            .partition(|face| face.normal.dot(&(vertex.w - vertices[face.index[0]].w)) > 0.0);
+        faces = kept;
+        let edges: Vec<(usize, usize)> = visible
+            .iter()
+            .flat_map(|face| {
+                let [i, j, k] = face.index;
+                [(i, j), (j, k), (k, i)]
+            })
+            .collect();
+        for &(i, j) in edges.iter() {
+            if !edges.contains(&(j, i)) {
+                if let Some(face) = make_face(&vertices, [i, j, new_index], &interior) {
+                    faces.push(face);
+                }
+            }
+        }
+        if visible.is_empty() || faces.is_empty() {
+            break;
+        }
+        closest = nearest_face(&faces);
+    }
+
+    // 原点在最近面上的投影即为穿透向量
+    let [i, j, k] = closest.index;
+    let projected = closest.normal * closest.distance;
+    let shifted = |vertex: &Vertex| Vertex {
+        w: vertex.w - projected,
+        ..*vertex
+    };
+    let simplex = closest_on_triangle(
+        &shifted(&vertices[i]),
+        &shifted(&vertices[j]),
+        &shifted(&vertices[k]),
+    );
+    let (a, b) = witness(&simplex);
+    DistanceResult {
+        distance: -closest.distance,
+        point_a: a,
+        point_b: b,
+        normal: closest.normal,
+    }
+}
+
+fn nearest_face(faces: &[Face]) -> Face {
+    *faces
+        .iter()
+        .min_by(|a, b| a.distance.total_cmp(&b.distance))
+        .unwrap()
+}
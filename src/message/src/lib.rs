@@ -1,6 +1,8 @@
+mod broad_phase;
 mod collision_object;
 mod constraint;
 mod control_command;
+mod gjk;
 mod massage_trait;
 mod node_message;
 mod problem;
@@ -9,6 +11,7 @@ mod target;
 mod track;
 mod utilities;
 
+pub use broad_phase::*;
 pub use collision_object::*;
 pub use constraint::*;
 pub use control_command::*;
@@ -24,6 +24,30 @@ pub enum NodeMessage<V> {
     Tau(V),
 }
 
+/// 消息的种类，即 NodeMessage 的变体，不携带具体数据，用于声明节点端口接受的消息
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize, Serialize)]
+pub enum MessageKind {
+    NoneNodeMessage,
+    KillNode,
+    NodeMessages,
+    Process,
+    Period,
+    Relative,
+    Pose,
+    Transform,
+    Joint,
+    JointList,
+    JointVel,
+    JointVelAcc,
+    Tau,
+}
+
+impl std::fmt::Display for MessageKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
 pub type DNodeMessage = NodeMessage<na::DVector<f64>>;
 pub type SNodeMessage<const N: usize> = NodeMessage<na::SVector<f64, N>>;
 
@@ -31,7 +55,67 @@ pub type NodeMessageQueue<V> = Arc<SegQueue<NodeMessage<V>>>;
 pub type DNodeMessageQueue = Arc<SegQueue<DNodeMessage>>;
 pub type SNodeMessageQueue<const N: usize> = Arc<SegQueue<SNodeMessage<N>>>;
 
+impl<V> NodeMessage<V> {
+    pub fn kind(&self) -> MessageKind {
+        match self {
+            Self::NoneNodeMessage => MessageKind::NoneNodeMessage,
+            Self::KillNode => MessageKind::KillNode,
+            Self::NodeMessages(_) => MessageKind::NodeMessages,
+            Self::Process(_, _) => MessageKind::Process,
+            Self::Period(_, _) => MessageKind::Period,
+            Self::Relative(_, _, _) => MessageKind::Relative,
+            Self::Pose(_) => MessageKind::Pose,
+            Self::Transform(_, _, _) => MessageKind::Transform,
+            Self::Joint(_) => MessageKind::Joint,
+            Self::JointList(_) => MessageKind::JointList,
+            Self::JointVel(_, _) => MessageKind::JointVel,
+            Self::JointVelAcc(_, _, _) => MessageKind::JointVelAcc,
+            Self::Tau(_) => MessageKind::Tau,
+        }
+    }
+
+    /// 逐个转换消息中的向量，任一向量转换失败时返回 None
+    pub fn try_map<W>(self, f: &impl Fn(V) -> Option<W>) -> Option<NodeMessage<W>> {
+        let message = match self {
+            Self::NoneNodeMessage => NodeMessage::NoneNodeMessage,
+            Self::KillNode => NodeMessage::KillNode,
+            Self::NodeMessages(messages) => NodeMessage::NodeMessages(
+                messages
+                    .into_iter()
+                    .map(|message| message.try_map(f))
+                    .collect::<Option<_>>()?,
+            ),
+            Self::Process(start, end) => {
+                NodeMessage::Process(Box::new(start.try_map(f)?), Box::new(end.try_map(f)?))
+            }
+            Self::Period(period, message) => {
+                NodeMessage::Period(period, Box::new(message.try_map(f)?))
+            }
+            Self::Relative(from, to, pose) => NodeMessage::Relative(from, to, pose),
+            Self::Pose(pose) => NodeMessage::Pose(pose),
+            Self::Transform(id, from, to) => NodeMessage::Transform(id, from, to),
+            Self::Joint(joint) => NodeMessage::Joint(f(joint)?),
+            Self::JointList(joint_list) => {
+                NodeMessage::JointList(joint_list.into_iter().map(f).collect::<Option<_>>()?)
+            }
+            Self::JointVel(joint, vel) => NodeMessage::JointVel(f(joint)?, f(vel)?),
+            Self::JointVelAcc(joint, vel, acc) => {
+                NodeMessage::JointVelAcc(f(joint)?, f(vel)?, f(acc)?)
+            }
+            Self::Tau(tau) => NodeMessage::Tau(f(tau)?),
+        };
+        Some(message)
+    }
+}
+
 impl NodeMessage<na::DVector<f64>> {
+    /// 转换为定长消息，向量长度与 N 不一致时返回 None
+    pub fn to_static<const N: usize>(self) -> Option<SNodeMessage<N>> {
+        self.try_map(&|v: na::DVector<f64>| {
+            (v.len() == N).then(|| na::SVector::from_column_slice(v.as_slice()))
+        })
+    }
+
     pub fn as_slice(&self) -> &[f64] {
         match self {
             Self::Period(_, msg) => msg.as_slice(),
@@ -75,6 +159,26 @@ impl Div for NodeMessage<na::DVector<f64>> {
     }
 }
 
+impl<const N: usize> NodeMessage<na::SVector<f64, N>> {
+    /// 转换为变长消息
+    pub fn to_dynamic(self) -> DNodeMessage {
+        self.try_map(&|v: na::SVector<f64, N>| Some(na::DVector::from_column_slice(v.as_slice())))
+            .unwrap()
+    }
+
+    pub fn as_slice(&self) -> &[f64] {
+        match self {
+            Self::Period(_, msg) => msg.as_slice(),
+            Self::Pose(pose) => pose.translation.vector.as_slice(),
+            Self::Joint(joint) => joint.as_slice(),
+            Self::JointVel(joint, _) => joint.as_slice(),
+            Self::JointVelAcc(joint, _, _) => joint.as_slice(),
+            Self::Tau(tau) => tau.as_slice(),
+            _ => panic!("This type does not support as_slice"),
+        }
+    }
+}
+
 impl<const N: usize> Div for NodeMessage<na::SVector<f64, N>> {
     type Output = f64;
     fn div(self, rhs: Self) -> Self::Output {
@@ -1,14 +1,99 @@
 use crate::constraint::*;
 use osqp::CscMatrix;
+use std::{fmt, rc::Rc};
 
 #[derive(Debug)]
 pub enum Problem<'a> {
     QuadraticProgramming(QuadraticProgramming<'a>),
+    NonlinearProgramming(NonlinearProgramming<'a>),
 }
 
+/// 二次规划：min 0.5 x'hx + f'x，h 为完整的对称矩阵
 #[derive(Debug)]
 pub struct QuadraticProgramming<'a> {
     pub h: &'a CscMatrix<'a>,
     pub f: &'a [f64],
     pub constraints: Constraint,
 }
+
+/// 目标函数
+pub type CostFn<'a> = Box<dyn Fn(&[f64]) -> f64 + 'a>;
+/// 目标函数的梯度，写入第二个参数
+pub type GradFn<'a> = Box<dyn Fn(&[f64], &mut [f64]) + 'a>;
+
+/// 一般非线性规划：min cost(x)，x 满足 constraints
+pub struct NonlinearProgramming<'a> {
+    pub dim: usize,
+    pub cost: CostFn<'a>,
+    pub grad: GradFn<'a>,
+    pub constraints: Constraint,
+}
+
+impl Problem<'_> {
+    /// 问题类型的名称
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Problem::QuadraticProgramming(_) => "quadratic programming",
+            Problem::NonlinearProgramming(_) => "nonlinear programming",
+        }
+    }
+}
+
+impl fmt::Debug for NonlinearProgramming<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("NonlinearProgramming")
+            .field("dim", &self.dim)
+            .field("constraints", &self.constraints)
+            .finish_non_exhaustive()
+    }
+}
+
+impl NonlinearProgramming<'static> {
+    /// 由 h 与 f 构造二次规划 min 0.5 x'hx + f'x 对应的非线性规划，目标函数持有 h 与 f
+    pub fn quadratic(h: CscMatrix<'static>, f: Vec<f64>, constraints: Constraint) -> Self {
+        let dim = f.len();
+        let cost_data = Rc::new((h, f));
+        let grad_data = cost_data.clone();
+        NonlinearProgramming {
+            dim,
+            cost: Box::new(move |x| {
+                let (h, f) = &*cost_data;
+                let hx = csc_mul(h, x);
+                x.iter()
+                    .zip(hx.iter().zip(f))
+                    .map(|(x, (hx, f))| x * (0.5 * hx + f))
+                    .sum()
+            }),
+            grad: Box::new(move |x, grad| {
+                let (h, f) = &*grad_data;
+                let hx = csc_mul(h, x);
+                for (grad, (hx, f)) in grad.iter_mut().zip(hx.iter().zip(f)) {
+                    *grad = hx + f;
+                }
+            }),
+            constraints,
+        }
+    }
+}
+
+/// 二次规划是非线性规划的特例，供只接受一般目标函数的求解器使用
+impl<'a> From<QuadraticProgramming<'a>> for NonlinearProgramming<'a> {
+    fn from(problem: QuadraticProgramming<'a>) -> Self {
+        NonlinearProgramming::quadratic(
+            problem.h.clone().into_owned(),
+            problem.f.to_vec(),
+            problem.constraints,
+        )
+    }
+}
+
+/// 稀疏矩阵与向量的乘积 h x
+pub fn csc_mul(h: &CscMatrix, x: &[f64]) -> Vec<f64> {
+    let mut y = vec![0.0; h.nrows];
+    for (j, x) in x.iter().enumerate().take(h.ncols) {
+        for k in h.indptr[j]..h.indptr[j + 1] {
+            y[h.indices[k]] += h.data[k] * x;
+        }
+    }
+    y
+}
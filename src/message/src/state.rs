@@ -42,4 +42,7 @@ pub enum TaskState {
     RelyRelease(String),
     PlanEnd(String),
     ControlEnd(String),
+    NodeEnd(String),
+    /// 节点收到停止信号而退出，不算完成任务，但节点不再向机器人下发指令
+    NodeStop(String),
 }
@@ -4,7 +4,7 @@ use nalgebra as na;
 use serde::Deserialize;
 use std::sync::{Arc, Mutex};
 
-use crate::{Node, NodeBehavior, NodeExtBehavior, NodeRegister};
+use crate::{Node, NodeBehavior, NodeError, NodeExtBehavior, NodeRegister};
 
 #[node_registration("zmq_comm")]
 pub type ZmqComm = Node<ZmqCommState, ZmqCommParams, (), na::DVector<f64>>;
@@ -50,4 +50,8 @@ impl NodeBehavior for ZmqComm {
             responder.send("Failed to receive message", 0).unwrap();
         }
     }
+
+    fn node_name(&self) -> String {
+        self.name.clone()
+    }
 }
@@ -0,0 +1,98 @@
+use std::fmt;
+
+use crate::MessageType;
+use message::MessageKind;
+
+/// 节点创建过程中的错误
+#[derive(Debug)]
+pub enum NodeError {
+    /// 节点类型未注册
+    UnknownType(String),
+    /// 节点参数无法被反序列化
+    Params {
+        name: String,
+        source: serde_json::Error,
+    },
+    /// 边两端节点的消息类型不一致
+    MessageMismatch {
+        from: String,
+        to: String,
+        from_type: MessageType,
+        to_type: MessageType,
+    },
+    /// 任务目标无法转换为节点的消息类型
+    UnsupportedTarget {
+        name: String,
+        message_type: MessageType,
+    },
+    /// 节点类型没有声明该端口
+    UnknownPort { name: String, port: String },
+    /// 输出端口发出的消息种类不被输入端口接受
+    PortMismatch {
+        from: String,
+        from_port: String,
+        to: String,
+        to_port: String,
+    },
+    /// 任务目标的消息种类不被节点的输入端口接受
+    TargetMismatch {
+        name: String,
+        port: String,
+        kind: MessageKind,
+    },
+}
+
+impl fmt::Display for NodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            NodeError::UnknownType(node_type) => {
+                write!(f, "unknown or unregistered node type: {}", node_type)
+            }
+            NodeError::Params { name, source } => {
+                write!(f, "invalid params for node {}: {}", name, source)
+            }
+            NodeError::MessageMismatch {
+                from,
+                to,
+                from_type,
+                to_type,
+            } => write!(
+                f,
+                "cannot connect {} ({}) to {} ({}), message types differ",
+                from, from_type, to, to_type
+            ),
+            NodeError::UnsupportedTarget { name, message_type } => write!(
+                f,
+                "task targets cannot be converted to {} messages for node {}",
+                message_type, name
+            ),
+            NodeError::UnknownPort { name, port } => {
+                write!(f, "node {} has no port named {}", name, port)
+            }
+            NodeError::PortMismatch {
+                from,
+                from_port,
+                to,
+                to_port,
+            } => write!(
+                f,
+                "cannot connect {}.{} to {}.{}, the ports share no message kind",
+                from, from_port, to, to_port
+            ),
+            NodeError::TargetMismatch { name, port, kind } => write!(
+                f,
+                "task target of kind {} is not accepted by port {} of node {}",
+                kind, port, name
+            ),
+        }
+    }
+}
+
+impl std::error::Error for NodeError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            NodeError::Params { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
@@ -3,7 +3,7 @@ use nalgebra as na;
 use serde::Deserialize;
 use std::time::Duration;
 
-use crate::{Node, NodeBehavior, NodeExtBehavior, NodeRegister};
+use crate::{Node, NodeBehavior, NodeError, NodeExtBehavior, NodeRegister};
 use message::DNodeMessage;
 use robot::{DSeriseRobot, Robot, RobotLock};
 
@@ -83,4 +83,8 @@ impl NodeBehavior for ExController {
     fn period(&self) -> Duration {
         Duration::from_secs_f64(self.params.period)
     }
+
+    fn node_name(&self) -> String {
+        self.name.clone()
+    }
 }
@@ -2,7 +2,7 @@ use kernel_macro::node_registration;
 use message::NodeMessage;
 use serde::Deserialize;
 
-use crate::{Node, NodeBehavior, NodeExtBehavior, NodeRegister};
+use crate::{Node, NodeBehavior, NodeError, NodeExtBehavior, NodeRegister};
 
 #[node_registration("example_node")]
 type ExNode = Node<ExNodeState, ExNodeParams, (), f64>;
@@ -41,4 +41,8 @@ impl NodeBehavior for ExNode {
         let control_message = NodeMessage::Joint(output);
         self.output_queue.push(control_message);
     }
+
+    fn node_name(&self) -> String {
+        self.name.clone()
+    }
 }
@@ -1,12 +1,14 @@
+use kernel_macro::node_registration;
 use message::{NodeMessage, Pose};
 use nalgebra as na;
 use rand::Rng;
 use robot::{Gripper, RobotLock};
 use serde::Deserialize;
 
-use crate::{Node, NodeBehavior, NodeState};
+use crate::{Node, NodeBehavior, NodeError, NodeExtBehavior, NodeRegister, NodeState};
 use robot::{DSeriseRobot, Robot};
 
+#[node_registration("example_planner")]
 pub type ExPlanner = Node<
     ExPlannerState,
     ExPlannerParams,
@@ -80,11 +82,14 @@ impl NodeBehavior for ExPlanner {
                 }),
             );
 
-            let mut gripper_write = self.robot.1.as_mut().unwrap().write().unwrap();
-            if gripper_write.width() > 0.01 {
-                gripper_write.grasp(0.01);
-            } else {
-                gripper_write.home();
+            // the gripper is optional, the demo only opens and closes it when it is given
+            if let Some(gripper) = self.robot.1.as_ref() {
+                let mut gripper_write = gripper.write().unwrap();
+                if gripper_write.width() > 0.01 {
+                    gripper_write.grasp(0.01);
+                } else {
+                    gripper_write.home();
+                }
             }
 
             // // Interpolate between current q and q_target using lerp
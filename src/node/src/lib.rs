@@ -2,18 +2,24 @@
 
 mod communication;
 mod create;
+mod error;
 mod example;
+mod node_handle;
 mod node_trait;
 mod nodes;
 mod plants;
+mod port;
 mod sensor_releaser;
 mod simulators;
 mod utilities;
 
 pub use communication::*;
+pub use error::*;
+pub use node_handle::*;
 pub use node_trait::*;
 pub use nodes::*;
 pub use plants::*;
+pub use port::*;
 pub use sensor_releaser::*;
 pub use simulators::*;
 pub use utilities::*;
@@ -0,0 +1,259 @@
+use nalgebra as na;
+use serde::Deserialize;
+use std::{
+    fmt,
+    sync::{Arc, RwLock},
+};
+
+use crate::{factory, NodeError, NodeExtBehavior, Port};
+use message::{NodeMessage, Target};
+use robot::{RobotType, PANDA_DOF};
+use sensor::Sensor;
+
+/// 节点之间传递的消息类型，决定节点从哪一个注册表中创建
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+pub enum MessageType {
+    /// 变长向量 `na::DVector<f64>`，所有节点的默认类型
+    #[default]
+    #[serde(rename = "dvector")]
+    DVector,
+    /// 定长向量 `na::SVector<f64, PANDA_DOF>`，用于 Panda 的定长管线
+    #[serde(rename = "svector7")]
+    SVector7,
+    /// 标量 `f64`
+    #[serde(rename = "f64")]
+    F64,
+}
+
+impl MessageType {
+    pub const ALL: [MessageType; 3] = [
+        MessageType::DVector,
+        MessageType::SVector7,
+        MessageType::F64,
+    ];
+}
+
+impl fmt::Display for MessageType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MessageType::DVector => write!(f, "dvector"),
+            MessageType::SVector7 => write!(f, "svector7"),
+            MessageType::F64 => write!(f, "f64"),
+        }
+    }
+}
+
+/// 类型擦除后的节点，不同消息类型的节点可以放在同一个列表中，连接时再检查消息类型是否一致
+pub enum NodeHandle {
+    DVector(Box<dyn NodeExtBehavior<na::DVector<f64>>>),
+    SVector7(Box<dyn NodeExtBehavior<na::SVector<f64, PANDA_DOF>>>),
+    F64(Box<dyn NodeExtBehavior<f64>>),
+}
+
+impl NodeHandle {
+    pub fn name(&self) -> String {
+        match self {
+            NodeHandle::DVector(node) => node.name(),
+            NodeHandle::SVector7(node) => node.name(),
+            NodeHandle::F64(node) => node.name(),
+        }
+    }
+
+    pub fn node_name(&self) -> String {
+        match self {
+            NodeHandle::DVector(node) => node.node_name(),
+            NodeHandle::SVector7(node) => node.node_name(),
+            NodeHandle::F64(node) => node.node_name(),
+        }
+    }
+
+    pub fn message_type(&self) -> MessageType {
+        match self {
+            NodeHandle::DVector(_) => MessageType::DVector,
+            NodeHandle::SVector7(_) => MessageType::SVector7,
+            NodeHandle::F64(_) => MessageType::F64,
+        }
+    }
+
+    pub fn set_robot(&mut self, robot: RobotType) {
+        match self {
+            NodeHandle::DVector(node) => node.set_robot(robot),
+            NodeHandle::SVector7(node) => node.set_robot(robot),
+            NodeHandle::F64(node) => node.set_robot(robot),
+        }
+    }
+
+    pub fn set_sensor(&mut self, sensor: Arc<RwLock<Sensor>>) {
+        match self {
+            NodeHandle::DVector(node) => node.set_sensor(sensor),
+            NodeHandle::SVector7(node) => node.set_sensor(sensor),
+            NodeHandle::F64(node) => node.set_sensor(sensor),
+        }
+    }
+
+    /// 将任务目标注入节点的输入端口，定长节点的目标会被转换为定长消息
+    pub fn push_targets(&mut self, port: &str, targets: &[Target]) -> Result<(), NodeError> {
+        match self {
+            NodeHandle::DVector(node) => push_to_port(node.as_mut(), port, targets.to_vec()),
+            NodeHandle::SVector7(node) => {
+                let Some(targets) = targets
+                    .iter()
+                    .map(|target| target.clone().to_static::<PANDA_DOF>())
+                    .collect::<Option<Vec<_>>>()
+                else {
+                    return Err(NodeError::UnsupportedTarget {
+                        name: node.name(),
+                        message_type: MessageType::SVector7,
+                    });
+                };
+                push_to_port(node.as_mut(), port, targets)
+            }
+            NodeHandle::F64(node) => {
+                if !targets.is_empty() {
+                    return Err(NodeError::UnsupportedTarget {
+                        name: node.name(),
+                        message_type: MessageType::F64,
+                    });
+                }
+                input_port(node.as_ref(), port).map(|_| ())
+            }
+        }
+    }
+}
+
+/// 按照消息类型在对应的注册表中创建节点
+pub fn create_node(
+    node_type: &str,
+    robot_name: &str,
+    params: serde_json::Value,
+    message_type: MessageType,
+) -> Result<NodeHandle, NodeError> {
+    let node = match message_type {
+        MessageType::DVector => NodeHandle::DVector(factory(node_type, robot_name, params)?),
+        MessageType::SVector7 => NodeHandle::SVector7(factory(node_type, robot_name, params)?),
+        MessageType::F64 => NodeHandle::F64(factory(node_type, robot_name, params)?),
+    };
+    Ok(node)
+}
+
+/// 将节点列表中 from 节点的输出端口连接到 to 节点的输入端口，两个节点的消息类型必须一致，
+/// 端口的消息种类必须有交集。同一个输出端口的多条边广播，同一个输入端口的多条边共用一个队列
+pub fn connect(
+    nodes: &mut [NodeHandle],
+    (from, from_port): (usize, &str),
+    (to, to_port): (usize, &str),
+) -> Result<(), NodeError> {
+    if from == to {
+        // 自环，节点的输出直接回到自身的输入
+        return match &mut nodes[from] {
+            NodeHandle::DVector(node) => link_self(node.as_mut(), from_port, to_port),
+            NodeHandle::SVector7(node) => link_self(node.as_mut(), from_port, to_port),
+            NodeHandle::F64(node) => link_self(node.as_mut(), from_port, to_port),
+        };
+    }
+    let (from, to) = if from < to {
+        let (left, right) = nodes.split_at_mut(to);
+        (&mut left[from], &mut right[0])
+    } else {
+        let (left, right) = nodes.split_at_mut(from);
+        (&mut right[0], &mut left[to])
+    };
+
+    match (from, to) {
+        (NodeHandle::DVector(from), NodeHandle::DVector(to)) => {
+            link(from.as_mut(), from_port, to.as_mut(), to_port)
+        }
+        (NodeHandle::SVector7(from), NodeHandle::SVector7(to)) => {
+            link(from.as_mut(), from_port, to.as_mut(), to_port)
+        }
+        (NodeHandle::F64(from), NodeHandle::F64(to)) => {
+            link(from.as_mut(), from_port, to.as_mut(), to_port)
+        }
+        (from, to) => Err(NodeError::MessageMismatch {
+            from: from.name(),
+            to: to.name(),
+            from_type: from.message_type(),
+            to_type: to.message_type(),
+        }),
+    }
+}
+
+fn input_port<V>(node: &dyn NodeExtBehavior<V>, port: &str) -> Result<Port, NodeError> {
+    node.ports()
+        .input(port)
+        .copied()
+        .ok_or_else(|| NodeError::UnknownPort {
+            name: node.name(),
+            port: port.to_string(),
+        })
+}
+
+fn output_port<V>(node: &dyn NodeExtBehavior<V>, port: &str) -> Result<Port, NodeError> {
+    node.ports()
+        .output(port)
+        .copied()
+        .ok_or_else(|| NodeError::UnknownPort {
+            name: node.name(),
+            port: port.to_string(),
+        })
+}
+
+fn check_ports<V>(
+    from: &dyn NodeExtBehavior<V>,
+    from_port: &str,
+    to: &dyn NodeExtBehavior<V>,
+    to_port: &str,
+) -> Result<(), NodeError> {
+    if output_port(from, from_port)?.compatible(&input_port(to, to_port)?) {
+        Ok(())
+    } else {
+        Err(NodeError::PortMismatch {
+            from: from.name(),
+            from_port: from_port.to_string(),
+            to: to.name(),
+            to_port: to_port.to_string(),
+        })
+    }
+}
+
+fn link<V>(
+    from: &mut dyn NodeExtBehavior<V>,
+    from_port: &str,
+    to: &mut dyn NodeExtBehavior<V>,
+    to_port: &str,
+) -> Result<(), NodeError> {
+    check_ports(from, from_port, to, to_port)?;
+    from.add_output_queue(from_port, to.input_queue(to_port));
+    Ok(())
+}
+
+fn link_self<V>(
+    node: &mut dyn NodeExtBehavior<V>,
+    from_port: &str,
+    to_port: &str,
+) -> Result<(), NodeError> {
+    check_ports(node, from_port, node, to_port)?;
+    let queue = node.input_queue(to_port);
+    node.add_output_queue(from_port, queue);
+    Ok(())
+}
+
+fn push_to_port<V>(
+    node: &mut dyn NodeExtBehavior<V>,
+    port: &str,
+    targets: Vec<NodeMessage<V>>,
+) -> Result<(), NodeError> {
+    let input = input_port(node, port)?;
+    if let Some(target) = targets.iter().find(|target| !input.accepts(target.kind())) {
+        return Err(NodeError::TargetMismatch {
+            name: node.name(),
+            port: port.to_string(),
+            kind: target.kind(),
+        });
+    }
+    let queue = node.input_queue(port);
+    for target in targets {
+        queue.push(target);
+    }
+    Ok(())
+}
@@ -4,17 +4,23 @@ use sensor::Sensor;
 use serde::de::DeserializeOwned;
 use serde_json::{from_value, Value};
 use std::{
+    collections::HashMap,
     sync::{Arc, RwLock},
     time::Duration,
 };
 
+use crate::{MessageType, NodeError, NodePorts, OutputQueue, DEFAULT_INPUT, DEFAULT_OUTPUT};
 use message::NodeMessageQueue;
-use robot::{DownCastRobot, RobotType};
+use robot::{DownCastRobot, RobotType, PANDA_DOF};
 
 pub trait NodeExt<V> {
     fn name(&self) -> String;
-    fn set_input_queue(&mut self, input_queue: NodeMessageQueue<V>);
-    fn set_output_queue(&mut self, output_queue: NodeMessageQueue<V>);
+    fn ports(&self) -> NodePorts;
+    fn set_ports(&mut self, ports: NodePorts);
+    /// 输入端口的队列，连接到同一个输入端口的多条边共用这一个队列
+    fn input_queue(&mut self, port: &str) -> NodeMessageQueue<V>;
+    /// 为输出端口增加一条边，消息会广播到该端口的所有边
+    fn add_output_queue(&mut self, port: &str, output_queue: NodeMessageQueue<V>);
     fn set_params(&mut self, params: Value);
     fn set_sensor(&mut self, sensor: Arc<RwLock<Sensor>>);
     fn set_robot(&mut self, robot: RobotType);
@@ -61,8 +67,14 @@ where
     pub(crate) name: String,
     pub(crate) node_state: NodeState,
     pub(crate) is_end: bool,
+    pub(crate) ports: NodePorts,
+    /// 默认输入端口 "in"
     pub(crate) input_queue: NodeMessageQueue<V>,
-    pub(crate) output_queue: NodeMessageQueue<V>,
+    /// 默认输出端口 "out"
+    pub(crate) output_queue: OutputQueue<V>,
+    /// 除默认端口以外的具名端口
+    pub(crate) inputs: HashMap<String, NodeMessageQueue<V>>,
+    pub(crate) outputs: HashMap<String, OutputQueue<V>>,
 
     pub state: S,
     pub params: P,
@@ -76,17 +88,40 @@ where
     P: DeserializeOwned,
     R: Default,
 {
-    pub fn from_params(name: String, params: Value) -> Self {
-        Node {
+    pub fn from_params(name: String, params: Value) -> Result<Self, NodeError> {
+        let params = match from_value(params) {
+            Ok(params) => params,
+            Err(source) => return Err(NodeError::Params { name, source }),
+        };
+        Ok(Node {
             name,
             node_state: NodeState::Init,
             is_end: false,
+            ports: NodePorts::DEFAULT,
             input_queue: NodeMessageQueue::default(),
-            output_queue: NodeMessageQueue::default(),
+            output_queue: OutputQueue::default(),
+            inputs: HashMap::new(),
+            outputs: HashMap::new(),
             state: S::default(),
-            params: from_value(params).unwrap(),
+            params,
             robot: R::default(),
             sensor: None,
+        })
+    }
+
+    /// 具名输入端口的队列，端口没有连接时返回 None
+    pub fn input(&self, port: &str) -> Option<&NodeMessageQueue<V>> {
+        match port {
+            DEFAULT_INPUT => Some(&self.input_queue),
+            _ => self.inputs.get(port),
+        }
+    }
+
+    /// 具名输出端口，端口没有连接时返回 None
+    pub fn output(&self, port: &str) -> Option<&OutputQueue<V>> {
+        match port {
+            DEFAULT_OUTPUT => Some(&self.output_queue),
+            _ => self.outputs.get(port),
         }
     }
 }
@@ -97,11 +132,26 @@ where
     P: DeserializeOwned,
     R: DownCastRobot + Clone,
 {
-    get_fn!((name: String));
-    set_fn!(
-        (set_input_queue, input_queue: NodeMessageQueue<V>),
-        (set_output_queue, output_queue: NodeMessageQueue<V>)
-    );
+    get_fn!((name: String), (ports: NodePorts));
+    set_fn!((set_ports, ports: NodePorts));
+
+    fn input_queue(&mut self, port: &str) -> NodeMessageQueue<V> {
+        match port {
+            DEFAULT_INPUT => self.input_queue.clone(),
+            _ => self.inputs.entry(port.to_string()).or_default().clone(),
+        }
+    }
+
+    fn add_output_queue(&mut self, port: &str, output_queue: NodeMessageQueue<V>) {
+        match port {
+            DEFAULT_OUTPUT => self.output_queue.connect(output_queue),
+            _ => self
+                .outputs
+                .entry(port.to_string())
+                .or_default()
+                .connect(output_queue),
+        }
+    }
 
     fn set_params(&mut self, params: Value) {
         self.params = from_value(params).unwrap();
@@ -116,25 +166,80 @@ where
     }
 }
 
+/// 根据节点名称与参数创建节点
+pub type NodeCreator<V> = fn(String, Value) -> Result<Box<dyn NodeExtBehavior<V>>, NodeError>;
+
 pub struct NodeRegister<V> {
     pub node_type: &'static str,
-    pub node_creator: fn(String, Value) -> Box<dyn NodeExtBehavior<V>>,
+    pub node_creator: NodeCreator<V>,
+    pub ports: NodePorts,
 }
 
 inventory::collect!(NodeRegister<na::DVector<f64>>);
+inventory::collect!(NodeRegister<na::SVector<f64, PANDA_DOF>>);
 inventory::collect!(NodeRegister<f64>);
 
-pub fn factory(
+/// 在消息类型 V 对应的注册表中查找节点类型并创建节点
+pub fn factory<V: 'static>(
     node_type: &str,
     robot_name: &str,
     params: Value,
-) -> Box<dyn NodeExtBehavior<na::DVector<f64>>> {
+) -> Result<Box<dyn NodeExtBehavior<V>>, NodeError>
+where
+    NodeRegister<V>: inventory::Collect,
+{
     let name = format!("{}:{}", node_type, robot_name);
-    for reg in inventory::iter::<NodeRegister<na::DVector<f64>>> {
+    for reg in inventory::iter::<NodeRegister<V>> {
         if reg.node_type == node_type {
-            return (reg.node_creator)(name, params);
+            let mut node = (reg.node_creator)(name, params)?;
+            node.set_ports(reg.ports);
+            return Ok(node);
         }
     }
-    // 未注册的节点类型会导致 panic
-    panic!("Unknown or unregisted node type: {}", node_type);
+    Err(NodeError::UnknownType(node_type.to_string()))
+}
+
+fn registered<V: 'static>() -> Vec<&'static str>
+where
+    NodeRegister<V>: inventory::Collect,
+{
+    inventory::iter::<NodeRegister<V>>
+        .into_iter()
+        .map(|reg| reg.node_type)
+        .collect()
+}
+
+/// 列出某一消息类型下所有已经注册的节点类型
+pub fn node_types(message_type: MessageType) -> Vec<&'static str> {
+    let mut node_types = match message_type {
+        MessageType::DVector => registered::<na::DVector<f64>>(),
+        MessageType::SVector7 => registered::<na::SVector<f64, PANDA_DOF>>(),
+        MessageType::F64 => registered::<f64>(),
+    };
+    node_types.sort_unstable();
+    node_types
+}
+
+/// 检查节点类型是否已经在该消息类型下注册
+pub fn is_registered(node_type: &str, message_type: MessageType) -> bool {
+    node_types(message_type).contains(&node_type)
+}
+
+fn registered_ports<V: 'static>(node_type: &str) -> Option<NodePorts>
+where
+    NodeRegister<V>: inventory::Collect,
+{
+    inventory::iter::<NodeRegister<V>>
+        .into_iter()
+        .find(|reg| reg.node_type == node_type)
+        .map(|reg| reg.ports)
+}
+
+/// 查找节点类型声明的端口，节点类型没有在该消息类型下注册时返回 None
+pub fn node_ports(node_type: &str, message_type: MessageType) -> Option<NodePorts> {
+    match message_type {
+        MessageType::DVector => registered_ports::<na::DVector<f64>>(node_type),
+        MessageType::SVector7 => registered_ports::<na::SVector<f64, PANDA_DOF>>(node_type),
+        MessageType::F64 => registered_ports::<f64>(node_type),
+    }
 }
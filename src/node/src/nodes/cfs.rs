@@ -1,23 +1,45 @@
 use kernel_macro::node_registration;
 use nalgebra as na;
+use sensor::Sensor;
 use serde::Deserialize;
-use std::time::Duration;
-use tracing::info;
+use std::{sync::RwLock, time::Duration};
+use tracing::{info, warn};
 
-use crate::{utilities::*, Node, NodeBehavior, NodeExtBehavior, NodeRegister, NodeState};
-use message::{iso_to_vec, Constraint, DNodeMessage, NodeMessage, QuadraticProgramming};
-use robot::{DRobot, DSeriseRobot, Robot, RobotLock};
-use solver::{OsqpSolver, Solver};
+use crate::{
+    utilities::*, Node, NodeBehavior, NodeError, NodeExtBehavior, NodePorts, NodeRegister,
+    NodeState, Port, DEFAULT_INPUT, DEFAULT_OUTPUT,
+};
+use message::{
+    iso_to_vec, Constraint, DNodeMessage, MessageKind, NodeMessage, Problem, QuadraticProgramming,
+    SNodeMessage,
+};
+use robot::{
+    best_ik, DRobot, DSeriseRobot, IkParams, JacobianFrame, Robot, RobotLock, SPanda, SSeriseRobot,
+    PANDA_DOF,
+};
 
 pub type Cfs<R, V> = Node<CfsState<V>, CfsParams, RobotLock<R>, V>;
 
-#[node_registration("cfs")]
+/// 接收关节目标或末端位姿目标，输出避障后的关节轨迹
+pub const CFS_PORTS: NodePorts = NodePorts {
+    inputs: &[Port::new(
+        DEFAULT_INPUT,
+        &[MessageKind::Joint, MessageKind::Pose],
+    )],
+    outputs: &[Port::new(DEFAULT_OUTPUT, &[MessageKind::Joint])],
+};
+
+#[node_registration("cfs", ports = CFS_PORTS)]
 pub type DCfs = Cfs<DSeriseRobot, na::DVector<f64>>;
 pub type SCfs<R, const N: usize> = Cfs<R, na::SVector<f64, N>>;
+#[node_registration("cfs", ports = CFS_PORTS)]
+pub type SPandaCfs = Cfs<SPanda, na::SVector<f64, PANDA_DOF>>;
 
 #[derive(Default)]
 pub struct CfsState<V> {
     target: Option<NodeMessage<V>>,
+    /// 上一次求解得到的原始解与对偶解，作为下一次规划的初值
+    warm_start: Option<(Vec<f64>, Vec<f64>)>,
 }
 
 #[derive(Deserialize)]
@@ -26,160 +48,305 @@ pub struct CfsParams {
     ninterp: usize,
     niter: usize,
     cost_weight: Vec<f64>,
+    #[serde(deserialize_with = "deserialize_solver")]
     solver: String,
+    /// 障碍物与参考轨迹扫过区域的筛选距离，为空时考虑全部障碍物
+    #[serde(default)]
+    collision_range: Option<f64>,
 }
 
-impl NodeBehavior for DCfs {
-    fn update(&mut self) {
-        // 获取 robot 状态
-        let robot_read = self.robot.as_ref().unwrap().read().unwrap();
-        let ndof = robot_read.dof();
-        let q = robot_read.q();
-        let q_min_bound = robot_read.q_min_bound().as_slice().to_vec();
-        let q_max_bound = robot_read.q_max_bound().as_slice().to_vec();
+/// 末端位姿向量（见 iso_to_vec）及其对关节角的梯度，由几何雅可比矩阵解析求得
+fn end_pose_grad(
+    robot: &DSeriseRobot,
+    q: &na::DVector<f64>,
+) -> (na::DVector<f64>, na::DMatrix<f64>) {
+    let pose = robot.cul_end_pose(q);
+    let jacobian = robot.cul_jacobian(q, JacobianFrame::World);
+    // 单位四元数虚部对时间的导数为 0.5 (w I - [v×]) ω
+    let rate =
+        0.5 * (pose.rotation.w * na::Matrix3::identity() - pose.rotation.imag().cross_matrix());
+    let mut grad = na::DMatrix::zeros(6, q.len());
+    grad.rows_mut(0, 3).copy_from(&(rate * jacobian.rows(3, 3)));
+    grad.rows_mut(3, 3).copy_from(&jacobian.rows(0, 3));
+    (iso_to_vec(pose), grad)
+}
 
-        let currect_state = DNodeMessage::Joint(q.clone());
-        // println!("{}: currect_pose: {:?}", self.name(), robot_read.end_pose());
-        // println!("{}: currect_q: {:?}", self.name(), q);
+/// 一次 CFS 规划：以关节目标或逆运动学的解为终点参考，迭代求解带有碰撞约束的二次规划，
+/// 返回不含起点的关节轨迹，没有可行轨迹时返回 None。定长的机器人转换为变长的机器人后共用这一实现
+fn plan(
+    name: &str,
+    params: &CfsParams,
+    warm_start: &mut Option<(Vec<f64>, Vec<f64>)>,
+    robot: &DSeriseRobot,
+    sensor: &RwLock<Sensor>,
+    target: &DNodeMessage,
+) -> Option<Vec<na::DVector<f64>>> {
+    let ndof = robot.dof();
+    let q = robot.q();
+    let q_min_bound = robot.q_min_bound().as_slice().to_vec();
+    let q_max_bound = robot.q_max_bound().as_slice().to_vec();
 
-        // 检查当前状态是否达到储存的目标状态.
-        if let Some(target) = self.state.target.clone() {
-            if (target / currect_state).abs() < 1e-2 {
-                self.state.target = None
-            }
+    // 准备
+    let q_ref_list = match target {
+        NodeMessage::Joint(q_target) => lerp(&q, &vec![q_target.clone()], params.ninterp),
+        NodeMessage::Pose(pose) => {
+            // 以逆运动学的解作为终点参考，不收敛时保持当前关节角
+            let ik = best_ik(robot, pose, &IkParams::default());
+            info!(node = name, ik_residual = ik.residual);
+            let q_end = if ik.converged { ik.q } else { q.clone() };
+            lerp(&q, &vec![q_end], params.ninterp)
         }
-
-        // 获取 target, 如果两侧都没有消息那就说明任务完成了，皆大欢喜
-        // 这里的策略是完成当前任务优先
-        let target = self.state.target.clone().or_else(|| self.input_queue.pop());
-        if target.is_none() {
-            self.node_state = NodeState::Finished;
-            return;
+        _ => panic!("Cfs: Unsupported message type"),
+    };
+    // 给定筛选距离时只考虑参考轨迹扫过区域附近的障碍物
+    let swept = swept_aabb(
+        q_ref_list
+            .iter()
+            .flat_map(|q_ref| robot.cul_capsules(q_ref)),
+    );
+    let collision_objects = {
+        let sensor = sensor.read().unwrap();
+        match (params.collision_range, swept) {
+            (Some(range), Some(swept)) => sensor.collision_near(&swept, range),
+            _ => sensor.collision(),
         }
-        let target = target.unwrap();
-        info!(node = self.name.as_str(), input = ?target.as_slice());
+    };
+    let mut last_result = Vec::new();
+    let dim = (params.ninterp + 2) * ndof;
+    let h = get_optimize_function(dim, ndof, params.cost_weight.clone());
+    let f = na::DVector::<f64>::zeros(dim);
+    let mut optimizer: Option<Box<dyn solver::Solver + '_>> = None;
 
-        // 准备
-        let q_ref_list = match &target {
-            NodeMessage::Joint(q_target) => lerp(&q, &vec![q_target.clone()], self.params.ninterp),
-            NodeMessage::Pose(_) => lerp(&q, &vec![q.clone()], self.params.ninterp),
-            _ => panic!("Cfs: Unsupported message type"),
-        };
-        let collision_objects = self.sensor.as_ref().unwrap().read().unwrap().collision();
-        let mut last_result = Vec::new();
-        let dim = (self.params.ninterp + 2) * ndof;
+    for _ in 0..params.niter {
+        // =======  建立约束  =======
 
-        for _ in 0..self.params.niter {
-            // =======  建立约束  =======
+        // 提前分配空间
+        let mut constraints =
+            Constraint::CartesianProduct(0, 0, Vec::with_capacity(params.ninterp + 1));
 
-            // 提前分配空间
-            let mut constraints =
-                Constraint::CartesianProduct(0, 0, Vec::with_capacity(self.params.ninterp + 1));
+        // 起点位置的约束，这是绝对约束
+        constraints.push(Constraint::Equared(q.as_slice().to_vec()));
 
-            // 起点位置的约束，这是绝对约束
-            constraints.push(Constraint::Equared(q.as_slice().to_vec()));
+        // 中间过程中的约束
+        for q_ref in q_ref_list.iter() {
+            // 边界约束
+            let mut process_constraint =
+                Constraint::Rectangle(q_min_bound.clone(), q_max_bound.clone());
 
-            // 中间过程中的约束
-            for q_ref in q_ref_list.iter() {
-                // 边界约束
-                let mut process_constraint =
-                    Constraint::Rectangle(q_min_bound.clone(), q_max_bound.clone());
-
-                // 如果有障碍物的话，增加碰撞约束
-
-                for collision in &collision_objects {
-                    let func = |q: &na::DVector<f64>| robot_read.cul_dis_to_collision(q, collision);
-                    let (dis, grad) = robot_read.cul_func(q_ref, &func);
-                    // 过程中对每个障碍物的碰撞约束与关节角约束
+            // 如果有障碍物的话，对每个连杆与障碍物分别增加碰撞约束，梯度由最近点处的雅可比矩阵解析求得
+            for collision in &collision_objects {
+                for distance in robot.cul_link_distances(q_ref, collision) {
+                    let grad = robot.cul_distance_grad(q_ref, &distance);
                     process_constraint += Constraint::Halfspace(
                         (-&grad).as_slice().to_vec(),
-                        (dis - (&grad * q_ref))[(0, 0)],
+                        distance.result.distance - grad.dot(q_ref),
                     );
                 }
+            }
 
-                constraints.push(process_constraint);
+            // 自碰撞约束
+            for distance in robot.cul_self_distances(q_ref) {
+                let grad = robot.cul_pair_distance_grad(q_ref, &distance);
+                process_constraint += Constraint::Halfspace(
+                    (-&grad).as_slice().to_vec(),
+                    distance.result.distance - grad.dot(q_ref),
+                );
             }
 
-            // 终点约束
-            match target {
-                NodeMessage::Joint(ref q) => {
-                    constraints.push(Constraint::Equared(q.clone().as_slice().to_vec()));
-                }
-                NodeMessage::Pose(ref_pose) => {
-                    let mut end_constraint =
-                        Constraint::Rectangle(q_min_bound.clone(), q_max_bound.clone());
-
-                    let q_end_ref = if last_result.is_empty() {
-                        q.clone()
-                    } else {
-                        na::DVector::from_vec(last_result[last_result.len() - ndof..].to_vec())
-                    };
-
-                    // 在这里写一个测试，用于测试 求解器是否能迭代出一组合理的解
-                    // test_pose_constraint(ref_pose, &q_end_ref, &robot_read);
-
-                    let func = |q: &na::DVector<f64>| iso_to_vec(robot_read.cul_end_pose(q));
-                    let (value, grad) = robot_read.cul_func(&q_end_ref, &func);
-
-                    let b_bar = iso_to_vec(ref_pose) - value + &grad * q_end_ref;
-                    end_constraint += Constraint::Hyperplane(
-                        grad.nrows(),
-                        grad.ncols(),
-                        grad.as_slice().to_vec(),
-                        b_bar.as_slice().to_vec(),
-                    );
+            constraints.push(process_constraint);
+        }
 
-                    constraints.push(end_constraint);
-                }
-                _ => panic!("Cfs: Unsupported message type"),
+        // 终点约束
+        match target {
+            NodeMessage::Joint(q) => {
+                constraints.push(Constraint::Equared(q.as_slice().to_vec()));
             }
+            NodeMessage::Pose(ref_pose) => {
+                let mut end_constraint =
+                    Constraint::Rectangle(q_min_bound.clone(), q_max_bound.clone());
+
+                let q_end_ref = if last_result.is_empty() {
+                    q_ref_list.last().unwrap().clone()
+                } else {
+                    na::DVector::from_vec(last_result[last_result.len() - ndof..].to_vec())
+                };
+
+                let (value, grad) = end_pose_grad(robot, &q_end_ref);
 
-            // =======  优化  =======
-            let h = get_optimize_function(dim, ndof, self.params.cost_weight.clone());
-            let f = na::DVector::<f64>::zeros(dim);
+                let b_bar = iso_to_vec(*ref_pose) - value + &grad * q_end_ref;
+                end_constraint += Constraint::Hyperplane(
+                    grad.nrows(),
+                    grad.ncols(),
+                    grad.as_slice().to_vec(),
+                    b_bar.as_slice().to_vec(),
+                );
+
+                constraints.push(end_constraint);
+            }
+            _ => panic!("Cfs: Unsupported message type"),
+        }
+
+        // =======  优化  =======
+        // 第一次迭代时按名称从求解器注册表中创建求解器，之后只更新约束，沿用已有的分解与上一次的解
+        // 创建或更新失败与求解失败一样停止迭代，沿用上一次迭代的结果
+        if let Some(optimizer) = optimizer.as_mut() {
+            if let Err(error) = optimizer.update_constraints(constraints) {
+                warn!(node = name, "solver: {}", error);
+                break;
+            }
+        } else {
             let problem = QuadraticProgramming {
                 h: &h,
                 f: f.as_slice(),
                 constraints,
             };
-
-            let solver_result = match self.params.solver.as_str() {
-                "osqp" => {
-                    let mut osqp_solver = OsqpSolver::from_problem(problem);
-                    osqp_solver.solve()
+            let mut created =
+                match solver::factory(&params.solver, Problem::QuadraticProgramming(problem)) {
+                    Ok(created) => created,
+                    Err(error) => {
+                        warn!(node = name, "solver: {}", error);
+                        break;
+                    }
+                };
+            // 以上一次规划的解作为初值，维数不符时冷启动
+            if let Some((x, y)) = warm_start.as_ref() {
+                if let Err(error) = created.warm_start(x, y) {
+                    warn!(node = name, "solver: {}", error);
                 }
-                _ => unimplemented!(),
-            };
+            }
+            optimizer = Some(created);
+        }
+        let solver_result = optimizer.as_mut().unwrap().solve();
 
-            // 检查是否收敛，更新 q_ref_list
-
-            if last_result.is_empty() {
-                last_result = solver_result.clone();
-            } else {
-                // 一个比较严苛的收敛条件，学长使用的是平均值，而我采用的是轨迹的误差的和，可能会导致部分情况下收敛不通过
-                let diff: f64 = solver_result
-                    .iter()
-                    .zip(last_result.iter())
-                    .map(|(a, b)| (a - b).abs())
-                    .sum();
-                if diff.abs() < 1e-1 {
-                    break;
-                }
-                last_result = solver_result.clone();
+        // 求解失败时停止迭代，沿用上一次迭代的结果
+        if !solver_result.status.is_solved() {
+            warn!(node = name, "solver: {}", solver_result);
+            break;
+        }
+        info!(node = name, "solver: {}", solver_result);
+        *warm_start = Some((solver_result.x.clone(), solver_result.y));
+        let solver_result = solver_result.x;
+
+        // 检查是否收敛，更新 q_ref_list
+        if !last_result.is_empty() {
+            // 一个比较严苛的收敛条件，学长使用的是平均值，而我采用的是轨迹的误差的和，可能会导致部分情况下收敛不通过
+            let diff: f64 = solver_result
+                .iter()
+                .zip(last_result.iter())
+                .map(|(a, b)| (a - b).abs())
+                .sum();
+            if diff.abs() < 1e-1 {
+                break;
             }
         }
+        last_result = solver_result;
+    }
+    // 第一次求解就失败时没有可用的轨迹，放弃该目标
+    if last_result.is_empty() {
+        warn!(node = name, "no feasible trajectory, target dropped");
+        return None;
+    }
+
+    Some(
+        (1..params.ninterp + 2)
+            .map(|i| na::DVector::from_column_slice(&last_result[i * ndof..(i + 1) * ndof]))
+            .collect(),
+    )
+}
+
+impl NodeBehavior for DCfs {
+    fn update(&mut self) {
+        // 获取 robot 状态
+        let robot_read = self.robot.as_ref().unwrap().read().unwrap();
+        let currect_state = DNodeMessage::Joint(robot_read.q());
+
+        // 检查当前状态是否达到储存的目标状态.
+        if let Some(target) = self.state.target.clone() {
+            if (target / currect_state).abs() < 1e-2 {
+                self.state.target = None
+            }
+        }
+
+        // 获取 target, 如果两侧都没有消息那就说明任务完成了，皆大欢喜
+        // 这里的策略是完成当前任务优先
+        let target = self.state.target.clone().or_else(|| self.input_queue.pop());
+        let Some(target) = target else {
+            self.node_state = NodeState::Finished;
+            return;
+        };
+        info!(node = self.name.as_str(), input = ?target.as_slice());
+
+        let Some(track_list) = plan(
+            &self.name,
+            &self.params,
+            &mut self.state.warm_start,
+            &robot_read,
+            self.sensor.as_ref().unwrap(),
+            &target,
+        ) else {
+            return;
+        };
+
         // =======  轨迹发送  =======
-        // 生成 track
-        let mut track_list = Vec::new();
-        for i in 1..self.params.ninterp + 2 {
-            track_list.push(DNodeMessage::Joint(na::DVector::from_column_slice(
-                &last_result[i * ndof..(i + 1) * ndof],
-            )));
+        while self.output_queue.pop().is_some() {}
+        for track in track_list {
+            self.output_queue.push(DNodeMessage::Joint(track));
+        }
+    }
+
+    fn period(&self) -> Duration {
+        Duration::from_secs_f64(self.params.period)
+    }
+    fn node_name(&self) -> String {
+        self.name.clone()
+    }
+    fn state(&mut self) -> NodeState {
+        self.node_state
+    }
+}
+
+impl<const N: usize> NodeBehavior for SCfs<SSeriseRobot<N>, N>
+where
+    na::SVector<f64, N>: Default,
+{
+    fn update(&mut self) {
+        // 获取 robot 状态，规划在变长的机器人上进行
+        let robot = self.robot.as_ref().unwrap().read().unwrap().to_dynamic();
+        let q = na::SVector::<f64, N>::from_column_slice(robot.q().as_slice());
+
+        // 检查当前状态是否达到储存的目标状态.
+        if let Some(target) = self.state.target.clone() {
+            if (target / SNodeMessage::<N>::Joint(q)).abs() < 1e-2 {
+                self.state.target = None
+            }
         }
-        // 发送 track
+
+        // 获取 target, 如果两侧都没有消息那就说明任务完成了
+        let target = self.state.target.clone().or_else(|| self.input_queue.pop());
+        let Some(target) = target else {
+            self.node_state = NodeState::Finished;
+            return;
+        };
+        info!(node = self.name.as_str(), input = ?target.as_slice());
+
+        let Some(track_list) = plan(
+            &self.name,
+            &self.params,
+            &mut self.state.warm_start,
+            &robot,
+            self.sensor.as_ref().unwrap(),
+            &target.to_dynamic(),
+        ) else {
+            return;
+        };
+
+        // =======  轨迹发送  =======
         while self.output_queue.pop().is_some() {}
         for track in track_list {
-            self.output_queue.push(track);
+            self.output_queue
+                .push(SNodeMessage::<N>::Joint(na::SVector::from_column_slice(
+                    track.as_slice(),
+                )));
         }
     }
 
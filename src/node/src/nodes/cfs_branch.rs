@@ -4,12 +4,13 @@ use std::{
     sync::{Arc, RwLock},
     time::Duration,
 };
-use tracing::info;
+use tracing::{info, warn};
 
 use crate::{utilities::*, Node, NodeBehavior, NodeState};
-use message::{iso_to_vec, Constraint, DNodeMessage, NodeMessage, Pose, QuadraticProgramming};
+use message::{
+    iso_to_vec, Constraint, DNodeMessage, NodeMessage, Pose, Problem, QuadraticProgramming,
+};
 use robot::{DRobot, DSeriseRobot, Robot, RobotBranch};
-use solver::{OsqpSolver, Solver};
 
 pub type CfsBranch<R, V> = Node<CfsBranchState<V>, CfsBranchParams, RobotBranch<R>, V>;
 pub type DCfsBranch = CfsBranch<DSeriseRobot, na::DVector<f64>>;
@@ -17,6 +18,8 @@ pub type DCfsBranch = CfsBranch<DSeriseRobot, na::DVector<f64>>;
 #[derive(Default)]
 pub struct CfsBranchState<V> {
     target: Option<NodeMessage<V>>,
+    /// 上一次求解得到的原始解与对偶解，作为下一次规划的初值
+    warm_start: Option<(Vec<f64>, Vec<f64>)>,
 }
 
 #[derive(Deserialize)]
@@ -25,7 +28,11 @@ pub struct CfsBranchParams {
     ninterp: usize,
     niter: usize,
     cost_weight: Vec<f64>,
+    #[serde(deserialize_with = "deserialize_solver")]
     solver: String,
+    /// 障碍物与参考轨迹扫过区域的筛选距离，为空时考虑全部障碍物
+    #[serde(default)]
+    collision_range: Option<f64>,
 }
 
 impl NodeBehavior for DCfsBranch {
@@ -65,9 +72,24 @@ impl NodeBehavior for DCfsBranch {
             NodeMessage::Joint(q_target) => lerp(&q, &vec![q_target.clone()], self.params.ninterp),
             _ => lerp(&q, &vec![q.clone()], self.params.ninterp),
         };
-        let collision_objects = self.sensor.as_ref().unwrap().read().unwrap().collision();
+        // 给定筛选距离时只考虑参考轨迹扫过区域附近的障碍物
+        let swept = swept_aabb(
+            q_ref_list
+                .iter()
+                .flat_map(|q_ref| self.robot.cul_capsules(q_ref)),
+        );
+        let collision_objects = {
+            let sensor = self.sensor.as_ref().unwrap().read().unwrap();
+            match (self.params.collision_range, swept) {
+                (Some(range), Some(swept)) => sensor.collision_near(&swept, range),
+                _ => sensor.collision(),
+            }
+        };
         let mut last_result = Vec::new();
         let dim = (self.params.ninterp + 2) * ndof;
+        let h = get_optimize_function(dim, ndof, self.params.cost_weight.clone());
+        let f = na::DVector::<f64>::zeros(dim);
+        let mut optimizer: Option<Box<dyn solver::Solver + '_>> = None;
 
         for _ in 0..self.params.niter {
             // =======  建立约束  =======
@@ -87,15 +109,24 @@ impl NodeBehavior for DCfsBranch {
 
                 // 如果有障碍物的话，增加碰撞约束
                 for collision in &collision_objects {
-                    let func = |q: &na::DVector<f64>| self.robot.cul_dis_to_collision(q, collision);
-                    let (dis, grad) = self.robot.cul_func(q_ref, &func);
-                    // 过程中对每个障碍物的碰撞约束与关节角约束
+                    // 过程中对每个连杆与障碍物的碰撞约束
+                    for distance in self.robot.cul_link_distances(q_ref, collision) {
+                        let grad = self.robot.cul_distance_grad(q_ref, &distance);
+                        process_constraint += Constraint::Halfspace(
+                            (-&grad).as_slice().to_vec(),
+                            distance.result.distance - grad.dot(q_ref),
+                        );
+                    }
+                    // TODO 实际上虚构机器人也应该有碰撞检测，这代表约束实体的碰撞空间
+                }
+
+                // 各机器人的自碰撞约束以及机器人之间的碰撞约束
+                for distance in self.robot.cul_self_distances(q_ref) {
+                    let grad = self.robot.cul_pair_distance_grad(q_ref, &distance);
                     process_constraint += Constraint::Halfspace(
                         (-&grad).as_slice().to_vec(),
-                        (dis - (&grad * q_ref))[(0, 0)],
+                        distance.result.distance - grad.dot(q_ref),
                     );
-                    // TODO 实际上虚构机器人也应该有碰撞检测，这代表约束实体的碰撞空间
-                    // TODO 机器人之间的碰撞检测
                 }
 
                 // 过程中的任务约束
@@ -131,21 +162,46 @@ impl NodeBehavior for DCfsBranch {
             }
 
             // =======  优化器求解  =======
-            let h = get_optimize_function(dim, ndof, self.params.cost_weight.clone());
-            let f = na::DVector::<f64>::zeros(dim);
-            let problem = QuadraticProgramming {
-                h: &h,
-                f: f.as_slice(),
-                constraints,
-            };
-
-            let solver_result = match self.params.solver.as_str() {
-                "osqp" => {
-                    let mut osqp_solver = OsqpSolver::from_problem(problem);
-                    osqp_solver.solve()
+            // 第一次迭代时按名称从求解器注册表中创建求解器，之后只更新约束，沿用已有的分解与上一次的解
+            // 创建或更新失败与求解失败一样停止迭代，沿用上一次迭代的结果
+            if let Some(optimizer) = optimizer.as_mut() {
+                if let Err(error) = optimizer.update_constraints(constraints) {
+                    warn!(node = self.name.as_str(), "solver: {}", error);
+                    break;
                 }
-                _ => unimplemented!(),
-            };
+            } else {
+                let problem = QuadraticProgramming {
+                    h: &h,
+                    f: f.as_slice(),
+                    constraints,
+                };
+                let solver_type = &self.params.solver;
+                let mut created =
+                    match solver::factory(solver_type, Problem::QuadraticProgramming(problem)) {
+                        Ok(created) => created,
+                        Err(error) => {
+                            warn!(node = self.name.as_str(), "solver: {}", error);
+                            break;
+                        }
+                    };
+                // 以上一次规划的解作为初值，维数不符时冷启动
+                if let Some((x, y)) = &self.state.warm_start {
+                    if let Err(error) = created.warm_start(x, y) {
+                        warn!(node = self.name.as_str(), "solver: {}", error);
+                    }
+                }
+                optimizer = Some(created);
+            }
+            let solver_result = optimizer.as_mut().unwrap().solve();
+
+            // 求解失败时停止迭代，沿用上一次迭代的结果
+            if !solver_result.status.is_solved() {
+                warn!(node = self.name.as_str(), "solver: {}", solver_result);
+                break;
+            }
+            info!(node = self.name.as_str(), "solver: {}", solver_result);
+            self.state.warm_start = Some((solver_result.x.clone(), solver_result.y));
+            let solver_result = solver_result.x;
 
             // 检查是否收敛，更新 q_ref_list
             if last_result.is_empty() {
@@ -163,6 +219,15 @@ impl NodeBehavior for DCfsBranch {
                 last_result = solver_result.clone();
             }
         }
+        // 第一次求解就失败时没有可用的轨迹，放弃该目标
+        if last_result.is_empty() {
+            warn!(
+                node = self.name.as_str(),
+                "no feasible trajectory, target dropped"
+            );
+            return;
+        }
+
         // =======  轨迹发送  =======
         // 生成 track
         let mut track_list = Vec::new();
@@ -4,13 +4,32 @@ use serde::Deserialize;
 use std::time::Duration;
 use tracing::info;
 
-use crate::{Node, NodeBehavior, NodeExtBehavior, NodeRegister};
-use message::DNodeMessage;
+use crate::{
+    Node, NodeBehavior, NodeError, NodeExtBehavior, NodePorts, NodeRegister, Port, DEFAULT_INPUT,
+    DEFAULT_OUTPUT,
+};
+use message::{DNodeMessage, MessageKind};
 
 use robot::{DSeriseRobot, Robot, RobotLock};
 pub type Impedence<R, M, V> = Node<ImpedenceState<V>, ImpedenceParams<M>, RobotLock<R>, V>;
 
-#[node_registration("impedence")]
+/// "in" 接收参考轨迹，"force" 接收叠加在输出上的前馈关节力矩，输出关节力矩
+pub const IMPEDENCE_PORTS: NodePorts = NodePorts {
+    inputs: &[
+        Port::new(
+            DEFAULT_INPUT,
+            &[
+                MessageKind::Joint,
+                MessageKind::JointVel,
+                MessageKind::JointVelAcc,
+            ],
+        ),
+        Port::new("force", &[MessageKind::Tau]),
+    ],
+    outputs: &[Port::new(DEFAULT_OUTPUT, &[MessageKind::Tau])],
+};
+
+#[node_registration("impedence", ports = IMPEDENCE_PORTS)]
 pub type DImpedence = Impedence<DSeriseRobot, na::DMatrix<f64>, na::DVector<f64>>;
 pub type SImpedence<R, const N: usize> = Impedence<R, na::SMatrix<f64, N, N>, na::SVector<f64, N>>;
 pub type DImpedenceDiag = Impedence<DSeriseRobot, na::DVector<f64>, na::DVector<f64>>;
@@ -20,6 +39,7 @@ pub struct ImpedenceState<V> {
     ref_q: V,
     ref_q_dot: V,
     ref_q_ddot: V,
+    ref_tau: Option<V>,
 }
 
 #[derive(Deserialize)]
@@ -47,6 +67,11 @@ impl NodeBehavior for DImpedence {
 
         // TODO 检查任务是否完成
 
+        if let Some(DNodeMessage::Tau(ref_tau)) = self.input("force").and_then(|queue| queue.pop())
+        {
+            self.state.ref_tau = Some(ref_tau);
+        }
+
         match self.input_queue.pop() {
             Some(DNodeMessage::Joint(ref_q)) => {
                 self.state.ref_q = ref_q;
@@ -64,9 +89,12 @@ impl NodeBehavior for DImpedence {
         };
 
         // 执行 impedance 逻辑
-        let output = &self.params.k * (&self.state.ref_q - q)
+        let mut output = &self.params.k * (&self.state.ref_q - q)
             + &self.params.b * (&self.state.ref_q_dot - &q_dot)
             + &self.params.m * (&self.state.ref_q_ddot - &q_ddot);
+        if let Some(ref_tau) = &self.state.ref_tau {
+            output += ref_tau;
+        }
 
         let control_message = DNodeMessage::Tau(output);
 
@@ -86,6 +114,10 @@ impl NodeBehavior for DImpedence {
     fn period(&self) -> Duration {
         Duration::from_secs_f64(self.params.period)
     }
+
+    fn node_name(&self) -> String {
+        self.name.clone()
+    }
 }
 
 impl NodeBehavior for DImpedenceDiag {
@@ -1,18 +1,38 @@
 use kernel_macro::node_registration;
 use nalgebra as na;
 use serde::Deserialize;
-use std::time::Duration;
+use std::{
+    ops::{Add, Div, Mul, Sub},
+    time::Duration,
+};
 use tracing::info;
 
-use crate::{utilities::lerp, Node, NodeBehavior, NodeExtBehavior, NodeRegister};
-use message::{DNodeMessage, NodeMessage};
-use robot::{DSeriseRobot, Robot, RobotLock};
+use crate::{
+    utilities::lerp, Node, NodeBehavior, NodeError, NodeExtBehavior, NodePorts, NodeRegister, Port,
+    DEFAULT_INPUT, DEFAULT_OUTPUT,
+};
+use message::{MessageKind, NodeMessage};
+use robot::{DSeriseRobot, Robot, RobotLock, SPanda, SSeriseRobot, PANDA_DOF};
 
 pub type Interp<R, V> = Node<InterpState<V>, InterpParams, RobotLock<R>, V>;
 
-#[node_registration("interp")]
+/// 接收单个关节目标或关节路点，输出插值后的关节轨迹
+pub const INTERP_PORTS: NodePorts = NodePorts {
+    inputs: &[Port::new(
+        DEFAULT_INPUT,
+        &[MessageKind::Joint, MessageKind::JointList],
+    )],
+    outputs: &[Port::new(DEFAULT_OUTPUT, &[MessageKind::Joint])],
+};
+
+#[node_registration("interp", ports = INTERP_PORTS)]
 pub type DInterp = Interp<DSeriseRobot, na::DVector<f64>>;
 pub type SInterp<R, const N: usize> = Interp<R, na::SVector<f64, N>>;
+#[node_registration("interp", ports = INTERP_PORTS)]
+pub type SPandaInterp = Interp<SPanda, na::SVector<f64, PANDA_DOF>>;
+
+/// 插值函数，输入起点、路点与每两点之间的插值数量
+type InterpFn<V> = fn(&V, &Vec<V>, usize) -> Vec<V>;
 
 #[derive(Default)]
 pub struct InterpState<V> {
@@ -27,43 +47,76 @@ pub struct InterpParams {
     ninter: usize,
 }
 
-impl NodeBehavior for DInterp {
-    fn update(&mut self) {
-        // 获取当前 robot 状态
-        let robot_read = self.robot.as_ref().unwrap().read().unwrap();
-        let q = robot_read.q();
-        // TODO 需要在此处检查任务是否完成，如果未完成则无需从队列中取出新的目标，而是应当继续执行当前目标
-
-        let interp_fn = match self.params.interp_fn.as_str() {
+impl<R, V> Interp<R, V>
+where
+    R: Robot<V>,
+    V: Default
+        + Clone
+        + Add<Output = V>
+        + Sub<Output = V>
+        + Mul<f64, Output = V>
+        + Div<f64, Output = V>,
+{
+    /// 获取新的目标，插值函数未知或没有新目标时返回 None
+    fn next_target(&mut self) -> Option<(InterpFn<V>, NodeMessage<V>)> {
+        let interp_fn: InterpFn<V> = match self.params.interp_fn.as_str() {
             "lerp" => lerp,
-            _ => return,
+            _ => return None,
         };
+        let target = self.input_queue.pop()?;
+        self.state.target = Some(target.clone());
+        Some((interp_fn, target))
+    }
 
-        // 根据不同的Target生成插值轨迹，每两点之间的插值数量为 ninter
-        if let Some(target) = self.input_queue.pop() {
-            self.state.target = Some(target);
-        } else {
-            return;
-        }
-
-        let target = self.state.target.clone().unwrap();
-        info!(node = self.node_name().as_str(), input = ?target.as_slice());
-
+    /// 根据不同的 Target 生成插值轨迹并替换输出队列中尚未执行的轨迹，每两点之间的插值数量为 ninter
+    fn send_track(&mut self, interp_fn: InterpFn<V>, target: NodeMessage<V>) {
+        let q = self.robot.as_ref().unwrap().read().unwrap().q();
         let track_list = match target {
-            DNodeMessage::Joint(joint) => {
-                interp_fn(&(0.3 * &q + 0.7 * &joint), &vec![joint], self.params.ninter)
-            }
-            DNodeMessage::JointList(joint_list) => interp_fn(&q, &joint_list, self.params.ninter),
+            NodeMessage::Joint(joint) => interp_fn(
+                &(q * 0.3 + joint.clone() * 0.7),
+                &vec![joint],
+                self.params.ninter,
+            ),
+            NodeMessage::JointList(joint_list) => interp_fn(&q, &joint_list, self.params.ninter),
             _ => return,
         };
-        // 将 track_list 中的轨迹放入 track_queue 中
         while self.output_queue.pop().is_some() {}
         for track in track_list {
-            let control_message = DNodeMessage::Joint(track);
-
-            self.output_queue.push(control_message);
+            self.output_queue.push(NodeMessage::Joint(track));
         }
     }
+}
+
+impl NodeBehavior for DInterp {
+    fn update(&mut self) {
+        // TODO 需要在此处检查任务是否完成，如果未完成则无需从队列中取出新的目标，而是应当继续执行当前目标
+        let Some((interp_fn, target)) = self.next_target() else {
+            return;
+        };
+        info!(node = self.node_name().as_str(), input = ?target.as_slice());
+        self.send_track(interp_fn, target);
+    }
+
+    fn period(&self) -> Duration {
+        Duration::from_secs_f64(self.params.period)
+    }
+
+    fn node_name(&self) -> String {
+        self.name.clone()
+    }
+}
+
+impl<const N: usize> NodeBehavior for SInterp<SSeriseRobot<N>, N>
+where
+    na::SVector<f64, N>: Default,
+{
+    fn update(&mut self) {
+        let Some((interp_fn, target)) = self.next_target() else {
+            return;
+        };
+        info!(node = self.node_name().as_str(), input = ?target.as_slice());
+        self.send_track(interp_fn, target);
+    }
 
     fn period(&self) -> Duration {
         Duration::from_secs_f64(self.params.period)
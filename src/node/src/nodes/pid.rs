@@ -1,17 +1,29 @@
 use kernel_macro::node_registration;
 use nalgebra as na;
-use serde::Deserialize;
+use serde::{de::DeserializeOwned, Deserialize};
 use std::{f64, time::Duration};
 
-use crate::{Node, NodeBehavior, NodeExtBehavior, NodeRegister};
-use message::DNodeMessage;
-use robot::{DSeriseRobot, Robot, RobotLock};
+use crate::{
+    Node, NodeBehavior, NodeError, NodeExtBehavior, NodePorts, NodeRegister, Port, DEFAULT_INPUT,
+    DEFAULT_OUTPUT,
+};
+use message::{MessageKind, NodeMessage};
+use robot::{DSeriseRobot, Robot, RobotLock, SPanda, SSeriseRobot, PANDA_DOF};
 
 pub type Pid<R, M, V> = Node<PidState<V>, PidParams<M>, RobotLock<R>, V>;
 
-#[node_registration("pid")]
+/// 跟踪关节轨迹，输出关节指令
+pub const PID_PORTS: NodePorts = NodePorts {
+    inputs: &[Port::new(DEFAULT_INPUT, &[MessageKind::Joint])],
+    outputs: &[Port::new(DEFAULT_OUTPUT, &[MessageKind::Joint])],
+};
+
+#[node_registration("pid", ports = PID_PORTS)]
 pub type DPid = Pid<DSeriseRobot, na::DMatrix<f64>, na::DVector<f64>>;
 pub type SPid<R, const N: usize> = Pid<R, na::SMatrix<f64, N, N>, na::SVector<f64, N>>;
+#[node_registration("pid", ports = PID_PORTS)]
+pub type SPandaPid =
+    Pid<SPanda, na::SMatrix<f64, PANDA_DOF, PANDA_DOF>, na::SVector<f64, PANDA_DOF>>;
 pub type DPidDiag = Pid<DSeriseRobot, na::DVector<f64>, na::DVector<f64>>;
 
 #[derive(Default)]
@@ -30,44 +42,66 @@ pub struct PidParams<M> {
     kd: M,
 }
 
-impl NodeBehavior for DPid {
-    fn init(&mut self) {
+impl<R, M, D> Pid<R, M, na::OVector<f64, D>>
+where
+    R: Robot<na::OVector<f64, D>>,
+    PidParams<M>: DeserializeOwned,
+    D: na::Dim,
+    na::DefaultAllocator: na::allocator::Allocator<D>,
+    na::OVector<f64, D>: Default,
+{
+    /// 将跟踪目标与误差项清零，维度与机器人的自由度一致
+    fn reset(&mut self) {
         let dof = self.robot.as_ref().unwrap().read().unwrap().dof();
-        self.state.track = na::DVector::from_element(dof, 0.0);
-        self.state.error = na::DVector::from_element(dof, 0.0);
-        self.state.integral = na::DVector::from_element(dof, 0.0);
-        self.state.derivative = na::DVector::from_element(dof, 0.0);
+        let zeros = na::OVector::<f64, D>::zeros_generic(D::from_usize(dof), na::U1);
+        self.state.track = zeros.clone();
+        self.state.error = zeros.clone();
+        self.state.integral = zeros.clone();
+        self.state.derivative = zeros;
     }
 
-    fn update(&mut self) {
-        // 获取 robot 状态
-        let robot_read = self.robot.as_ref().unwrap().read().unwrap();
-        let q = robot_read.q();
-        drop(robot_read);
-        // 获取输入
-        if let Some(DNodeMessage::Joint(track)) = self.input_queue.pop() {
+    /// 获取新的跟踪目标，并更新误差、积分与微分项
+    fn update_error(&mut self) {
+        let q = self.robot.as_ref().unwrap().read().unwrap().q();
+        if let Some(NodeMessage::Joint(track)) = self.input_queue.pop() {
             self.state.track = track;
         }
 
-        // 执行 pid 逻辑
         let new_error = &self.state.track - &q;
         self.state.integral += &new_error * self.params.period;
         self.state.derivative = (&new_error - &self.state.error) / self.params.period;
         self.state.error = new_error;
+    }
 
-        let output = &self.params.kp * &self.state.error
-            + &self.params.ki * &self.state.integral
-            + &self.params.kd * &self.state.derivative;
-
-        let control_message = DNodeMessage::Joint(output);
-
-        // 发送控制指令
+    /// 发送控制指令，系统末端的节点直接写入机器人
+    fn send(&mut self, output: na::OVector<f64, D>) {
+        let control_message = NodeMessage::Joint(output);
         if self.is_end {
-            todo!();
+            self.robot
+                .as_ref()
+                .unwrap()
+                .write()
+                .unwrap()
+                .set_control_message(control_message);
         } else {
             self.output_queue.push(control_message);
         }
     }
+}
+
+impl NodeBehavior for DPid {
+    fn init(&mut self) {
+        self.reset();
+    }
+
+    fn update(&mut self) {
+        // 执行 pid 逻辑
+        self.update_error();
+        let output = &self.params.kp * &self.state.error
+            + &self.params.ki * &self.state.integral
+            + &self.params.kd * &self.state.derivative;
+        self.send(output);
+    }
 
     fn period(&self) -> Duration {
         Duration::from_secs_f64(self.params.period)
@@ -80,44 +114,45 @@ impl NodeBehavior for DPid {
 
 impl NodeBehavior for DPidDiag {
     fn init(&mut self) {
-        let dof = self.robot.as_ref().unwrap().read().unwrap().dof();
-        self.state.track = na::DVector::from_element(dof, 0.0);
-        self.state.error = na::DVector::from_element(dof, 0.0);
-        self.state.integral = na::DVector::from_element(dof, 0.0);
-        self.state.derivative = na::DVector::from_element(dof, 0.0);
+        self.reset();
     }
 
     fn update(&mut self) {
-        // 获取 robot 状态
-        let robot_read = self.robot.as_ref().unwrap().read().unwrap();
-        let q = robot_read.q();
-        drop(robot_read);
-
-        if let Some(DNodeMessage::Joint(track)) = self.input_queue.pop() {
-            self.state.track = track;
-        }
-
         let kp = na::DMatrix::from_diagonal(&self.params.kp);
         let ki = na::DMatrix::from_diagonal(&self.params.ki);
         let kd = na::DMatrix::from_diagonal(&self.params.kd);
 
         // 执行 pid 逻辑
-        let new_error = &self.state.track - &q;
-        self.state.integral += &new_error * self.params.period;
-        self.state.derivative = (&new_error - &self.state.error) / self.params.period;
-        self.state.error = new_error;
-
+        self.update_error();
         let output =
             &kp * &self.state.error + &ki * &self.state.integral + &kd * &self.state.derivative;
+        self.send(output);
+    }
 
-        let control_message = DNodeMessage::Joint(output);
+    fn period(&self) -> Duration {
+        Duration::from_secs_f64(self.params.period)
+    }
 
-        // 发送控制指令
-        if self.is_end {
-            todo!();
-        } else {
-            self.output_queue.push(control_message);
-        }
+    fn node_name(&self) -> String {
+        self.name.clone()
+    }
+}
+
+impl<const N: usize> NodeBehavior for SPid<SSeriseRobot<N>, N>
+where
+    na::SVector<f64, N>: Default,
+{
+    fn init(&mut self) {
+        self.reset();
+    }
+
+    fn update(&mut self) {
+        // 执行 pid 逻辑
+        self.update_error();
+        let output = self.params.kp * self.state.error
+            + self.params.ki * self.state.integral
+            + self.params.kd * self.state.derivative;
+        self.send(output);
     }
 
     fn period(&self) -> Duration {
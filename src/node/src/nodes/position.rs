@@ -4,20 +4,23 @@ use serde::Deserialize;
 use std::f64;
 use std::time::Duration;
 
-use crate::{Node, NodeBehavior, NodeExtBehavior, NodeRegister};
+use crate::{Node, NodeBehavior, NodeError, NodeExtBehavior, NodeRegister};
+use robot::PANDA_DOF;
 
 pub type Position<V> = Node<(), PositionParams, (), V>;
 
 #[node_registration("position")]
 pub type DPosition = Position<na::DVector<f64>>;
 pub type SPosition<const N: usize> = Position<na::SVector<f64, N>>;
+#[node_registration("position")]
+pub type SPandaPosition = Position<na::SVector<f64, PANDA_DOF>>;
 
 #[derive(Deserialize)]
 pub struct PositionParams {
     period: f64,
 }
 
-impl NodeBehavior for DPosition {
+impl<V: Clone + Send + Sync> NodeBehavior for Position<V> {
     fn update(&mut self) {
         if let Some(control_message) = self.input_queue.pop() {
             if self.is_end {
@@ -2,7 +2,7 @@ use kernel_macro::node_registration;
 use nalgebra as na;
 use serde::Deserialize;
 
-use crate::{Node, NodeBehavior, NodeExtBehavior, NodeRegister};
+use crate::{Node, NodeBehavior, NodeError, NodeExtBehavior, NodeRegister};
 use robot::{Gripper, RobotLock};
 
 #[node_registration("gripper_plant")]
@@ -0,0 +1,109 @@
+use std::fmt;
+
+use message::{MessageKind, NodeMessage, NodeMessageQueue};
+
+/// 默认输入端口，对应 `Node::input_queue`
+pub const DEFAULT_INPUT: &str = "in";
+/// 默认输出端口，对应 `Node::output_queue`
+pub const DEFAULT_OUTPUT: &str = "out";
+
+/// 节点的一个具名端口，kinds 为空时接受任意种类的消息
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Port {
+    pub name: &'static str,
+    pub kinds: &'static [MessageKind],
+}
+
+impl Port {
+    pub const fn new(name: &'static str, kinds: &'static [MessageKind]) -> Self {
+        Port { name, kinds }
+    }
+
+    /// 端口是否接受该种类的消息
+    pub fn accepts(&self, kind: MessageKind) -> bool {
+        self.kinds.is_empty() || self.kinds.contains(&kind)
+    }
+
+    /// 输出端口发出的消息是否可能被输入端口接受
+    pub fn compatible(&self, input: &Port) -> bool {
+        self.kinds.is_empty() || self.kinds.iter().any(|&kind| input.accepts(kind))
+    }
+}
+
+impl fmt::Display for Port {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.kinds.is_empty() {
+            return write!(f, "{} (any)", self.name);
+        }
+        let kinds: Vec<String> = self.kinds.iter().map(|kind| kind.to_string()).collect();
+        write!(f, "{} ({})", self.name, kinds.join("|"))
+    }
+}
+
+/// 节点类型声明的输入与输出端口，随节点一起注册
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct NodePorts {
+    pub inputs: &'static [Port],
+    pub outputs: &'static [Port],
+}
+
+impl NodePorts {
+    /// 未声明端口的节点只有接受任意消息的 "in" 与 "out"
+    pub const DEFAULT: NodePorts = NodePorts {
+        inputs: &[Port::new(DEFAULT_INPUT, &[])],
+        outputs: &[Port::new(DEFAULT_OUTPUT, &[])],
+    };
+
+    pub fn input(&self, name: &str) -> Option<&Port> {
+        self.inputs.iter().find(|port| port.name == name)
+    }
+
+    pub fn output(&self, name: &str) -> Option<&Port> {
+        self.outputs.iter().find(|port| port.name == name)
+    }
+}
+
+impl Default for NodePorts {
+    fn default() -> Self {
+        NodePorts::DEFAULT
+    }
+}
+
+/// 输出端口，一个端口可以连接多条边，发送的消息会广播到每一条边的队列中
+pub struct OutputQueue<V> {
+    queues: Vec<NodeMessageQueue<V>>,
+}
+
+impl<V> Default for OutputQueue<V> {
+    fn default() -> Self {
+        OutputQueue { queues: Vec::new() }
+    }
+}
+
+impl<V> OutputQueue<V> {
+    pub fn connect(&mut self, queue: NodeMessageQueue<V>) {
+        self.queues.push(queue);
+    }
+
+    pub fn is_connected(&self) -> bool {
+        !self.queues.is_empty()
+    }
+
+    /// 从每一条边的队列中各取出一条消息，返回最后取出的消息，通常用于清空尚未被下游取走的旧消息
+    pub fn pop(&self) -> Option<NodeMessage<V>> {
+        self.queues
+            .iter()
+            .fold(None, |popped, queue| queue.pop().or(popped))
+    }
+}
+
+impl<V: Clone> OutputQueue<V> {
+    pub fn push(&self, message: NodeMessage<V>) {
+        if let Some((last, rest)) = self.queues.split_last() {
+            for queue in rest {
+                queue.push(message.clone());
+            }
+            last.push(message);
+        }
+    }
+}
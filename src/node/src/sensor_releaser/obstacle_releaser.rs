@@ -3,13 +3,21 @@ use kernel_macro::node_registration;
 use nalgebra as na;
 use tracing::info;
 
-use crate::{Node, NodeBehavior, NodeExtBehavior, NodeRegister};
-use message::{Pose, Target};
+use crate::{
+    Node, NodeBehavior, NodeError, NodeExtBehavior, NodePorts, NodeRegister, Port, DEFAULT_INPUT,
+};
+use message::{MessageKind, Pose, Target};
 use sensor::Sensor;
 use serde::Deserialize;
 
 pub type ObstacleReleaser<V> = Node<ObstacleReleaserState, ObstacleReleaserParams, (), V>;
-#[node_registration("obstacle_releaser")]
+
+/// 接收障碍物的位姿变换并直接写入传感器，没有输出端口
+pub const OBSTACLE_RELEASER_PORTS: NodePorts = NodePorts {
+    inputs: &[Port::new(DEFAULT_INPUT, &[MessageKind::Transform])],
+    outputs: &[],
+};
+#[node_registration("obstacle_releaser", ports = OBSTACLE_RELEASER_PORTS)]
 pub type DObstacleReleaser = ObstacleReleaser<na::DVector<f64>>;
 
 #[derive(Default)]
@@ -58,7 +66,7 @@ impl NodeBehavior for DObstacleReleaser {
         // 输出当前障碍物列表
         if let Some(sensor) = &self.sensor {
             let Sensor::ObstacleList(obstacle_list) = &*sensor.read().unwrap();
-            for obstacle in &obstacle_list.obstacle {
+            for obstacle in obstacle_list.obstacle() {
                 info!(
                     node = format!("{}:{}", self.name, obstacle.id()),
                     obstacle = ?obstacle.as_slice()
@@ -8,7 +8,7 @@ use std::{process::Command, sync::Mutex};
 use tracing::info;
 use zmq;
 
-use crate::{Node, NodeBehavior, NodeExtBehavior, NodeRegister, NodeState};
+use crate::{Node, NodeBehavior, NodeError, NodeExtBehavior, NodeRegister, NodeState};
 use message::RobotState;
 
 #[node_registration("bullet")]
@@ -152,6 +152,10 @@ impl NodeBehavior for Bullet {
         std::time::Duration::from_secs_f64(self.params.period)
     }
 
+    fn node_name(&self) -> String {
+        self.name.clone()
+    }
+
     fn state(&mut self) -> crate::NodeState {
         self.node_state
     }
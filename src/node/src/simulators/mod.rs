@@ -1,3 +1,9 @@
 pub mod bullet;
+pub mod simulator;
 
 pub use bullet::Bullet;
+pub use simulator::{Contact, JointModel, Simulator, SIMULATOR_PORTS};
+
+/// 仿真器节点类型。仿真器代替真实机器人积分运动并写回机器人状态，只执行其他节点下发的指令，
+/// 自身不争夺指令权，因此不需要获取机器人租约
+pub const SIMULATOR_TYPES: &[&str] = &["simulator", "bullet"];
@@ -0,0 +1,290 @@
+use kernel_macro::node_registration;
+use nalgebra as na;
+use robot::{DRobot, DSeriseRobot, Robot};
+use serde::Deserialize;
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+use tracing::{info, warn};
+
+use crate::{
+    Node, NodeBehavior, NodeError, NodeExtBehavior, NodePorts, NodeRegister, NodeState, Port,
+    DEFAULT_INPUT,
+};
+use message::{CollisionObject, DNodeMessage, MessageKind};
+
+/// "in" 接收按机器人顺序拼接的关节指令，没有输入时使用各个机器人中保存的控制指令
+pub const SIMULATOR_PORTS: NodePorts = NodePorts {
+    inputs: &[Port::new(
+        DEFAULT_INPUT,
+        &[
+            MessageKind::Joint,
+            MessageKind::JointVel,
+            MessageKind::JointVelAcc,
+            MessageKind::Tau,
+        ],
+    )],
+    outputs: &[],
+};
+
+/// 纯 Rust 实现的关节空间仿真器，替代需要 python 与 pybullet 的 bullet 节点，
+/// 默认使用机器人的刚体动力学模型积分，也可以选择各个关节相互解耦的简化模型
+#[node_registration("simulator", ports = SIMULATOR_PORTS)]
+pub type Simulator =
+    Node<SimulatorState, SimulatorParams, Vec<Arc<RwLock<DSeriseRobot>>>, na::DVector<f64>>;
+
+#[derive(Default)]
+pub struct SimulatorState {
+    /// 通过输入端口收到的最新指令
+    command: Option<DNodeMessage>,
+    /// 当前与障碍物接触的连杆
+    contacts: Vec<Contact>,
+}
+
+#[derive(Deserialize)]
+pub struct SimulatorParams {
+    /// 节点周期，每个周期仿真时间推进一个周期，周期为 0 时推进一个积分步长
+    period: f64,
+    /// 积分步长
+    #[serde(default = "default_dt")]
+    dt: f64,
+    /// 位置指令与速度指令使用的伺服刚度与阻尼
+    #[serde(default = "default_kp")]
+    kp: f64,
+    #[serde(default = "default_kd")]
+    kd: f64,
+    /// 关节运动模型，缺省为机器人的刚体动力学模型
+    #[serde(default)]
+    model: JointModel,
+    /// 解耦模型中每个关节的等效惯量，刚体动力学模型不使用该参数
+    #[serde(default = "default_inertia")]
+    inertia: f64,
+    /// 关节的粘滞摩擦系数
+    #[serde(default)]
+    damping: f64,
+    /// 连杆与障碍物的距离小于该值时视为接触
+    #[serde(default)]
+    contact_margin: f64,
+}
+
+/// 仿真器的关节运动模型
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum JointModel {
+    /// 由机器人的正动力学求关节加速度，包含重力、科氏力以及关节之间的耦合惯量，
+    /// 位置与速度指令经过计算力矩法转换为力矩
+    #[default]
+    RigidBody,
+    /// 各个关节视为惯量为 inertia 的独立刚体，关节加速度为 (tau - damping * q_dot) / inertia，
+    /// 忽略重力、科氏力以及关节之间的耦合惯量
+    Decoupled,
+}
+
+fn default_dt() -> f64 {
+    0.001
+}
+
+fn default_kp() -> f64 {
+    100.0
+}
+
+fn default_kd() -> f64 {
+    20.0
+}
+
+fn default_inertia() -> f64 {
+    1.0
+}
+
+/// 机器人连杆与障碍物之间的一次接触
+#[derive(Debug, Clone, PartialEq)]
+pub struct Contact {
+    pub robot: String,
+    pub link: usize,
+    pub obstacle: usize,
+    pub distance: f64,
+}
+
+impl Simulator {
+    pub fn contacts(&self) -> &[Contact] {
+        &self.state.contacts
+    }
+
+    /// 由控制指令计算关节力矩，位置与速度指令经过伺服转换为期望的关节加速度，
+    /// 刚体动力学模型再由逆动力学补偿重力、科氏力与耦合惯量，没有指令时只做补偿
+    fn torque(&self, robot: &DSeriseRobot, command: &DNodeMessage) -> na::DVector<f64> {
+        let (kp, kd) = (self.params.kp, self.params.kd);
+        let q = &robot.state.q;
+        let q_dot = &robot.state.q_dot;
+        let zeros = na::DVector::zeros(robot.dof());
+        // 期望的关节加速度分为参考加速度与伺服修正两部分，解耦模型中伺服修正直接作为力矩；
+        // 力矩指令没有参考加速度，原样作为力矩
+        let (ref_q_ddot, servo) = match command {
+            DNodeMessage::Tau(tau) => (None, tau.clone()),
+            DNodeMessage::Joint(ref_q) => (Some(zeros), kp * (ref_q - q) - kd * q_dot),
+            DNodeMessage::JointVel(ref_q, ref_q_dot) => {
+                (Some(zeros), kp * (ref_q - q) + kd * (ref_q_dot - q_dot))
+            }
+            DNodeMessage::JointVelAcc(ref_q, ref_q_dot, ref_q_ddot) => (
+                Some(ref_q_ddot.clone()),
+                kp * (ref_q - q) + kd * (ref_q_dot - q_dot),
+            ),
+            _ => (Some(zeros.clone()), zeros),
+        };
+        let tau = match (ref_q_ddot, self.params.model) {
+            (None, _) => servo,
+            (Some(ref_q_ddot), JointModel::Decoupled) => self.params.inertia * ref_q_ddot + servo,
+            (Some(ref_q_ddot), JointModel::RigidBody) => {
+                robot.cul_inverse_dynamics(q, q_dot, &(ref_q_ddot + servo))
+            }
+        };
+        let tau_bound = &robot.params.tau_bound;
+        tau.zip_map(tau_bound, |tau, bound| tau.clamp(-bound, bound))
+    }
+
+    /// 半隐式欧拉积分一个步长，并施加速度与位置限位，到达位置限位的关节速度归零
+    fn integrate(&self, robot: &mut DSeriseRobot, tau: &na::DVector<f64>, dt: f64) {
+        let tau = tau - self.params.damping * &robot.state.q_dot;
+        let q_ddot = match self.params.model {
+            JointModel::RigidBody => {
+                robot.cul_forward_dynamics(&robot.state.q, &robot.state.q_dot, &tau)
+            }
+            JointModel::Decoupled => tau / self.params.inertia,
+        };
+        let params = &robot.params;
+        let mut q_dot = (&robot.state.q_dot + &q_ddot * dt)
+            .zip_map(&params.q_dot_bound, |q_dot, bound| {
+                q_dot.clamp(-bound, bound)
+            });
+        let mut q = &robot.state.q + &q_dot * dt;
+        for i in 0..q.len() {
+            let clamped = q[i].clamp(params.q_min_bound[i], params.q_max_bound[i]);
+            if clamped != q[i] {
+                q[i] = clamped;
+                q_dot[i] = 0.0;
+            }
+        }
+        robot.set_q(q);
+        robot.set_q_dot(q_dot);
+        robot.set_q_ddot(q_ddot);
+    }
+
+    /// 检查所有机器人连杆与障碍物之间的接触
+    fn detect_contacts(&self, obstacles: &[CollisionObject]) -> Vec<Contact> {
+        let mut contacts = Vec::new();
+        for robot in self.robot.iter() {
+            let robot_read = robot.read().unwrap();
+            for (link, capsule) in robot_read.capsules().into_iter().enumerate() {
+                let capsule = CollisionObject::Capsule(capsule);
+                for obstacle in obstacles {
+                    let distance = CollisionObject::get_distance(&capsule, obstacle);
+                    if distance <= self.params.contact_margin {
+                        contacts.push(Contact {
+                            robot: robot_read.name(),
+                            link,
+                            obstacle: obstacle.id(),
+                            distance,
+                        });
+                    }
+                }
+            }
+        }
+        contacts
+    }
+}
+
+impl NodeBehavior for Simulator {
+    fn init(&mut self) {
+        self.node_state = NodeState::Init;
+    }
+
+    fn update(&mut self) {
+        // 只保留输入端口中最新的指令，维数与全部机器人的自由度之和不符的指令被丢弃，沿用之前的指令
+        let total_dof: usize = self.robot.iter().map(|r| r.read().unwrap().dof()).sum();
+        while let Some(command) = self.input_queue.pop() {
+            let valid = command
+                .clone()
+                .try_map(&|v: na::DVector<f64>| (v.len() == total_dof).then_some(v))
+                .is_some();
+            if valid {
+                self.state.command = Some(command);
+            } else {
+                warn!(
+                    node = self.name.as_str(),
+                    "command dropped, its dimension does not match the total dof {}", total_dof
+                );
+            }
+        }
+
+        let step = if self.params.period > 0.0 {
+            self.params.period
+        } else {
+            self.params.dt
+        };
+        let substeps = (step / self.params.dt).ceil().max(1.0) as usize;
+        let dt = step / substeps as f64;
+
+        // 输入端口的指令按各个机器人的自由度依次拆分
+        let mut offset = 0;
+        for robot in self.robot.iter() {
+            let mut robot_write = robot.write().unwrap();
+            let dof = robot_write.dof();
+            let command = match &self.state.command {
+                Some(command) => command
+                    .clone()
+                    .try_map(&|v: na::DVector<f64>| Some(v.rows(offset, dof).into_owned()))
+                    .unwrap(),
+                None => robot_write.control_message(),
+            };
+            offset += dof;
+
+            for _ in 0..substeps {
+                let tau = self.torque(&robot_write, &command);
+                self.integrate(&mut robot_write, &tau, dt);
+            }
+            info!(node = self.name.as_str(), input = ?robot_write.state.q.as_slice());
+        }
+
+        // 汇报新出现的接触
+        let obstacles = match &self.sensor {
+            Some(sensor) => sensor.read().unwrap().collision(),
+            None => Vec::new(),
+        };
+        let contacts = self.detect_contacts(&obstacles);
+        for contact in contacts.iter() {
+            let is_new = !self.state.contacts.iter().any(|old| {
+                (&old.robot, old.link, old.obstacle)
+                    == (&contact.robot, contact.link, contact.obstacle)
+            });
+            if is_new {
+                warn!(
+                    node = self.name.as_str(),
+                    "contact: {} link {} with obstacle {} at distance {}",
+                    contact.robot,
+                    contact.link,
+                    contact.obstacle,
+                    contact.distance
+                );
+            }
+        }
+        self.state.contacts = contacts;
+
+        // 修改节点状态
+        if self.node_state == NodeState::Init {
+            self.node_state = NodeState::RelyRelease;
+        } else {
+            self.node_state = NodeState::Running;
+        }
+    }
+
+    fn period(&self) -> Duration {
+        Duration::from_secs_f64(self.params.period)
+    }
+
+    fn node_name(&self) -> String {
+        self.name.clone()
+    }
+
+    fn state(&mut self) -> NodeState {
+        self.node_state
+    }
+}
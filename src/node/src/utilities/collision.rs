@@ -0,0 +1,9 @@
+use message::{Aabb, Capsule, CollisionObject};
+
+/// 一组连杆胶囊体（例如参考轨迹上各路点的连杆）扫过区域的包围盒，没有胶囊体时返回 None
+pub fn swept_aabb(capsules: impl IntoIterator<Item = Capsule>) -> Option<Aabb> {
+    capsules
+        .into_iter()
+        .map(|capsule| CollisionObject::Capsule(capsule).aabb())
+        .reduce(|a, b| a.merge(&b))
+}
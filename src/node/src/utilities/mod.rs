@@ -1,5 +1,7 @@
+mod collision;
 mod interp_fn;
 mod optimize;
 
+pub use collision::*;
 pub use interp_fn::*;
 pub use optimize::*;
@@ -1,5 +1,6 @@
 use nalgebra as na;
 use osqp::CscMatrix;
+use serde::{de::Error, Deserialize, Deserializer};
 use std::borrow::Cow;
 
 pub fn get_optimize_function<'a>(
@@ -51,3 +52,18 @@ pub fn matrix_to_csc<'a>(h: &na::DMatrix<f64>) -> CscMatrix<'a> {
         data: Cow::Owned(data),
     }
 }
+
+/// 反序列化求解器类型，未注册的类型在创建节点时即报错
+pub fn deserialize_solver<'de, D>(deserializer: D) -> Result<String, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let solver_type = String::deserialize(deserializer)?;
+    if solver::solver_types().contains(&solver_type.as_str()) {
+        Ok(solver_type)
+    } else {
+        Err(D::Error::custom(solver::SolverError::UnknownType(
+            solver_type,
+        )))
+    }
+}
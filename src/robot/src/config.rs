@@ -2,26 +2,59 @@ use message::Pose;
 use serde::Deserialize;
 use std::sync::{Arc, RwLock};
 
-use crate::{DPanda, Gripper, RobotType};
+use crate::{AllowedCollisionMatrix, DPanda, DSeriseRobot, Gripper, RobotError, RobotType, SPanda};
 
 #[derive(Debug, Deserialize)]
 pub struct RobotConfig {
     pub name: String,
     pub robot_type: String,
     pub base_pose: Pose,
+    /// 给出 URDF 文件路径时由文件生成串联机器人，此时 robot_type 仅作为型号名称
+    #[serde(default)]
+    pub urdf_path: Option<String>,
+    /// 工具坐标系在法兰坐标系中的位姿，不给出时末端执行器位姿即法兰位姿
+    #[serde(default)]
+    pub tool_pose: Option<Pose>,
+    /// 额外允许碰撞的连杆对，与机器人默认跳过的相邻连杆合并
+    #[serde(default)]
+    pub allowed_collision: Option<AllowedCollisionMatrix>,
 }
 
+/// 当前支持通过配置文件生成的机器人类型，其中 static_panda 供定长消息的节点使用
+pub const ROBOT_TYPES: [&str; 3] = ["panda", "static_panda", "franka_gripper"];
+
 /// 通过配置文件生成机器人实例
-/// TODO 该函数目前只有动态版本，需要考虑如何实现对于其他储存类型的支持
-pub fn from_config(robot_config: &RobotConfig) -> RobotType {
-    match robot_config.robot_type.as_str() {
-        "panda" => RobotType::DSeriseRobot(Arc::new(RwLock::new(DPanda::new_panda(
+pub fn from_config(robot_config: &RobotConfig) -> Result<RobotType, RobotError> {
+    let robot = match (&robot_config.urdf_path, robot_config.robot_type.as_str()) {
+        (Some(urdf_path), _) => RobotType::DSeriseRobot(Arc::new(RwLock::new(
+            DSeriseRobot::from_file(robot_config.name.clone(), urdf_path, robot_config.base_pose)?,
+        ))),
+        (None, "panda") => RobotType::DSeriseRobot(Arc::new(RwLock::new(DPanda::new_panda(
+            robot_config.name.clone(),
+            robot_config.base_pose,
+        )))),
+        (None, "static_panda") => RobotType::Panda(Arc::new(RwLock::new(SPanda::new_panda(
             robot_config.name.clone(),
             robot_config.base_pose,
         )))),
-        "franka_gripper" => {
+        (None, "franka_gripper") => {
             RobotType::FrankaGripper(Arc::new(RwLock::new(Gripper::new(&robot_config.name))))
         }
-        _ => panic!("Unknown robot type: {}", robot_config.robot_type),
+        _ => return Err(RobotError::UnknownType(robot_config.robot_type.clone())),
+    };
+    if let Some(tool) = robot_config.tool_pose {
+        match &robot {
+            RobotType::DSeriseRobot(robot) => robot.write().unwrap().params.tool = tool,
+            RobotType::Panda(robot) => robot.write().unwrap().params.tool = tool,
+            RobotType::FrankaGripper(_) => {}
+        }
+    }
+    if let Some(acm) = &robot_config.allowed_collision {
+        match &robot {
+            RobotType::DSeriseRobot(robot) => robot.write().unwrap().params.acm.extend(acm),
+            RobotType::Panda(robot) => robot.write().unwrap().params.acm.extend(acm),
+            RobotType::FrankaGripper(_) => {}
+        }
     }
+    Ok(robot)
 }
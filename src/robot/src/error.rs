@@ -0,0 +1,24 @@
+use std::fmt;
+
+/// 机器人创建过程中的错误
+#[derive(Debug, Clone, PartialEq)]
+pub enum RobotError {
+    /// 配置文件中的机器人类型不受支持
+    UnknownType(String),
+    /// 无法读取机器人描述文件
+    ReadFile(String),
+    /// URDF 内容不合法或描述的不是转动关节串联机器人
+    InvalidUrdf(String),
+}
+
+impl fmt::Display for RobotError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RobotError::UnknownType(robot_type) => write!(f, "unknown robot type: {}", robot_type),
+            RobotError::ReadFile(error) => write!(f, "failed to read robot file: {}", error),
+            RobotError::InvalidUrdf(error) => write!(f, "invalid urdf: {}", error),
+        }
+    }
+}
+
+impl std::error::Error for RobotError {}
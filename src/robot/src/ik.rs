@@ -0,0 +1,274 @@
+use message::Pose;
+use nalgebra as na;
+use serde::Deserialize;
+
+use crate::{DRobot, JacobianFrame};
+
+/// 逆运动学的目标维度
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum IkMode {
+    /// 只要求末端位置到达目标
+    Position,
+    /// 要求末端位置与姿态同时到达目标
+    #[default]
+    Pose,
+}
+
+/// 逆运动学每一步的求解方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum IkMethod {
+    /// 阻尼最小二乘，每一步之后将关节角截断到边界内
+    Dls,
+    /// 以关节边界为盒约束的阻尼最小二乘二次规划
+    #[default]
+    Qp,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct IkParams {
+    #[serde(default)]
+    pub mode: IkMode,
+    #[serde(default)]
+    pub method: IkMethod,
+    /// 阻尼系数，奇异位形附近限制步长
+    #[serde(default = "default_damping")]
+    pub damping: f64,
+    /// 单步关节角变化的最大范数
+    #[serde(default = "default_max_step")]
+    pub max_step: f64,
+    #[serde(default = "default_max_iter")]
+    pub max_iter: usize,
+    /// 末端误差范数小于该值时视为收敛
+    #[serde(default = "default_tolerance")]
+    pub tolerance: f64,
+    /// 除当前关节角与默认关节角之外，在关节边界内额外采样的初值个数
+    #[serde(default = "default_nsample")]
+    pub nsample: usize,
+}
+
+fn default_damping() -> f64 {
+    1e-2
+}
+
+fn default_max_step() -> f64 {
+    0.5
+}
+
+fn default_max_iter() -> usize {
+    200
+}
+
+fn default_tolerance() -> f64 {
+    1e-6
+}
+
+fn default_nsample() -> usize {
+    16
+}
+
+impl Default for IkParams {
+    fn default() -> Self {
+        IkParams {
+            mode: IkMode::default(),
+            method: IkMethod::default(),
+            damping: default_damping(),
+            max_step: default_max_step(),
+            max_iter: default_max_iter(),
+            tolerance: default_tolerance(),
+            nsample: default_nsample(),
+        }
+    }
+}
+
+/// 一次逆运动学求解的结果，residual 为末端误差的范数（位置单位与弧度混合）
+#[derive(Debug, Clone, PartialEq)]
+pub struct IkResult {
+    pub q: na::DVector<f64>,
+    pub residual: f64,
+    pub iterations: usize,
+    pub converged: bool,
+}
+
+/// 世界坐标系下末端误差及对应的雅可比矩阵，姿态误差以旋转向量表示
+fn ik_error<R: DRobot + ?Sized>(
+    robot: &R,
+    target: &Pose,
+    q: &na::DVector<f64>,
+    mode: IkMode,
+) -> (na::DVector<f64>, na::DMatrix<f64>) {
+    let pose = robot.cul_end_pose(q);
+    let jacobian = robot.cul_jacobian(q, JacobianFrame::World);
+    let position = target.translation.vector - pose.translation.vector;
+    match mode {
+        IkMode::Position => (
+            na::DVector::from_column_slice(position.as_slice()),
+            jacobian.rows(0, 3).into_owned(),
+        ),
+        IkMode::Pose => {
+            let orientation = (target.rotation * pose.rotation.inverse()).scaled_axis();
+            let error =
+                na::DVector::from_iterator(6, position.iter().chain(orientation.iter()).copied());
+            (error, jacobian)
+        }
+    }
+}
+
+/// 坐标下降法求解盒约束二次规划 min 0.5 xᵀHx - gᵀx, lower <= x <= upper，H 需正定
+fn box_qp(
+    h: &na::DMatrix<f64>,
+    g: &na::DVector<f64>,
+    lower: &na::DVector<f64>,
+    upper: &na::DVector<f64>,
+) -> na::DVector<f64> {
+    let n = g.len();
+    let mut x = na::DVector::zeros(n);
+    for _ in 0..100 {
+        let mut change: f64 = 0.0;
+        for i in 0..n {
+            let rest = h.row(i).dot(&x.transpose()) - h[(i, i)] * x[i];
+            let value = ((g[i] - rest) / h[(i, i)]).clamp(lower[i], upper[i]);
+            change = change.max((value - x[i]).abs());
+            x[i] = value;
+        }
+        if change < 1e-12 {
+            break;
+        }
+    }
+    x
+}
+
+/// 从给定初值出发求解逆运动学，结果始终位于关节边界内
+pub fn solve_ik<R: DRobot + ?Sized>(
+    robot: &R,
+    target: &Pose,
+    seed: &na::DVector<f64>,
+    params: &IkParams,
+) -> IkResult {
+    let q_min = robot.q_min_bound();
+    let q_max = robot.q_max_bound();
+    let mut q = seed.zip_zip_map(&q_min, &q_max, |q, min, max| q.clamp(min, max));
+    let damping = params.damping * params.damping;
+
+    let (mut error, mut jacobian) = ik_error(robot, target, &q, params.mode);
+    let mut iterations = 0;
+    while iterations < params.max_iter && error.norm() >= params.tolerance {
+        iterations += 1;
+        let mut step = match params.method {
+            IkMethod::Dls => {
+                let jjt = &jacobian * jacobian.transpose()
+                    + damping * na::DMatrix::identity(error.len(), error.len());
+                match jjt.cholesky() {
+                    Some(cholesky) => jacobian.transpose() * cholesky.solve(&error),
+                    None => break,
+                }
+            }
+            IkMethod::Qp => {
+                let h = jacobian.transpose() * &jacobian
+                    + damping * na::DMatrix::identity(q.len(), q.len());
+                let g = jacobian.transpose() * &error;
+                box_qp(&h, &g, &(&q_min - &q), &(&q_max - &q))
+            }
+        };
+        let norm = step.norm();
+        if norm > params.max_step {
+            step *= params.max_step / norm;
+        }
+        if norm < 1e-12 {
+            break;
+        }
+        q = (&q + step).zip_zip_map(&q_min, &q_max, |q, min, max| q.clamp(min, max));
+        (error, jacobian) = ik_error(robot, target, &q, params.mode);
+    }
+
+    let residual = error.norm();
+    IkResult {
+        q,
+        residual,
+        iterations,
+        converged: residual < params.tolerance,
+    }
+}
+
+/// 第 index 个 Halton 序列值，用于在关节空间中确定性地均匀采样
+fn halton(mut index: usize, base: usize) -> f64 {
+    let mut result = 0.0;
+    let mut fraction = 1.0;
+    while index > 0 {
+        fraction /= base as f64;
+        result += fraction * (index % base) as f64;
+        index /= base;
+    }
+    result
+}
+
+fn primes(n: usize) -> Vec<usize> {
+    let mut primes = Vec::with_capacity(n);
+    let mut candidate = 2;
+    while primes.len() < n {
+        if primes.iter().all(|p| candidate % p != 0) {
+            primes.push(candidate);
+        }
+        candidate += 1;
+    }
+    primes
+}
+
+/// 初值依次为当前关节角、默认关节角和关节边界内的采样点
+fn ik_seeds<R: DRobot + ?Sized>(robot: &R, nsample: usize) -> Vec<na::DVector<f64>> {
+    let q_min = robot.q_min_bound();
+    let q_max = robot.q_max_bound();
+    let bases = primes(q_min.len());
+
+    let mut seeds = vec![robot.q(), robot.q_default()];
+    for index in 1..=nsample {
+        seeds.push(na::DVector::from_fn(q_min.len(), |i, _| {
+            q_min[i] + halton(index, bases[i]) * (q_max[i] - q_min[i])
+        }));
+    }
+    seeds
+}
+
+/// 从所有初值出发求解，返回互不相同的收敛解，按与当前关节角的距离由近到远排列
+pub fn sample_ik<R: DRobot + ?Sized>(robot: &R, target: &Pose, params: &IkParams) -> Vec<IkResult> {
+    let q = robot.q();
+    let mut solutions: Vec<IkResult> = Vec::new();
+    for seed in ik_seeds(robot, params.nsample) {
+        let result = solve_ik(robot, target, &seed, params);
+        let is_new = solutions
+            .iter()
+            .all(|solution| (&solution.q - &result.q).amax() > 1e-3);
+        if result.converged && is_new {
+            solutions.push(result);
+        }
+    }
+    solutions.sort_by(|a, b| (&a.q - &q).norm().total_cmp(&(&b.q - &q).norm()));
+    solutions
+}
+
+/// 选取离当前关节角最近的收敛解，所有初值都不收敛时返回残差最小的结果
+pub fn best_ik<R: DRobot + ?Sized>(robot: &R, target: &Pose, params: &IkParams) -> IkResult {
+    let q = robot.q();
+    let mut best: Option<IkResult> = None;
+    for seed in ik_seeds(robot, params.nsample) {
+        let result = solve_ik(robot, target, &seed, params);
+        // 以当前关节角为初值收敛时不再尝试其他初值
+        if result.converged && seed == q {
+            return result;
+        }
+        let better = match &best {
+            None => true,
+            Some(best) => match (result.converged, best.converged) {
+                (true, true) => (&result.q - &q).norm() < (&best.q - &q).norm(),
+                (true, false) => true,
+                (false, true) => false,
+                (false, false) => result.residual < best.residual,
+            },
+        };
+        if better {
+            best = Some(result);
+        }
+    }
+    best.unwrap()
+}
@@ -1,4 +1,6 @@
 mod config;
+mod error;
+mod ik;
 mod robot_trait;
 mod robots;
 mod utilities;
@@ -6,6 +8,8 @@ mod utilities;
 use std::sync::{Arc, RwLock};
 
 pub use config::*;
+pub use error::*;
+pub use ik::*;
 pub use robot_trait::*;
 pub use robots::*;
 
@@ -50,6 +54,25 @@ impl DownCastRobot for RobotLock<DSeriseRobot> {
     }
 }
 
+/// 同时持有多个机器人的节点，按照赋予的顺序保存
+impl DownCastRobot for Vec<Arc<RwLock<DSeriseRobot>>> {
+    fn downcast_robot(robot: RobotType, mut currect_robot: Self) -> Self {
+        if let RobotType::DSeriseRobot(robot) = robot {
+            currect_robot.push(robot);
+        }
+        currect_robot
+    }
+}
+
+impl DownCastRobot for RobotLock<SPanda> {
+    fn downcast_robot(robot: RobotType, currect_robot: Self) -> Self {
+        match robot {
+            RobotType::Panda(robot) => Some(robot),
+            _ => currect_robot,
+        }
+    }
+}
+
 impl DownCastRobot for RobotLock<Gripper> {
     fn downcast_robot(robot: RobotType, currect_robot: Self) -> Self {
         match robot {
@@ -63,6 +86,7 @@ impl DownCastRobot for (RobotLock<DSeriseRobot>, RobotLock<Gripper>) {
     fn downcast_robot(robot: RobotType, currect_robot: Self) -> Self {
         match robot {
             RobotType::DSeriseRobot(robot1) => (Some(robot1.clone()), currect_robot.1.clone()),
+            RobotType::FrankaGripper(gripper) => (currect_robot.0.clone(), Some(gripper)),
             _ => currect_robot,
         }
     }
@@ -1,6 +1,6 @@
 use nalgebra as na;
 
-use message::{Capsule, CollisionObject, NodeMessage, Pose};
+use message::{Capsule, CollisionObject, DistanceResult, NodeMessage, Pose};
 
 pub trait Robot<V> {
     // get functions
@@ -45,10 +45,63 @@ pub trait SRobot<const N: usize>: Robot<na::SVector<f64, N>> {
         q: &na::SVector<f64, N>,
         obj: &CollisionObject,
     ) -> na::SVector<f64, N>;
+    fn cul_link_distances(
+        &self,
+        q: &na::SVector<f64, N>,
+        obj: &CollisionObject,
+    ) -> Vec<LinkDistance>;
+    fn cul_distance_grad(
+        &self,
+        q: &na::SVector<f64, N>,
+        distance: &LinkDistance,
+    ) -> na::SVector<f64, N>;
 
     fn reset(&mut self);
 }
 
+/// 某个连杆的胶囊体到碰撞体的距离，result 中 a 为胶囊体，b 为碰撞体
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LinkDistance {
+    pub link: usize,
+    pub result: DistanceResult,
+}
+
+/// 两个连杆的胶囊体之间的距离，两个连杆可以属于同一个机器人，也可以属于同一分支中的不同机器人，
+/// result 中 a 为 link_a 的胶囊体，b 为 link_b 的胶囊体
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LinkPairDistance {
+    pub link_a: usize,
+    pub link_b: usize,
+    pub result: DistanceResult,
+}
+
+impl LinkPairDistance {
+    /// 拆分为两个连杆各自到对方胶囊体的距离，两者的梯度之和即为连杆对距离的梯度
+    pub fn split(&self) -> (LinkDistance, LinkDistance) {
+        (
+            LinkDistance {
+                link: self.link_a,
+                result: self.result,
+            },
+            LinkDistance {
+                link: self.link_b,
+                result: self.result.swap(),
+            },
+        )
+    }
+}
+
+/// 雅可比矩阵所在的坐标系，雅可比矩阵的前三行对应线速度，后三行对应角速度
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum JacobianFrame {
+    /// 世界坐标系，与 cul_end_pose 的结果一致
+    World,
+    /// 机器人基座坐标系
+    Base,
+    /// 末端执行器坐标系
+    End,
+}
+
 pub trait DRobot: Robot<na::DVector<f64>> {
     // get functions
     fn end_pose(&self) -> Pose;
@@ -60,6 +113,14 @@ pub trait DRobot: Robot<na::DVector<f64>> {
     fn cul_capsules(&self, q: &na::DVector<f64>) -> Vec<Capsule>;
     fn cul_dis_to_collision(&self, q: &na::DVector<f64>, obj: &CollisionObject)
         -> na::DVector<f64>;
+    fn cul_link_distances(&self, q: &na::DVector<f64>, obj: &CollisionObject) -> Vec<LinkDistance>;
+    fn cul_distance_grad(&self, q: &na::DVector<f64>, distance: &LinkDistance) -> na::DVector<f64>;
+    fn cul_self_distances(&self, q: &na::DVector<f64>) -> Vec<LinkPairDistance>;
+    fn cul_pair_distance_grad(
+        &self,
+        q: &na::DVector<f64>,
+        distance: &LinkPairDistance,
+    ) -> na::DVector<f64>;
 
     fn cul_func(
         &self,
@@ -67,5 +128,31 @@ pub trait DRobot: Robot<na::DVector<f64>> {
         func: &dyn Fn(&na::DVector<f64>) -> na::DVector<f64>,
     ) -> (na::DVector<f64>, na::DMatrix<f64>);
 
+    // kinematics functions
+    fn cul_jacobian(&self, q: &na::DVector<f64>, frame: JacobianFrame) -> na::DMatrix<f64>;
+    fn cul_jacobian_dot(
+        &self,
+        q: &na::DVector<f64>,
+        q_dot: &na::DVector<f64>,
+        frame: JacobianFrame,
+    ) -> na::DMatrix<f64>;
+
+    // dynamics functions
+    fn cul_mass_matrix(&self, q: &na::DVector<f64>) -> na::DMatrix<f64>;
+    fn cul_coriolis(&self, q: &na::DVector<f64>, q_dot: &na::DVector<f64>) -> na::DVector<f64>;
+    fn cul_gravity(&self, q: &na::DVector<f64>) -> na::DVector<f64>;
+    fn cul_inverse_dynamics(
+        &self,
+        q: &na::DVector<f64>,
+        q_dot: &na::DVector<f64>,
+        q_ddot: &na::DVector<f64>,
+    ) -> na::DVector<f64>;
+    fn cul_forward_dynamics(
+        &self,
+        q: &na::DVector<f64>,
+        q_dot: &na::DVector<f64>,
+        tau: &na::DVector<f64>,
+    ) -> na::DVector<f64>;
+
     fn reset(&mut self);
 }
@@ -0,0 +1,217 @@
+use nalgebra as na;
+use serde::Deserialize;
+
+use super::DhConvention;
+use message::Pose;
+
+/// 重力加速度的大小，世界坐标系中重力沿 -z 方向
+pub const GRAVITY: f64 = 9.81;
+
+/// 连杆的惯性参数，质心与惯性张量均在连杆坐标系中表示，惯性张量以质心为参考点
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+pub struct LinkInertia {
+    pub mass: f64,
+    pub com: na::Vector3<f64>,
+    pub inertia: na::Matrix3<f64>,
+}
+
+impl LinkInertia {
+    /// inertia 依次为 ixx, ixy, ixz, iyy, iyz, izz
+    pub fn new(mass: f64, com: [f64; 3], inertia: [f64; 6]) -> LinkInertia {
+        let [ixx, ixy, ixz, iyy, iyz, izz] = inertia;
+        LinkInertia {
+            mass,
+            com: na::Vector3::from(com),
+            inertia: na::Matrix3::new(ixx, ixy, ixz, ixy, iyy, iyz, ixz, iyz, izz),
+        }
+    }
+
+    /// 将惯性参数变换到另一个坐标系下，pose 为原坐标系在新坐标系中的位姿
+    pub(crate) fn transformed(&self, pose: &Pose) -> LinkInertia {
+        let rotation = pose.rotation.to_rotation_matrix().into_inner();
+        LinkInertia {
+            mass: self.mass,
+            com: (pose * na::Point3::from(self.com)).coords,
+            inertia: rotation * self.inertia * rotation.transpose(),
+        }
+    }
+
+    /// 以连杆坐标系原点为参考点的空间惯量，角量在前线量在后
+    fn spatial(&self) -> na::Matrix6<f64> {
+        let c = self.com.cross_matrix();
+        let mut spatial = na::Matrix6::zeros();
+        spatial
+            .fixed_view_mut::<3, 3>(0, 0)
+            .copy_from(&(self.inertia + self.mass * c * c.transpose()));
+        spatial
+            .fixed_view_mut::<3, 3>(0, 3)
+            .copy_from(&(self.mass * c));
+        spatial
+            .fixed_view_mut::<3, 3>(3, 0)
+            .copy_from(&(self.mass * c.transpose()));
+        spatial
+            .fixed_view_mut::<3, 3>(3, 3)
+            .copy_from(&(self.mass * na::Matrix3::identity()));
+        spatial
+    }
+}
+
+/// 第 i 个关节坐标系相对于上一个关节坐标系的旋转与原点位置
+fn link_transform(
+    convention: DhConvention,
+    dh: &na::DMatrix<f64>,
+    i: usize,
+    q: f64,
+) -> (na::Rotation3<f64>, na::Vector3<f64>) {
+    let transform = convention.joint_transform(dh, i, q);
+    (
+        transform.rotation.to_rotation_matrix(),
+        transform.translation.vector,
+    )
+}
+
+/// 递归牛顿-欧拉法求逆动力学，inertials 在各个关节坐标系下表示，gravity 为基座坐标系下的重力加速度
+pub(crate) fn rnea(
+    convention: DhConvention,
+    dh: &na::DMatrix<f64>,
+    inertials: &[LinkInertia],
+    q: &na::DVector<f64>,
+    q_dot: &na::DVector<f64>,
+    q_ddot: &na::DVector<f64>,
+    gravity: &na::Vector3<f64>,
+) -> na::DVector<f64> {
+    let n = q.len();
+    let z = na::Vector3::z();
+    let mut transforms = Vec::with_capacity(n);
+    let mut forces = Vec::with_capacity(n);
+    let mut moments = Vec::with_capacity(n);
+
+    // 由基座向末端传递速度与加速度，基座以 -gravity 加速等效于施加重力
+    let mut omega = na::Vector3::zeros();
+    let mut omega_dot = na::Vector3::zeros();
+    let mut acc = -gravity;
+    for (i, link) in inertials.iter().enumerate().take(n) {
+        let (rotation, position) = link_transform(convention, dh, i, q[i]);
+        let inverse = rotation.inverse();
+        acc = inverse * (omega_dot.cross(&position) + omega.cross(&omega.cross(&position)) + acc);
+        let omega_parent = inverse * omega;
+        omega = omega_parent + q_dot[i] * z;
+        omega_dot = inverse * omega_dot + omega_parent.cross(&(q_dot[i] * z)) + q_ddot[i] * z;
+
+        let acc_com = omega_dot.cross(&link.com) + omega.cross(&omega.cross(&link.com)) + acc;
+        forces.push(link.mass * acc_com);
+        moments.push(link.inertia * omega_dot + omega.cross(&(link.inertia * omega)));
+        transforms.push((rotation, position));
+    }
+
+    // 由末端向基座传递力与力矩，力矩在关节轴上的分量即关节力矩
+    let mut tau = na::DVector::zeros(n);
+    let mut force = na::Vector3::zeros();
+    let mut moment = na::Vector3::zeros();
+    for i in (0..n).rev() {
+        let (child_force, child_moment) = match transforms.get(i + 1) {
+            Some((rotation, position)) => {
+                let child_force = rotation * force;
+                (
+                    child_force,
+                    rotation * moment + position.cross(&child_force),
+                )
+            }
+            None => (na::Vector3::zeros(), na::Vector3::zeros()),
+        };
+        moment = moments[i] + child_moment + inertials[i].com.cross(&forces[i]);
+        force = forces[i] + child_force;
+        tau[i] = moment.z;
+    }
+    tau
+}
+
+/// 由父连杆坐标系到子连杆坐标系的空间运动变换
+fn motion_transform(
+    rotation: &na::Rotation3<f64>,
+    position: &na::Vector3<f64>,
+) -> na::Matrix6<f64> {
+    let e = rotation.inverse().into_inner();
+    let mut transform = na::Matrix6::zeros();
+    transform.fixed_view_mut::<3, 3>(0, 0).copy_from(&e);
+    transform
+        .fixed_view_mut::<3, 3>(3, 0)
+        .copy_from(&(-e * position.cross_matrix()));
+    transform.fixed_view_mut::<3, 3>(3, 3).copy_from(&e);
+    transform
+}
+
+/// 空间运动向量的叉乘矩阵
+fn motion_cross(v: &na::Vector6<f64>) -> na::Matrix6<f64> {
+    let omega = na::Vector3::new(v[0], v[1], v[2]).cross_matrix();
+    let linear = na::Vector3::new(v[3], v[4], v[5]).cross_matrix();
+    let mut cross = na::Matrix6::zeros();
+    cross.fixed_view_mut::<3, 3>(0, 0).copy_from(&omega);
+    cross.fixed_view_mut::<3, 3>(3, 0).copy_from(&linear);
+    cross.fixed_view_mut::<3, 3>(3, 3).copy_from(&omega);
+    cross
+}
+
+/// 空间力向量的叉乘矩阵
+fn force_cross(v: &na::Vector6<f64>) -> na::Matrix6<f64> {
+    -motion_cross(v).transpose()
+}
+
+/// 铰接体算法求正动力学，inertials 在各个关节坐标系下表示，gravity 为基座坐标系下的重力加速度
+pub(crate) fn aba(
+    convention: DhConvention,
+    dh: &na::DMatrix<f64>,
+    inertials: &[LinkInertia],
+    q: &na::DVector<f64>,
+    q_dot: &na::DVector<f64>,
+    tau: &na::DVector<f64>,
+    gravity: &na::Vector3<f64>,
+) -> na::DVector<f64> {
+    let n = q.len();
+    // 关节转轴，即关节坐标系的 z 轴
+    let axis = na::Vector6::z();
+    let mut transforms = Vec::with_capacity(n);
+    let mut bias_acc = Vec::with_capacity(n);
+    let mut articulated = Vec::with_capacity(n);
+    let mut bias_force = Vec::with_capacity(n);
+
+    // 由基座向末端计算速度与偏置项
+    let mut velocity = na::Vector6::zeros();
+    for (i, link) in inertials.iter().enumerate().take(n) {
+        let (rotation, position) = link_transform(convention, dh, i, q[i]);
+        let transform = motion_transform(&rotation, &position);
+        let joint_velocity = axis * q_dot[i];
+        velocity = transform * velocity + joint_velocity;
+        let inertia = link.spatial();
+        bias_acc.push(motion_cross(&velocity) * joint_velocity);
+        bias_force.push(force_cross(&velocity) * inertia * velocity);
+        articulated.push(inertia);
+        transforms.push(transform);
+    }
+
+    // 由末端向基座计算铰接体惯量
+    let mut u_axis = vec![na::Vector6::zeros(); n];
+    let mut d = vec![0.0; n];
+    let mut u = vec![0.0; n];
+    for i in (0..n).rev() {
+        u_axis[i] = articulated[i] * axis;
+        d[i] = axis.dot(&u_axis[i]);
+        u[i] = tau[i] - axis.dot(&bias_force[i]);
+        if i > 0 {
+            let inertia = articulated[i] - u_axis[i] * u_axis[i].transpose() / d[i];
+            let force = bias_force[i] + inertia * bias_acc[i] + u_axis[i] * u[i] / d[i];
+            articulated[i - 1] += transforms[i].transpose() * inertia * transforms[i];
+            bias_force[i - 1] += transforms[i].transpose() * force;
+        }
+    }
+
+    // 由基座向末端求关节加速度
+    let mut q_ddot = na::DVector::zeros(n);
+    let mut acc = na::Vector6::new(0.0, 0.0, 0.0, -gravity.x, -gravity.y, -gravity.z);
+    for i in 0..n {
+        acc = transforms[i] * acc + bias_acc[i];
+        q_ddot[i] = (u[i] - u_axis[i].dot(&acc)) / d[i];
+        acc += axis * q_ddot[i];
+    }
+    q_ddot
+}
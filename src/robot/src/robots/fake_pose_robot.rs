@@ -1,6 +1,6 @@
 use nalgebra as na;
 
-use crate::{DRobot, Robot};
+use crate::{DRobot, JacobianFrame, LinkDistance, LinkPairDistance, Robot};
 use generate_tools::todo_fn;
 use message::{iso_to_vec, vec_to_iso, Capsule, NodeMessage, Pose};
 
@@ -68,6 +68,30 @@ impl DRobot for FakePoseRobot {
         unimplemented!()
     }
 
+    fn cul_link_distances(
+        &self,
+        _: &na::DVector<f64>,
+        _: &message::CollisionObject,
+    ) -> Vec<LinkDistance> {
+        unimplemented!()
+    }
+
+    fn cul_distance_grad(&self, _: &na::DVector<f64>, _: &LinkDistance) -> na::DVector<f64> {
+        unimplemented!()
+    }
+
+    fn cul_self_distances(&self, _: &na::DVector<f64>) -> Vec<LinkPairDistance> {
+        unimplemented!()
+    }
+
+    fn cul_pair_distance_grad(
+        &self,
+        _: &na::DVector<f64>,
+        _: &LinkPairDistance,
+    ) -> na::DVector<f64> {
+        unimplemented!()
+    }
+
     fn cul_end_pose(&self, q: &nalgebra::DVector<f64>) -> Pose {
         vec_to_iso(q.clone())
     }
@@ -92,6 +116,49 @@ impl DRobot for FakePoseRobot {
         (value, grad)
     }
 
+    fn cul_jacobian(&self, _: &na::DVector<f64>, _: JacobianFrame) -> na::DMatrix<f64> {
+        unimplemented!()
+    }
+
+    fn cul_jacobian_dot(
+        &self,
+        _: &na::DVector<f64>,
+        _: &na::DVector<f64>,
+        _: JacobianFrame,
+    ) -> na::DMatrix<f64> {
+        unimplemented!()
+    }
+
+    fn cul_mass_matrix(&self, _: &na::DVector<f64>) -> na::DMatrix<f64> {
+        unimplemented!()
+    }
+
+    fn cul_coriolis(&self, _: &na::DVector<f64>, _: &na::DVector<f64>) -> na::DVector<f64> {
+        unimplemented!()
+    }
+
+    fn cul_gravity(&self, _: &na::DVector<f64>) -> na::DVector<f64> {
+        unimplemented!()
+    }
+
+    fn cul_inverse_dynamics(
+        &self,
+        _: &na::DVector<f64>,
+        _: &na::DVector<f64>,
+        _: &na::DVector<f64>,
+    ) -> na::DVector<f64> {
+        unimplemented!()
+    }
+
+    fn cul_forward_dynamics(
+        &self,
+        _: &na::DVector<f64>,
+        _: &na::DVector<f64>,
+        _: &na::DVector<f64>,
+    ) -> na::DVector<f64> {
+        unimplemented!()
+    }
+
     fn reset(&mut self) {
         self.pose = Pose::default();
     }
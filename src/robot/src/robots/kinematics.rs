@@ -0,0 +1,151 @@
+use nalgebra as na;
+use serde::Deserialize;
+
+use super::SeriseRobot;
+use crate::LinkDistance;
+use message::{Capsule, CollisionObject, Pose};
+
+/// DH 参数的约定，dh 的每一行依次为 theta 偏置、d、a、alpha
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DhConvention {
+    /// 标准 DH：Rz(theta) Tz(d) Tx(a) Rx(alpha)，关节 i 绕上一个连杆坐标系的 z 轴转动
+    Standard,
+    /// 改进 DH（Craig）：Rx(alpha) Tx(a) Rz(theta) Tz(d)，关节 i 绕本连杆坐标系的 z 轴转动
+    #[default]
+    Modified,
+}
+
+fn rotation_x(angle: f64) -> na::UnitQuaternion<f64> {
+    na::UnitQuaternion::from_axis_angle(&na::Vector3::x_axis(), angle)
+}
+
+fn rotation_z(angle: f64) -> na::UnitQuaternion<f64> {
+    na::UnitQuaternion::from_axis_angle(&na::Vector3::z_axis(), angle)
+}
+
+impl DhConvention {
+    /// 第 i 行 DH 参数在关节角 q 下对应的相邻连杆坐标系之间的变换
+    pub fn transform(&self, dh: &na::DMatrix<f64>, i: usize, q: f64) -> Pose {
+        let theta = q + dh[(i, 0)];
+        let d = dh[(i, 1)];
+        let a = dh[(i, 2)];
+        let alpha = dh[(i, 3)];
+        match self {
+            DhConvention::Standard => Pose::from_parts(
+                na::Translation3::new(a * theta.cos(), a * theta.sin(), d),
+                rotation_z(theta) * rotation_x(alpha),
+            ),
+            DhConvention::Modified => Pose::from_parts(
+                na::Translation3::new(a, -d * alpha.sin(), d * alpha.cos()),
+                rotation_x(alpha) * rotation_z(theta),
+            ),
+        }
+    }
+
+    /// 第 i 个连杆坐标系在其关节坐标系中的位姿。关节坐标系以关节轴为 z 轴、原点位于关节轴上并随连杆转动，
+    /// 改进 DH 下即连杆坐标系本身
+    pub(crate) fn joint_to_link(&self, dh: &na::DMatrix<f64>, i: usize) -> Pose {
+        match self {
+            DhConvention::Standard => Pose::from_parts(
+                na::Translation3::new(dh[(i, 2)], 0.0, dh[(i, 1)]),
+                rotation_x(dh[(i, 3)]),
+            ),
+            DhConvention::Modified => Pose::identity(),
+        }
+    }
+
+    /// 第 i 个关节坐标系在上一个关节坐标系（i 为 0 时为基座坐标系）中的位姿
+    pub(crate) fn joint_transform(&self, dh: &na::DMatrix<f64>, i: usize, q: f64) -> Pose {
+        let parent = if i == 0 {
+            Pose::identity()
+        } else {
+            self.joint_to_link(dh, i - 1)
+        };
+        parent * self.transform(dh, i, q) * self.joint_to_link(dh, i).inverse()
+    }
+}
+
+/// 静态与动态串联机器人共用的正运动学
+impl<V> SeriseRobot<V> {
+    /// 各个关节坐标系在世界坐标系下的位姿，关节 i 绕第 i 个关节坐标系的 z 轴转动
+    pub(crate) fn fk_joint_frames(&self, q: &[f64]) -> Vec<Pose> {
+        let params = &self.params;
+        let mut isometry = self.state.base;
+        let mut frames = Vec::with_capacity(params.nlink);
+        for (i, q) in q.iter().enumerate().take(params.nlink) {
+            isometry *= params.convention.joint_transform(&params.dh, i, *q);
+            frames.push(isometry);
+        }
+        frames
+    }
+
+    /// 各个连杆坐标系在世界坐标系下的位姿
+    pub(crate) fn fk_link_frames(&self, q: &[f64]) -> Vec<Pose> {
+        let params = &self.params;
+        self.fk_joint_frames(q)
+            .into_iter()
+            .enumerate()
+            .map(|(i, frame)| frame * params.convention.joint_to_link(&params.dh, i))
+            .collect()
+    }
+
+    /// 法兰坐标系：最后一个连杆坐标系之后依次经过 dh 中 nlink 之后各行的固定变换
+    pub(crate) fn fk_flange(&self, q: &[f64]) -> Pose {
+        let params = &self.params;
+        let last = self
+            .fk_link_frames(q)
+            .last()
+            .map_or(self.state.base, |frame| *frame);
+        (params.nlink..params.dh.nrows()).fold(last, |isometry, i| {
+            isometry * params.convention.transform(&params.dh, i, 0.0)
+        })
+    }
+
+    /// 末端执行器位姿，即法兰坐标系再经过工具坐标系的固定变换
+    pub(crate) fn fk_end_pose(&self, q: &[f64]) -> Pose {
+        self.fk_flange(q) * self.params.tool
+    }
+
+    /// 各个连杆上的胶囊体，胶囊体固连在对应的连杆坐标系上，不包括末端执行器以及所夹取的物体
+    pub(crate) fn fk_capsules(&self, q: &[f64]) -> Vec<Capsule> {
+        self.fk_link_frames(q)
+            .into_iter()
+            .zip(self.params.capsules.iter())
+            .map(|(pose, capsule)| Capsule { pose, ..*capsule })
+            .collect()
+    }
+
+    /// 各个连杆的胶囊体到碰撞体的带符号距离、最近点与法向量
+    pub(crate) fn link_distances(&self, q: &[f64], obj: &CollisionObject) -> Vec<LinkDistance> {
+        self.fk_capsules(q)
+            .into_iter()
+            .enumerate()
+            .map(|(link, capsule)| LinkDistance {
+                link,
+                result: CollisionObject::get_distance_result(
+                    &CollisionObject::Capsule(capsule),
+                    obj,
+                ),
+            })
+            .collect()
+    }
+
+    /// 连杆距离对关节角的解析梯度。胶囊体上的最近点固连在连杆上，其速度为 J_link(q) q_dot，
+    /// 法向量由胶囊体指向碰撞体，因此梯度为 -nᵀ J_link(q)
+    pub(crate) fn distance_grad(&self, q: &[f64], distance: &LinkDistance) -> Vec<f64> {
+        let point = distance.result.point_a.coords;
+        let normal = distance.result.normal;
+        let mut grad = vec![0.0; self.params.nlink];
+        for (i, frame) in self
+            .fk_joint_frames(q)
+            .iter()
+            .enumerate()
+            .take(distance.link + 1)
+        {
+            let z = frame.rotation * na::Vector3::z();
+            grad[i] = -normal.dot(&z.cross(&(point - frame.translation.vector)));
+        }
+        grad
+    }
+}
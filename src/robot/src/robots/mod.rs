@@ -1,13 +1,20 @@
+mod dynamics;
 mod fake_pose_robot;
 mod franka_gripper;
+mod kinematics;
 mod panda;
 mod robot_branch;
+mod self_collision;
 mod serise_robot;
+mod urdf;
 
 // 请尽快新增其他类型的支持
 
+pub use dynamics::{LinkInertia, GRAVITY};
 pub use fake_pose_robot::*;
 pub use franka_gripper::*;
+pub use kinematics::DhConvention;
 pub use panda::*;
 pub use robot_branch::*;
+pub use self_collision::AllowedCollisionMatrix;
 pub use serise_robot::*;
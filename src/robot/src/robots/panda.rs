@@ -1,7 +1,7 @@
 use nalgebra as na;
 use std::f64::consts::{FRAC_PI_2, FRAC_PI_4};
 
-use crate::{DSeriseRobot, SeriseRobotParams};
+use crate::{AllowedCollisionMatrix, DSeriseRobot, DhConvention, LinkInertia, SeriseRobotParams};
 use message::{Capsule, NodeMessage, Pose};
 
 use super::{SeriseRobot, SeriseRobotState};
@@ -48,6 +48,8 @@ impl DPanda {
                 tau_dot_bound: na::DVector::from_vec(vec![
                     1000., 1000., 1000., 1000., 1000., 1000., 1000.,
                 ]),
+                // 最后一行为法兰
+                convention: DhConvention::Modified,
                 #[rustfmt::skip]
                 dh: na::DMatrix::from_row_slice(PANDA_DOF+1, 4, &[
                     0.,  0.333,  0.,      0.,
@@ -59,6 +61,7 @@ impl DPanda {
                     0.,  0.,     0.088,   FRAC_PI_2,
                     0.,  0.107,  0.,      0.,
                     ]),
+                tool: Pose::identity(),
                 capsules: vec![
                     Capsule::from_vec(vec![0., 0., 0., 0., 0., -0.333, 0.07]),
                     Capsule::from_vec(vec![0., 0., -0.05, 0., 0., 0., 0.07]),
@@ -69,6 +72,135 @@ impl DPanda {
                     Capsule::from_vec(vec![0., 0., 0., 0., 0., 0.107, 0.07]),
                     Capsule::from_vec(vec![0., -0.05, 0., 0., 0.05, 0., 0.10]),
                 ],
+                // 相邻连杆以及腕部始终重叠的第 5、7 个连杆不做自碰撞检查
+                acm: {
+                    let mut acm = AllowedCollisionMatrix::adjacent(PANDA_DOF, 1);
+                    acm.allow(4, 6);
+                    acm
+                },
+                // 辨识得到的连杆惯性参数，不含末端执行器
+                inertials: vec![
+                    LinkInertia::new(
+                        4.970684,
+                        [3.875e-03, 2.081e-03, -0.1750],
+                        [
+                            7.0337e-01,
+                            -1.3900e-04,
+                            6.7720e-03,
+                            7.0661e-01,
+                            1.9169e-02,
+                            9.1170e-03,
+                        ],
+                    ),
+                    LinkInertia::new(
+                        0.646926,
+                        [-3.141e-03, -2.872e-02, 3.495e-03],
+                        [
+                            7.9620e-03,
+                            -3.9250e-03,
+                            1.0254e-02,
+                            2.8110e-02,
+                            7.0400e-04,
+                            2.5995e-02,
+                        ],
+                    ),
+                    LinkInertia::new(
+                        3.228604,
+                        [2.7518e-02, 3.9252e-02, -6.6502e-02],
+                        [
+                            3.7242e-02,
+                            -4.7610e-03,
+                            -1.1396e-02,
+                            3.6155e-02,
+                            -1.2805e-02,
+                            1.0830e-02,
+                        ],
+                    ),
+                    LinkInertia::new(
+                        3.587895,
+                        [-5.317e-02, 1.04419e-01, 2.7454e-02],
+                        [
+                            2.5853e-02,
+                            7.7960e-03,
+                            -1.3320e-03,
+                            1.9552e-02,
+                            8.6410e-03,
+                            2.8323e-02,
+                        ],
+                    ),
+                    LinkInertia::new(
+                        1.225946,
+                        [-1.1953e-02, 4.1065e-02, -3.8437e-02],
+                        [
+                            3.5549e-02,
+                            -2.1170e-03,
+                            -4.0370e-03,
+                            2.9474e-02,
+                            2.2900e-04,
+                            8.6270e-03,
+                        ],
+                    ),
+                    LinkInertia::new(
+                        1.666555,
+                        [6.0149e-02, -1.4117e-02, -1.0517e-02],
+                        [
+                            1.9640e-03,
+                            1.0900e-04,
+                            -1.1580e-03,
+                            4.3540e-03,
+                            3.4100e-04,
+                            5.4330e-03,
+                        ],
+                    ),
+                    LinkInertia::new(
+                        7.35522e-01,
+                        [1.0517e-02, -4.252e-03, 6.1597e-02],
+                        [
+                            1.2516e-02,
+                            -4.2800e-04,
+                            -1.1960e-03,
+                            1.0027e-02,
+                            -7.4100e-04,
+                            4.8150e-03,
+                        ],
+                    ),
+                ],
+            },
+        }
+    }
+}
+
+impl SPanda {
+    /// 静态 Panda 与动态 Panda 使用相同的参数，仅储存类型不同
+    pub fn new_panda(name: String, base: Pose) -> SPanda {
+        let panda = DPanda::new_panda(name, base);
+        let to_static = |v: &na::DVector<f64>| na::SVector::from_column_slice(v.as_slice());
+        SPanda {
+            name: panda.name,
+            state: SeriseRobotState::<na::SVector<f64, PANDA_DOF>> {
+                q: to_static(&panda.state.q),
+                q_dot: to_static(&panda.state.q_dot),
+                q_ddot: to_static(&panda.state.q_ddot),
+                q_jerk: to_static(&panda.state.q_jerk),
+                base,
+                control_message: NodeMessage::NoneNodeMessage,
+            },
+            params: SeriseRobotParams::<na::SVector<f64, PANDA_DOF>> {
+                nlink: panda.params.nlink,
+                q_default: to_static(&panda.params.q_default),
+                q_min_bound: to_static(&panda.params.q_min_bound),
+                q_max_bound: to_static(&panda.params.q_max_bound),
+                q_dot_bound: to_static(&panda.params.q_dot_bound),
+                q_ddot_bound: to_static(&panda.params.q_ddot_bound),
+                q_jerk_bound: to_static(&panda.params.q_jerk_bound),
+                tau_bound: to_static(&panda.params.tau_bound),
+                tau_dot_bound: to_static(&panda.params.tau_dot_bound),
+                convention: panda.params.convention,
+                dh: panda.params.dh,
+                tool: panda.params.tool,
+                capsules: panda.params.capsules,
+                acm: panda.params.acm,
+                inertials: panda.params.inertials,
             },
         }
     }
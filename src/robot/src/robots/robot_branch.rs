@@ -1,8 +1,8 @@
-use message::{NodeMessage, Pose};
+use message::{CollisionObject, NodeMessage, Pose};
 use nalgebra as na;
 use std::sync::{Arc, RwLock};
 
-use crate::{DRobot, Robot};
+use crate::{AllowedCollisionMatrix, DRobot, JacobianFrame, LinkDistance, LinkPairDistance, Robot};
 
 macro_rules! merge_fn {
     ($($fn_name:ident),*) => {
@@ -31,6 +31,8 @@ macro_rules! split_fn {
 pub struct RobotBranch<R> {
     robots: Vec<Arc<RwLock<R>>>,
     indices: Vec<usize>,
+    /// 不同机器人的连杆之间允许碰撞的连杆对，连杆编号在前面各机器人的连杆数之后顺延
+    acm: AllowedCollisionMatrix,
 }
 
 impl<R: DRobot> RobotBranch<R> {
@@ -42,9 +44,15 @@ impl<R: DRobot> RobotBranch<R> {
         RobotBranch {
             robots: Vec::new(),
             indices: Vec::new(),
+            acm: AllowedCollisionMatrix::new(),
         }
     }
 
+    /// 设置不同机器人的连杆之间的允许碰撞矩阵，机器人自身的连杆对由各自的参数决定
+    pub fn set_acm(&mut self, acm: AllowedCollisionMatrix) {
+        self.acm = acm;
+    }
+
     pub fn push(&mut self, robot: Arc<RwLock<R>>) {
         if self.indices.is_empty() {
             self.indices.push(0);
@@ -189,6 +197,71 @@ impl<R: DRobot> RobotBranch<R> {
     }
 }
 
+impl<R: DRobot> RobotBranch<R> {
+    /// 第 id 个机器人在合并向量中对应的部分
+    fn rows_of(&self, id: usize, vector: &na::DVector<f64>) -> na::DVector<f64> {
+        let start = self.indices[id];
+        vector
+            .rows(start, self.indices[id + 1] - start)
+            .into_owned()
+    }
+
+    /// 分支中的连杆编号所属的机器人以及在该机器人中的连杆编号
+    fn locate(&self, q: &na::DVector<f64>, link: usize) -> (usize, usize) {
+        let mut offset = 0;
+        for (id, robot) in self.robots.iter().enumerate() {
+            let nlink = robot
+                .read()
+                .unwrap()
+                .cul_capsules(&self.rows_of(id, q))
+                .len();
+            if link < offset + nlink {
+                return (id, link - offset);
+            }
+            offset += nlink;
+        }
+        panic!("link {} is out of range of the robot branch", link)
+    }
+
+    /// 把第 id 个机器人的梯度累加到合并梯度中
+    fn add_grad(&self, id: usize, grad_i: &na::DVector<f64>, grad: &mut na::DVector<f64>) {
+        let mut rows = grad.rows_mut(self.indices[id], grad_i.len());
+        rows += grad_i;
+    }
+
+    /// 对每个机器人分别计算矩阵后按分块对角排列
+    fn block_diagonal(&self, func: impl Fn(&R, usize) -> na::DMatrix<f64>) -> na::DMatrix<f64> {
+        let blocks: Vec<na::DMatrix<f64>> = self
+            .robots
+            .iter()
+            .enumerate()
+            .map(|(id, robot)| func(&*robot.read().unwrap(), id))
+            .collect();
+        let nrows = blocks.iter().map(|block| block.nrows()).sum();
+        let mut matrix = na::DMatrix::zeros(nrows, *self.indices.last().unwrap_or(&0));
+        let mut row = 0;
+        for (id, block) in blocks.iter().enumerate() {
+            matrix
+                .view_mut((row, self.indices[id]), block.shape())
+                .copy_from(block);
+            row += block.nrows();
+        }
+        matrix
+    }
+
+    /// 对每个机器人分别计算后按顺序拼接
+    fn concat_each(&self, func: impl Fn(&R, usize) -> na::DVector<f64>) -> na::DVector<f64> {
+        let mut combined_vector = na::DVector::zeros(*self.indices.last().unwrap_or(&0));
+        for (id, robot) in self.robots.iter().enumerate() {
+            let vector = func(&*robot.read().unwrap(), id);
+            combined_vector
+                .rows_mut(self.indices[id], vector.len())
+                .copy_from(&vector);
+        }
+        combined_vector
+    }
+}
+
 impl<R: DRobot> Robot<na::DVector<f64>> for RobotBranch<R> {
     merge_fn!(
         q,
@@ -244,8 +317,13 @@ impl<R: DRobot> DRobot for RobotBranch<R> {
         unimplemented!()
     }
 
-    fn cul_capsules(&self, _: &na::DVector<f64>) -> Vec<message::Capsule> {
-        unimplemented!()
+    /// 依次拼接各个机器人的连杆胶囊体，顺序与连杆编号一致
+    fn cul_capsules(&self, q: &na::DVector<f64>) -> Vec<message::Capsule> {
+        self.robots
+            .iter()
+            .enumerate()
+            .flat_map(|(id, robot)| robot.read().unwrap().cul_capsules(&self.rows_of(id, q)))
+            .collect()
     }
 
     fn cul_dis_to_collision(
@@ -261,6 +339,144 @@ impl<R: DRobot> DRobot for RobotBranch<R> {
         )
     }
 
+    /// 依次拼接各个机器人的连杆距离，连杆编号在前面各机器人的连杆数之后顺延
+    fn cul_link_distances(
+        &self,
+        q: &na::DVector<f64>,
+        obj: &message::CollisionObject,
+    ) -> Vec<LinkDistance> {
+        let mut distances = Vec::new();
+        for (id, robot) in self.robots.iter().enumerate() {
+            let offset = distances.len();
+            let robot_distances = robot
+                .read()
+                .unwrap()
+                .cul_link_distances(&self.rows_of(id, q), obj);
+            distances.extend(robot_distances.into_iter().map(|distance| LinkDistance {
+                link: distance.link + offset,
+                ..distance
+            }));
+        }
+        distances
+    }
+
+    /// 找到连杆所属的机器人，其余机器人对应的梯度为零
+    fn cul_distance_grad(&self, q: &na::DVector<f64>, distance: &LinkDistance) -> na::DVector<f64> {
+        let (id, link) = self.locate(q, distance.link);
+        let local = LinkDistance { link, ..*distance };
+        let grad_i = self.robots[id]
+            .read()
+            .unwrap()
+            .cul_distance_grad(&self.rows_of(id, q), &local);
+        let mut grad = na::DVector::zeros(q.len());
+        self.add_grad(id, &grad_i, &mut grad);
+        grad
+    }
+
+    /// 各个机器人自身的连杆对距离，以及不同机器人之间允许碰撞矩阵之外的连杆对距离
+    fn cul_self_distances(&self, q: &na::DVector<f64>) -> Vec<LinkPairDistance> {
+        let capsules: Vec<Vec<message::Capsule>> = self
+            .robots
+            .iter()
+            .enumerate()
+            .map(|(id, robot)| robot.read().unwrap().cul_capsules(&self.rows_of(id, q)))
+            .collect();
+        let offsets: Vec<usize> = capsules
+            .iter()
+            .scan(0, |offset, capsules| {
+                let start = *offset;
+                *offset += capsules.len();
+                Some(start)
+            })
+            .collect();
+
+        let mut distances = Vec::new();
+        for (id, robot) in self.robots.iter().enumerate() {
+            let robot_distances = robot
+                .read()
+                .unwrap()
+                .cul_self_distances(&self.rows_of(id, q));
+            distances.extend(
+                robot_distances
+                    .into_iter()
+                    .map(|distance| LinkPairDistance {
+                        link_a: distance.link_a + offsets[id],
+                        link_b: distance.link_b + offsets[id],
+                        ..distance
+                    }),
+            );
+        }
+        for id_a in 0..capsules.len() {
+            for id_b in id_a + 1..capsules.len() {
+                for (i, capsule_a) in capsules[id_a].iter().enumerate() {
+                    for (j, capsule_b) in capsules[id_b].iter().enumerate() {
+                        let (link_a, link_b) = (offsets[id_a] + i, offsets[id_b] + j);
+                        if self.acm.is_allowed(link_a, link_b) {
+                            continue;
+                        }
+                        distances.push(LinkPairDistance {
+                            link_a,
+                            link_b,
+                            result: CollisionObject::get_distance_result(
+                                &CollisionObject::Capsule(*capsule_a),
+                                &CollisionObject::Capsule(*capsule_b),
+                            ),
+                        });
+                    }
+                }
+            }
+        }
+        distances
+    }
+
+    /// 两个连杆分别对所属机器人的关节角求梯度后合并，同一机器人的连杆对交由该机器人计算
+    fn cul_pair_distance_grad(
+        &self,
+        q: &na::DVector<f64>,
+        distance: &LinkPairDistance,
+    ) -> na::DVector<f64> {
+        let (id_a, link_a) = self.locate(q, distance.link_a);
+        let (id_b, link_b) = self.locate(q, distance.link_b);
+        let mut grad = na::DVector::zeros(q.len());
+        if id_a == id_b {
+            let local = LinkPairDistance {
+                link_a,
+                link_b,
+                ..*distance
+            };
+            let grad_i = self.robots[id_a]
+                .read()
+                .unwrap()
+                .cul_pair_distance_grad(&self.rows_of(id_a, q), &local);
+            self.add_grad(id_a, &grad_i, &mut grad);
+        } else {
+            let (distance_a, distance_b) = distance.split();
+            for (id, distance) in [
+                (
+                    id_a,
+                    LinkDistance {
+                        link: link_a,
+                        ..distance_a
+                    },
+                ),
+                (
+                    id_b,
+                    LinkDistance {
+                        link: link_b,
+                        ..distance_b
+                    },
+                ),
+            ] {
+                let grad_i = self.robots[id]
+                    .read()
+                    .unwrap()
+                    .cul_distance_grad(&self.rows_of(id, q), &distance);
+                self.add_grad(id, &grad_i, &mut grad);
+            }
+        }
+        grad
+    }
+
     fn cul_func(
         &self,
         q: &nalgebra::DVector<f64>,
@@ -281,6 +497,67 @@ impl<R: DRobot> DRobot for RobotBranch<R> {
         (value, grad)
     }
 
+    /// 依次堆叠各个机器人的雅可比矩阵，每个机器人占据六行
+    fn cul_jacobian(&self, q: &na::DVector<f64>, frame: JacobianFrame) -> na::DMatrix<f64> {
+        self.block_diagonal(|robot, id| robot.cul_jacobian(&self.rows_of(id, q), frame))
+    }
+
+    fn cul_jacobian_dot(
+        &self,
+        q: &na::DVector<f64>,
+        q_dot: &na::DVector<f64>,
+        frame: JacobianFrame,
+    ) -> na::DMatrix<f64> {
+        self.block_diagonal(|robot, id| {
+            robot.cul_jacobian_dot(&self.rows_of(id, q), &self.rows_of(id, q_dot), frame)
+        })
+    }
+
+    /// 各个机器人之间没有动力学耦合，质量矩阵为分块对角矩阵
+    fn cul_mass_matrix(&self, q: &na::DVector<f64>) -> na::DMatrix<f64> {
+        self.block_diagonal(|robot, id| robot.cul_mass_matrix(&self.rows_of(id, q)))
+    }
+
+    fn cul_coriolis(&self, q: &na::DVector<f64>, q_dot: &na::DVector<f64>) -> na::DVector<f64> {
+        self.concat_each(|robot, id| {
+            robot.cul_coriolis(&self.rows_of(id, q), &self.rows_of(id, q_dot))
+        })
+    }
+
+    fn cul_gravity(&self, q: &na::DVector<f64>) -> na::DVector<f64> {
+        self.concat_each(|robot, id| robot.cul_gravity(&self.rows_of(id, q)))
+    }
+
+    fn cul_inverse_dynamics(
+        &self,
+        q: &na::DVector<f64>,
+        q_dot: &na::DVector<f64>,
+        q_ddot: &na::DVector<f64>,
+    ) -> na::DVector<f64> {
+        self.concat_each(|robot, id| {
+            robot.cul_inverse_dynamics(
+                &self.rows_of(id, q),
+                &self.rows_of(id, q_dot),
+                &self.rows_of(id, q_ddot),
+            )
+        })
+    }
+
+    fn cul_forward_dynamics(
+        &self,
+        q: &na::DVector<f64>,
+        q_dot: &na::DVector<f64>,
+        tau: &na::DVector<f64>,
+    ) -> na::DVector<f64> {
+        self.concat_each(|robot, id| {
+            robot.cul_forward_dynamics(
+                &self.rows_of(id, q),
+                &self.rows_of(id, q_dot),
+                &self.rows_of(id, tau),
+            )
+        })
+    }
+
     fn reset(&mut self) {
         self.robots
             .iter()
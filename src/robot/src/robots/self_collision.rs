@@ -0,0 +1,95 @@
+use serde::Deserialize;
+use std::collections::BTreeSet;
+
+use super::SeriseRobot;
+use crate::LinkPairDistance;
+use message::CollisionObject;
+
+/// 允许碰撞矩阵，其中记录的连杆对不做碰撞检查，例如相邻连杆的胶囊体在关节处总是重叠。
+/// 配置文件中写作连杆编号对的列表
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(from = "Vec<(usize, usize)>")]
+pub struct AllowedCollisionMatrix {
+    pairs: BTreeSet<(usize, usize)>,
+}
+
+impl From<Vec<(usize, usize)>> for AllowedCollisionMatrix {
+    fn from(pairs: Vec<(usize, usize)>) -> Self {
+        let mut acm = AllowedCollisionMatrix::new();
+        pairs.into_iter().for_each(|(i, j)| acm.allow(i, j));
+        acm
+    }
+}
+
+impl AllowedCollisionMatrix {
+    pub fn new() -> AllowedCollisionMatrix {
+        AllowedCollisionMatrix::default()
+    }
+
+    /// 编号相差不超过 gap 的连杆对允许碰撞，gap 为 1 时只跳过相邻连杆
+    pub fn adjacent(nlink: usize, gap: usize) -> AllowedCollisionMatrix {
+        let mut acm = AllowedCollisionMatrix::new();
+        for i in 0..nlink {
+            for j in i + 1..(i + gap + 1).min(nlink) {
+                acm.allow(i, j);
+            }
+        }
+        acm
+    }
+
+    pub fn allow(&mut self, i: usize, j: usize) {
+        self.pairs.insert((i.min(j), i.max(j)));
+    }
+
+    pub fn disallow(&mut self, i: usize, j: usize) {
+        self.pairs.remove(&(i.min(j), i.max(j)));
+    }
+
+    pub fn is_allowed(&self, i: usize, j: usize) -> bool {
+        i == j || self.pairs.contains(&(i.min(j), i.max(j)))
+    }
+
+    /// 合并另一个矩阵中允许碰撞的连杆对
+    pub fn extend(&mut self, other: &AllowedCollisionMatrix) {
+        self.pairs.extend(other.pairs.iter());
+    }
+
+    /// nlink 个连杆中需要检查碰撞的连杆对，每对中编号较小的在前
+    pub fn checked_pairs(&self, nlink: usize) -> Vec<(usize, usize)> {
+        (0..nlink)
+            .flat_map(|i| (i + 1..nlink).map(move |j| (i, j)))
+            .filter(|&(i, j)| !self.is_allowed(i, j))
+            .collect()
+    }
+}
+
+/// 静态与动态串联机器人共用的自碰撞检查
+impl<V> SeriseRobot<V> {
+    /// 允许碰撞矩阵之外的各对连杆胶囊体之间的带符号距离
+    pub(crate) fn self_distances(&self, q: &[f64]) -> Vec<LinkPairDistance> {
+        let capsules = self.fk_capsules(q);
+        self.params
+            .acm
+            .checked_pairs(capsules.len())
+            .into_iter()
+            .map(|(link_a, link_b)| LinkPairDistance {
+                link_a,
+                link_b,
+                result: CollisionObject::get_distance_result(
+                    &CollisionObject::Capsule(capsules[link_a]),
+                    &CollisionObject::Capsule(capsules[link_b]),
+                ),
+            })
+            .collect()
+    }
+
+    /// 连杆对距离对关节角的解析梯度，即两个连杆各自到对方胶囊体的距离梯度之和
+    pub(crate) fn pair_distance_grad(&self, q: &[f64], distance: &LinkPairDistance) -> Vec<f64> {
+        let (link_a, link_b) = distance.split();
+        self.distance_grad(q, &link_a)
+            .into_iter()
+            .zip(self.distance_grad(q, &link_b))
+            .map(|(a, b)| a + b)
+            .collect()
+    }
+}
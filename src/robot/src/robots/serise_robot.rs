@@ -1,6 +1,10 @@
 use nalgebra as na;
 
-use crate::{DRobot, Robot, SRobot};
+use super::dynamics::{aba, rnea, LinkInertia, GRAVITY};
+use crate::{
+    AllowedCollisionMatrix, DRobot, DhConvention, JacobianFrame, LinkDistance, LinkPairDistance,
+    Robot, SRobot,
+};
 use generate_tools::{get_fn, set_fn};
 use message::{Capsule, CollisionObject};
 use message::{NodeMessage, Pose};
@@ -37,8 +41,16 @@ pub struct SeriseRobotParams<V> {
     pub q_jerk_bound: V,
     pub tau_bound: V,
     pub tau_dot_bound: V,
+    /// dh 的约定，前 nlink 行对应各个关节，其余各行为法兰之前的固定变换
+    pub convention: DhConvention,
     pub dh: na::DMatrix<f64>,
+    /// 工具坐标系在法兰坐标系中的位姿，末端执行器位姿即工具坐标系的位姿
+    pub tool: Pose,
     pub capsules: Vec<Capsule>,
+    /// 自碰撞检查时跳过的连杆对，编号与 capsules 一致
+    pub acm: AllowedCollisionMatrix,
+    /// 各个连杆的惯性参数，与 dh 的前 nlink 行一一对应
+    pub inertials: Vec<LinkInertia>,
 }
 
 impl<V> SeriseRobot<V> {
@@ -49,9 +61,6 @@ impl<V> SeriseRobot<V> {
     //         params,
     //     }
     // }
-    pub fn from_file() -> SeriseRobot<V> {
-        unimplemented!()
-    }
 }
 
 impl<V> Robot<V> for SeriseRobot<V>
@@ -90,6 +99,87 @@ where
     }
 }
 
+impl DSeriseRobot {
+    /// 世界坐标系下的几何雅可比矩阵，frames 为各个关节坐标系，参考点为 end
+    fn world_jacobian(frames: &[Pose], end: &na::Vector3<f64>) -> na::DMatrix<f64> {
+        let mut jacobian = na::DMatrix::zeros(6, frames.len());
+        for (i, frame) in frames.iter().enumerate() {
+            let z = frame.rotation * na::Vector3::z();
+            let linear = z.cross(&(end - frame.translation.vector));
+            jacobian.fixed_view_mut::<3, 1>(0, i).copy_from(&linear);
+            jacobian.fixed_view_mut::<3, 1>(3, i).copy_from(&z);
+        }
+        jacobian
+    }
+
+    /// 世界坐标系下几何雅可比矩阵对时间的导数
+    fn world_jacobian_dot(
+        frames: &[Pose],
+        end: &na::Vector3<f64>,
+        q_dot: &na::DVector<f64>,
+    ) -> na::DMatrix<f64> {
+        let jacobian = Self::world_jacobian(frames, end);
+        let end_velocity = jacobian.fixed_rows::<3>(0) * q_dot;
+
+        let mut jacobian_dot = na::DMatrix::zeros(6, frames.len());
+        // 上一个连杆坐标系的角速度、原点以及原点的速度，基座静止
+        let mut omega = na::Vector3::zeros();
+        let mut origin = frames
+            .first()
+            .map_or(na::Vector3::zeros(), |frame| frame.translation.vector);
+        let mut origin_velocity = na::Vector3::zeros();
+        for (i, frame) in frames.iter().enumerate() {
+            // 第 i 个关节坐标系的原点与 z 轴固连在上一个连杆上
+            origin_velocity += omega.cross(&(frame.translation.vector - origin));
+            origin = frame.translation.vector;
+            let z = frame.rotation * na::Vector3::z();
+            let z_dot = omega.cross(&z);
+
+            let linear = z_dot.cross(&(end - origin)) + z.cross(&(end_velocity - origin_velocity));
+            jacobian_dot.fixed_view_mut::<3, 1>(0, i).copy_from(&linear);
+            jacobian_dot.fixed_view_mut::<3, 1>(3, i).copy_from(&z_dot);
+            omega += z * q_dot[i];
+        }
+        jacobian_dot
+    }
+
+    /// 基座坐标系下的重力加速度
+    fn base_gravity(&self) -> na::Vector3<f64> {
+        self.state.base.rotation.inverse() * na::Vector3::new(0.0, 0.0, -GRAVITY)
+    }
+
+    fn inverse_dynamics_with(
+        &self,
+        q: &na::DVector<f64>,
+        q_dot: &na::DVector<f64>,
+        q_ddot: &na::DVector<f64>,
+        gravity: &na::Vector3<f64>,
+    ) -> na::DVector<f64> {
+        let params = &self.params;
+        rnea(
+            params.convention,
+            &params.dh,
+            &self.joint_inertials(),
+            q,
+            q_dot,
+            q_ddot,
+            gravity,
+        )
+    }
+
+    /// 各个连杆在其关节坐标系下的惯性参数
+    fn joint_inertials(&self) -> Vec<LinkInertia> {
+        let params = &self.params;
+        params.inertials[..params.nlink]
+            .iter()
+            .enumerate()
+            .map(|(i, inertial)| {
+                inertial.transformed(&params.convention.joint_to_link(&params.dh, i))
+            })
+            .collect()
+    }
+}
+
 impl DRobot for DSeriseRobot {
     fn end_pose(&self) -> Pose {
         self.cul_end_pose(&self.state.q)
@@ -111,53 +201,12 @@ impl DRobot for DSeriseRobot {
 
     /// 给定机器人的广义变量，计算末端执行器位姿
     fn cul_end_pose(&self, q: &na::DVector<f64>) -> Pose {
-        let dh = &self.params.dh;
-        let mut isometry = self.state.base;
-        for i in 0..self.params.nlink {
-            let d = dh[(i, 1)];
-            let a = dh[(i, 2)];
-            let alpha = dh[(i, 3)];
-            let theta = q[i] + dh[(i, 0)];
-
-            let rotation = na::UnitQuaternion::from_axis_angle(&na::Vector3::x_axis(), alpha)
-                * na::UnitQuaternion::from_axis_angle(&na::Vector3::z_axis(), theta);
-            let transform = na::Translation3::new(a, -d * alpha.sin(), d * alpha.cos());
-
-            let isometry_increment = na::Isometry3::from_parts(transform, rotation);
-
-            isometry *= isometry_increment;
-        }
-        isometry
+        self.fk_end_pose(q.as_slice())
     }
 
     /// 给定机器人的广义变量，计算机器人对应的所有胶囊体，这里需要留意的是，此时的胶囊体不包括末端执行器以及所夹取的物体。
     fn cul_capsules(&self, q: &na::DVector<f64>) -> Vec<Capsule> {
-        let dh = &self.params.dh;
-        let mut capsules = Vec::new();
-        let mut isometry = self.state.base;
-
-        for i in 0..self.params.nlink {
-            let d = dh[(i, 1)];
-            let a = dh[(i, 2)];
-            let alpha = dh[(i, 3)];
-            let theta = q[i] + dh[(i, 0)];
-
-            let rotation = na::UnitQuaternion::from_axis_angle(&na::Vector3::x_axis(), alpha)
-                * na::UnitQuaternion::from_axis_angle(&na::Vector3::z_axis(), theta);
-            let transform = na::Translation3::new(a, -d * alpha.sin(), d * alpha.cos());
-
-            let isometry_increment = na::Isometry3::from_parts(transform, rotation);
-
-            // Update the cumulative transformation matrix
-            isometry *= isometry_increment;
-
-            // Create a new Capsule object and add it to the vector
-            capsules.push(Capsule {
-                pose: isometry,
-                ..self.params.capsules[i]
-            });
-        }
-        capsules
+        self.fk_capsules(q.as_slice())
     }
 
     /// 给定机器人的广义变量，计算机器人到碰撞体的最小距禂
@@ -166,15 +215,135 @@ impl DRobot for DSeriseRobot {
         q: &nalgebra::DVector<f64>,
         obj: &message::CollisionObject,
     ) -> na::DVector<f64> {
-        let capsules = self.cul_capsules(q);
-        let dis = capsules
+        let dis = self
+            .cul_link_distances(q, obj)
             .iter()
-            .map(|&c| CollisionObject::get_distance(&CollisionObject::Capsule(c), obj))
+            .map(|distance| distance.result.distance)
             .min_by(|a, b| a.partial_cmp(b).unwrap())
             .unwrap();
         na::DVector::from_element(1, dis)
     }
 
+    /// 给定机器人的广义变量，计算各个连杆到碰撞体的距离、最近点与法向量
+    fn cul_link_distances(&self, q: &na::DVector<f64>, obj: &CollisionObject) -> Vec<LinkDistance> {
+        self.link_distances(q.as_slice(), obj)
+    }
+
+    /// 连杆到碰撞体的距离对关节角的解析梯度
+    fn cul_distance_grad(&self, q: &na::DVector<f64>, distance: &LinkDistance) -> na::DVector<f64> {
+        na::DVector::from_vec(self.distance_grad(q.as_slice(), distance))
+    }
+
+    /// 给定机器人的广义变量，计算允许碰撞矩阵之外的各对连杆之间的距离
+    fn cul_self_distances(&self, q: &na::DVector<f64>) -> Vec<LinkPairDistance> {
+        self.self_distances(q.as_slice())
+    }
+
+    /// 连杆对之间的距离对关节角的解析梯度
+    fn cul_pair_distance_grad(
+        &self,
+        q: &na::DVector<f64>,
+        distance: &LinkPairDistance,
+    ) -> na::DVector<f64> {
+        na::DVector::from_vec(self.pair_distance_grad(q.as_slice(), distance))
+    }
+
+    /// 几何雅可比矩阵，参考点为末端执行器，即 cul_end_pose 的原点
+    fn cul_jacobian(&self, q: &na::DVector<f64>, frame: JacobianFrame) -> na::DMatrix<f64> {
+        let frames = self.fk_joint_frames(q.as_slice());
+        let end = self.fk_end_pose(q.as_slice());
+        let jacobian = Self::world_jacobian(&frames, &end.translation.vector);
+        match frame {
+            JacobianFrame::World => jacobian,
+            JacobianFrame::Base => rotate_jacobian(&self.state.base.rotation.inverse(), &jacobian),
+            JacobianFrame::End => rotate_jacobian(&end.rotation.inverse(), &jacobian),
+        }
+    }
+
+    /// 几何雅可比矩阵对时间的导数，末端坐标系下需要计入坐标系自身的转动
+    fn cul_jacobian_dot(
+        &self,
+        q: &na::DVector<f64>,
+        q_dot: &na::DVector<f64>,
+        frame: JacobianFrame,
+    ) -> na::DMatrix<f64> {
+        let frames = self.fk_joint_frames(q.as_slice());
+        let end = self.fk_end_pose(q.as_slice());
+        let jacobian_dot = Self::world_jacobian_dot(&frames, &end.translation.vector, q_dot);
+        match frame {
+            JacobianFrame::World => jacobian_dot,
+            JacobianFrame::Base => {
+                rotate_jacobian(&self.state.base.rotation.inverse(), &jacobian_dot)
+            }
+            JacobianFrame::End => {
+                let jacobian = Self::world_jacobian(&frames, &end.translation.vector);
+                let omega_cross = (jacobian.fixed_rows::<3>(3) * q_dot).cross_matrix();
+                let mut correction = na::DMatrix::zeros(6, jacobian.ncols());
+                correction
+                    .rows_mut(0, 3)
+                    .copy_from(&(omega_cross * jacobian.rows(0, 3)));
+                correction
+                    .rows_mut(3, 3)
+                    .copy_from(&(omega_cross * jacobian.rows(3, 3)));
+                rotate_jacobian(&end.rotation.inverse(), &(jacobian_dot - correction))
+            }
+        }
+    }
+
+    /// 质量矩阵 M(q)，第 i 列为只有第 i 个关节存在单位加速度且不计重力时的关节力矩
+    fn cul_mass_matrix(&self, q: &na::DVector<f64>) -> na::DMatrix<f64> {
+        let dof = self.params.nlink;
+        let zeros = na::DVector::zeros(dof);
+        let mut mass_matrix = na::DMatrix::zeros(dof, dof);
+        for i in 0..dof {
+            let mut q_ddot = zeros.clone();
+            q_ddot[i] = 1.0;
+            let column = self.inverse_dynamics_with(q, &zeros, &q_ddot, &na::Vector3::zeros());
+            mass_matrix.set_column(i, &column);
+        }
+        mass_matrix
+    }
+
+    /// 科氏力与离心力项 C(q, q_dot) q_dot
+    fn cul_coriolis(&self, q: &na::DVector<f64>, q_dot: &na::DVector<f64>) -> na::DVector<f64> {
+        let zeros = na::DVector::zeros(self.params.nlink);
+        self.inverse_dynamics_with(q, q_dot, &zeros, &na::Vector3::zeros())
+    }
+
+    /// 重力项 g(q)
+    fn cul_gravity(&self, q: &na::DVector<f64>) -> na::DVector<f64> {
+        let zeros = na::DVector::zeros(self.params.nlink);
+        self.inverse_dynamics_with(q, &zeros, &zeros, &self.base_gravity())
+    }
+
+    /// 逆动力学 M(q) q_ddot + C(q, q_dot) q_dot + g(q)，使用递归牛顿-欧拉法
+    fn cul_inverse_dynamics(
+        &self,
+        q: &na::DVector<f64>,
+        q_dot: &na::DVector<f64>,
+        q_ddot: &na::DVector<f64>,
+    ) -> na::DVector<f64> {
+        self.inverse_dynamics_with(q, q_dot, q_ddot, &self.base_gravity())
+    }
+
+    /// 正动力学，给定关节力矩求关节加速度，使用铰接体算法
+    fn cul_forward_dynamics(
+        &self,
+        q: &na::DVector<f64>,
+        q_dot: &na::DVector<f64>,
+        tau: &na::DVector<f64>,
+    ) -> na::DVector<f64> {
+        aba(
+            self.params.convention,
+            &self.params.dh,
+            &self.joint_inertials(),
+            q,
+            q_dot,
+            tau,
+            &self.base_gravity(),
+        )
+    }
+
     /// 给任意函数的梯度
     fn cul_func(
         &self,
@@ -197,6 +366,57 @@ impl DRobot for DSeriseRobot {
     }
 }
 
+/// 将雅可比矩阵的线速度部分与角速度部分分别旋转到另一个坐标系
+fn rotate_jacobian(
+    rotation: &na::UnitQuaternion<f64>,
+    jacobian: &na::DMatrix<f64>,
+) -> na::DMatrix<f64> {
+    let rotation = rotation.to_rotation_matrix().into_inner();
+    let mut rotated = na::DMatrix::zeros(jacobian.nrows(), jacobian.ncols());
+    rotated
+        .rows_mut(0, 3)
+        .copy_from(&(rotation * jacobian.rows(0, 3)));
+    rotated
+        .rows_mut(3, 3)
+        .copy_from(&(rotation * jacobian.rows(3, 3)));
+    rotated
+}
+
+impl<const N: usize> SSeriseRobot<N> {
+    /// 转换为变长的机器人，运动学、碰撞与动力学参数保持不变，用于复用只对 DRobot 实现的算法
+    pub fn to_dynamic(&self) -> DSeriseRobot {
+        let dynamic = |v: &na::SVector<f64, N>| na::DVector::from_column_slice(v.as_slice());
+        SeriseRobot {
+            name: self.name.clone(),
+            state: SeriseRobotState {
+                q: dynamic(&self.state.q),
+                q_dot: dynamic(&self.state.q_dot),
+                q_ddot: dynamic(&self.state.q_ddot),
+                q_jerk: dynamic(&self.state.q_jerk),
+                base: self.state.base,
+                control_message: self.state.control_message.clone().to_dynamic(),
+            },
+            params: SeriseRobotParams {
+                nlink: self.params.nlink,
+                q_default: dynamic(&self.params.q_default),
+                q_min_bound: dynamic(&self.params.q_min_bound),
+                q_max_bound: dynamic(&self.params.q_max_bound),
+                q_dot_bound: dynamic(&self.params.q_dot_bound),
+                q_ddot_bound: dynamic(&self.params.q_ddot_bound),
+                q_jerk_bound: dynamic(&self.params.q_jerk_bound),
+                tau_bound: dynamic(&self.params.tau_bound),
+                tau_dot_bound: dynamic(&self.params.tau_dot_bound),
+                convention: self.params.convention,
+                dh: self.params.dh.clone(),
+                tool: self.params.tool,
+                capsules: self.params.capsules.clone(),
+                acm: self.params.acm.clone(),
+                inertials: self.params.inertials.clone(),
+            },
+        }
+    }
+}
+
 impl<const N: usize> SRobot<N> for SSeriseRobot<N> {
     fn end_pose(&self) -> Pose {
         self.cul_end_pose(&self.state.q)
@@ -218,49 +438,12 @@ impl<const N: usize> SRobot<N> for SSeriseRobot<N> {
 
     /// 给定机器人的广义变量，计算末端执行器位姿
     fn cul_end_pose(&self, q: &na::SVector<f64, N>) -> Pose {
-        let dh = &self.params.dh;
-        let mut isometry = self.state.base;
-
-        for i in 0..self.params.nlink {
-            let translation = na::Translation3::new(
-                dh[(i, 2)],
-                -dh[(i, 1)] * dh[(i, 3)].sin(),
-                dh[(i, 1)] * dh[(i, 3)].cos(),
-            );
-            let rotation = na::UnitQuaternion::from_euler_angles(q[i], 0.0, dh[(i, 3)]);
-            let isometry_increment = na::Isometry::from_parts(translation, rotation);
-
-            isometry *= isometry_increment;
-        }
-
-        isometry
+        self.fk_end_pose(q.as_slice())
     }
 
     /// 给定机器人的广义变量，计算机器人对应的所有胶囊体，这里需要留意的是，此时的胶囊体不包括末端执行器以及所夹取的物体。
     fn cul_capsules(&self, q: &nalgebra::SVector<f64, N>) -> Vec<Capsule> {
-        let dh = &self.params.dh;
-        let mut capsules = Vec::new();
-        let mut isometry = self.state.base;
-
-        for i in 0..self.params.nlink {
-            let translation = na::Translation3::new(
-                dh[(i, 2)],
-                -dh[(i, 1)] * dh[(i, 3)].sin(),
-                dh[(i, 1)] * dh[(i, 3)].cos(),
-            );
-            let rotation = na::UnitQuaternion::from_euler_angles(q[i], 0.0, dh[(i, 3)]);
-            let isometry_increment = na::Isometry::from_parts(translation, rotation);
-
-            // Update the cumulative transformation matrix
-            isometry *= isometry_increment;
-
-            // Create a new Capsule object and add it to the vector
-            capsules.push(Capsule {
-                pose: isometry,
-                ..capsules[i]
-            });
-        }
-        capsules
+        self.fk_capsules(q.as_slice())
     }
 
     /// 给定机器人的广义变量，计算机器人到碰撞体的最小距禂
@@ -269,31 +452,42 @@ impl<const N: usize> SRobot<N> for SSeriseRobot<N> {
         q: &nalgebra::SVector<f64, N>,
         obj: &message::CollisionObject,
     ) -> f64 {
-        let capsules = self.cul_capsules(q);
-        capsules
+        self.cul_link_distances(q, obj)
             .iter()
-            .map(|&c| CollisionObject::get_distance(&CollisionObject::Capsule(c), obj))
+            .map(|distance| distance.result.distance)
             .min_by(|a, b| a.partial_cmp(b).unwrap())
             .unwrap()
     }
 
-    /// 给定机器人的广义变量，计算机器人到碰撞体的最小距概的梯度
+    /// 给定机器人的广义变量，计算机器人到碰撞体的最小距概的梯度，即最近连杆的距离梯度
     fn cul_dis_grad_to_collision(
         &self,
         q: &nalgebra::SVector<f64, N>,
         obj: &message::CollisionObject,
     ) -> nalgebra::SVector<f64, N> {
-        let mut dis_grad = na::SVector::zeros();
-        let epsilon = 1e-3;
-        for i in 0..self.params.nlink {
-            let mut q_plus = *q;
-            q_plus[i] += epsilon;
-            let mut q_minus = *q;
-            q_minus[i] -= epsilon;
-            let dis_plus = self.cul_dis_to_collision(&q_plus, obj);
-            let dis_minus = self.cul_dis_to_collision(&q_minus, obj);
-            dis_grad[i] = (dis_plus - dis_minus) / (2.0 * epsilon);
-        }
-        dis_grad
+        let closest = self
+            .cul_link_distances(q, obj)
+            .into_iter()
+            .min_by(|a, b| a.result.distance.partial_cmp(&b.result.distance).unwrap())
+            .unwrap();
+        self.cul_distance_grad(q, &closest)
+    }
+
+    /// 给定机器人的广义变量，计算各个连杆到碰撞体的距离、最近点与法向量
+    fn cul_link_distances(
+        &self,
+        q: &na::SVector<f64, N>,
+        obj: &CollisionObject,
+    ) -> Vec<LinkDistance> {
+        self.link_distances(q.as_slice(), obj)
+    }
+
+    /// 连杆到碰撞体的距离对关节角的解析梯度
+    fn cul_distance_grad(
+        &self,
+        q: &na::SVector<f64, N>,
+        distance: &LinkDistance,
+    ) -> na::SVector<f64, N> {
+        na::SVector::from_column_slice(&self.distance_grad(q.as_slice(), distance))
     }
 }
@@ -0,0 +1,581 @@
+use nalgebra as na;
+use std::f64::consts::PI;
+
+use super::dynamics::LinkInertia;
+use crate::{
+    AllowedCollisionMatrix, DSeriseRobot, DhConvention, RobotError, SeriseRobotParams,
+    SeriseRobotState,
+};
+use message::{Capsule, NodeMessage, Pose};
+
+/// 连杆没有可用的碰撞几何体（例如只有网格）时，沿关节之间的连线生成胶囊体所用的半径
+const DEFAULT_CAPSULE_RADIUS: f64 = 0.05;
+
+const EPSILON: f64 = 1e-9;
+
+enum Geometry {
+    Box(na::Vector3<f64>),
+    Cylinder(f64, f64),
+    Sphere(f64),
+    /// 网格等无法直接转换为胶囊体的几何体
+    Other,
+}
+
+struct UrdfInertial {
+    origin: Pose,
+    mass: f64,
+    inertia: na::Matrix3<f64>,
+}
+
+struct UrdfLink {
+    name: String,
+    inertial: Option<UrdfInertial>,
+    collisions: Vec<(Pose, Geometry)>,
+}
+
+struct UrdfJoint {
+    name: String,
+    joint_type: String,
+    parent: String,
+    child: String,
+    origin: Pose,
+    axis: na::Vector3<f64>,
+    /// lower, upper, effort, velocity
+    limit: Option<[f64; 4]>,
+}
+
+impl UrdfJoint {
+    fn is_revolute(&self) -> bool {
+        self.joint_type == "revolute" || self.joint_type == "continuous"
+    }
+}
+
+fn invalid(message: impl Into<String>) -> RobotError {
+    RobotError::InvalidUrdf(message.into())
+}
+
+/// 解析以空格分隔的数值属性，属性缺失时使用默认值
+fn parse_floats<const N: usize>(
+    node: Option<roxmltree::Node>,
+    attribute: &str,
+    default: [f64; N],
+) -> Result<[f64; N], RobotError> {
+    let Some(text) = node.and_then(|node| node.attribute(attribute)) else {
+        return Ok(default);
+    };
+    let values = text
+        .split_whitespace()
+        .map(|value| value.parse::<f64>())
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|_| invalid(format!("invalid number in \"{}\"", text)))?;
+    values
+        .try_into()
+        .map_err(|_| invalid(format!("expected {} numbers in \"{}\"", N, text)))
+}
+
+fn child<'a, 'input>(
+    node: roxmltree::Node<'a, 'input>,
+    name: &str,
+) -> Option<roxmltree::Node<'a, 'input>> {
+    node.children().find(|child| child.has_tag_name(name))
+}
+
+fn parse_origin(node: roxmltree::Node) -> Result<Pose, RobotError> {
+    let origin = child(node, "origin");
+    let [x, y, z] = parse_floats(origin, "xyz", [0.0; 3])?;
+    let [roll, pitch, yaw] = parse_floats(origin, "rpy", [0.0; 3])?;
+    Ok(Pose::from_parts(
+        na::Translation3::new(x, y, z),
+        na::UnitQuaternion::from_euler_angles(roll, pitch, yaw),
+    ))
+}
+
+fn parse_geometry(node: roxmltree::Node) -> Result<Geometry, RobotError> {
+    let Some(shape) = child(node, "geometry")
+        .and_then(|geometry| geometry.children().find(|child| child.is_element()))
+    else {
+        return Err(invalid("collision without geometry"));
+    };
+    let geometry = match shape.tag_name().name() {
+        "box" => Geometry::Box(na::Vector3::from(parse_floats(
+            Some(shape),
+            "size",
+            [0.0; 3],
+        )?)),
+        "cylinder" => {
+            let [radius] = parse_floats(Some(shape), "radius", [0.0])?;
+            let [length] = parse_floats(Some(shape), "length", [0.0])?;
+            Geometry::Cylinder(radius, length)
+        }
+        "sphere" => Geometry::Sphere(parse_floats(Some(shape), "radius", [0.0])?[0]),
+        _ => Geometry::Other,
+    };
+    Ok(geometry)
+}
+
+fn parse_link(node: roxmltree::Node) -> Result<UrdfLink, RobotError> {
+    let inertial = match child(node, "inertial") {
+        Some(inertial) => {
+            let [mass] = parse_floats(child(inertial, "mass"), "value", [0.0])?;
+            let inertia = child(inertial, "inertia");
+            let mut values = [0.0; 6];
+            for (value, name) in values
+                .iter_mut()
+                .zip(["ixx", "ixy", "ixz", "iyy", "iyz", "izz"])
+            {
+                *value = parse_floats(inertia, name, [0.0])?[0];
+            }
+            Some(UrdfInertial {
+                origin: parse_origin(inertial)?,
+                mass,
+                inertia: LinkInertia::new(mass, [0.0; 3], values).inertia,
+            })
+        }
+        None => None,
+    };
+    let collisions = node
+        .children()
+        .filter(|child| child.has_tag_name("collision"))
+        .map(|collision| Ok((parse_origin(collision)?, parse_geometry(collision)?)))
+        .collect::<Result<Vec<_>, RobotError>>()?;
+    Ok(UrdfLink {
+        name: node.attribute("name").unwrap_or_default().to_string(),
+        inertial,
+        collisions,
+    })
+}
+
+fn parse_joint(node: roxmltree::Node) -> Result<UrdfJoint, RobotError> {
+    let name = node.attribute("name").unwrap_or_default().to_string();
+    let link = |tag: &str| {
+        child(node, tag)
+            .and_then(|link| link.attribute("link"))
+            .map(str::to_string)
+            .ok_or_else(|| invalid(format!("joint {} without {}", name, tag)))
+    };
+    let axis = na::Vector3::from(parse_floats(child(node, "axis"), "xyz", [1.0, 0.0, 0.0])?);
+    let limit = match child(node, "limit") {
+        Some(limit) => Some([
+            parse_floats(Some(limit), "lower", [0.0])?[0],
+            parse_floats(Some(limit), "upper", [0.0])?[0],
+            parse_floats(Some(limit), "effort", [0.0])?[0],
+            parse_floats(Some(limit), "velocity", [0.0])?[0],
+        ]),
+        None => None,
+    };
+    Ok(UrdfJoint {
+        joint_type: node.attribute("type").unwrap_or_default().to_string(),
+        parent: link("parent")?,
+        child: link("child")?,
+        origin: parse_origin(node)?,
+        axis: axis
+            .try_normalize(EPSILON)
+            .ok_or_else(|| invalid(format!("joint {} with zero axis", name)))?,
+        limit,
+        name,
+    })
+}
+
+/// 由根连杆出发，每次进入子树中转动关节最多的分支，得到串联链上的所有关节；
+/// 最后一个转动关节之后仅沿唯一的固定关节延伸到末端连杆
+fn serial_chain<'a>(
+    links: &[UrdfLink],
+    joints: &'a [UrdfJoint],
+) -> Result<Vec<&'a UrdfJoint>, RobotError> {
+    let root = links
+        .iter()
+        .find(|link| joints.iter().all(|joint| joint.child != link.name))
+        .ok_or_else(|| invalid("no root link"))?;
+
+    fn count_revolute(joints: &[UrdfJoint], link: &str) -> usize {
+        joints
+            .iter()
+            .filter(|joint| joint.parent == link)
+            .map(|joint| usize::from(joint.is_revolute()) + count_revolute(joints, &joint.child))
+            .sum()
+    }
+
+    let mut chain = Vec::new();
+    let mut link = root.name.as_str();
+    loop {
+        let children: Vec<&UrdfJoint> =
+            joints.iter().filter(|joint| joint.parent == link).collect();
+        let next = children
+            .iter()
+            .map(|joint| {
+                (
+                    usize::from(joint.is_revolute()) + count_revolute(joints, &joint.child),
+                    *joint,
+                )
+            })
+            .max_by_key(|(count, _)| *count);
+        let joint = match next {
+            Some((count, joint)) if count > 0 => joint,
+            _ if children.len() == 1 && children[0].joint_type == "fixed" => children[0],
+            _ => break,
+        };
+        if !joint.is_revolute() && joint.joint_type != "fixed" {
+            return Err(invalid(format!(
+                "unsupported joint type {} of joint {}",
+                joint.joint_type, joint.name
+            )));
+        }
+        chain.push(joint);
+        link = joint.child.as_str();
+    }
+    Ok(chain)
+}
+
+/// 两条直线之间公垂线在第一条直线上的垂足，以及由该垂足指向第二条直线的公垂线方向，两直线平行时返回 None
+fn common_normal(
+    p1: &na::Vector3<f64>,
+    z1: &na::Vector3<f64>,
+    p2: &na::Vector3<f64>,
+    z2: &na::Vector3<f64>,
+) -> Option<(na::Vector3<f64>, na::Vector3<f64>)> {
+    let cross = z1.cross(z2);
+    let sin2 = cross.norm_squared();
+    if sin2 < EPSILON {
+        return None;
+    }
+    let diff = p2 - p1;
+    let t1 = diff.cross(z2).dot(&cross) / sin2;
+    let t2 = diff.cross(z1).dot(&cross) / sin2;
+    let foot1 = p1 + z1 * t1;
+    let foot2 = p2 + z2 * t2;
+    let normal = (foot2 - foot1)
+        .try_normalize(EPSILON)
+        .unwrap_or_else(|| cross.normalize());
+    Some((foot1, normal))
+}
+
+/// 将向量投影到与 z 垂直的平面上并归一化，投影退化时返回任意一个垂直方向
+fn perpendicular(v: &na::Vector3<f64>, z: &na::Vector3<f64>) -> na::Vector3<f64> {
+    (v - z * z.dot(v))
+        .try_normalize(EPSILON)
+        .unwrap_or_else(|| {
+            let helper = if z.x.abs() < 0.9 {
+                na::Vector3::x()
+            } else {
+                na::Vector3::y()
+            };
+            z.cross(&helper).normalize()
+        })
+}
+
+fn frame(origin: na::Vector3<f64>, x: na::Vector3<f64>, z: na::Vector3<f64>) -> Pose {
+    let rotation = na::Rotation3::from_basis_unchecked(&[x, z.cross(&x), z]);
+    Pose::from_parts(
+        na::Translation3::from(origin),
+        na::UnitQuaternion::from_rotation_matrix(&rotation),
+    )
+}
+
+/// 相邻两个坐标系之间的改进 DH 参数，顺序与 SeriseRobotParams::dh 的列一致
+fn dh_row(from: &Pose, to: &Pose) -> [f64; 4] {
+    let (x0, z0) = (
+        from.rotation * na::Vector3::x(),
+        from.rotation * na::Vector3::z(),
+    );
+    let (x1, z1) = (
+        to.rotation * na::Vector3::x(),
+        to.rotation * na::Vector3::z(),
+    );
+    let diff = to.translation.vector - from.translation.vector;
+    let alpha = z0.cross(&z1).dot(&x0).atan2(z0.dot(&z1));
+    let theta = x0.cross(&x1).dot(&z1).atan2(x0.dot(&x1));
+    [theta, diff.dot(&z1), diff.dot(&x0), alpha]
+}
+
+/// 若干惯性参数在同一坐标系下的合成
+fn combine_inertials(parts: &[(f64, na::Vector3<f64>, na::Matrix3<f64>)]) -> LinkInertia {
+    let mass: f64 = parts.iter().map(|(mass, _, _)| mass).sum();
+    if mass <= 0.0 {
+        return LinkInertia {
+            mass: 0.0,
+            com: na::Vector3::zeros(),
+            inertia: na::Matrix3::zeros(),
+        };
+    }
+    let com = parts
+        .iter()
+        .map(|(mass, com, _)| com * *mass)
+        .sum::<na::Vector3<f64>>()
+        / mass;
+    let inertia = parts
+        .iter()
+        .map(|(mass, part_com, inertia)| {
+            let r = part_com - com;
+            inertia + *mass * (r.norm_squared() * na::Matrix3::identity() - r * r.transpose())
+        })
+        .sum();
+    LinkInertia { mass, com, inertia }
+}
+
+/// 包围若干线段胶囊体的单个胶囊体：以相距最远的两个端点为轴线，半径覆盖所有线段胶囊体
+fn enclosing_capsule(segments: &[(na::Vector3<f64>, na::Vector3<f64>, f64)]) -> Capsule {
+    let points: Vec<na::Vector3<f64>> = segments.iter().flat_map(|(a, b, _)| [*a, *b]).collect();
+    let mut axis = (points[0], points[0]);
+    for a in points.iter() {
+        for b in points.iter() {
+            if (a - b).norm() > (axis.0 - axis.1).norm() {
+                axis = (*a, *b);
+            }
+        }
+    }
+    let distance = |point: &na::Vector3<f64>| {
+        let direction = axis.1 - axis.0;
+        let t = if direction.norm_squared() < EPSILON {
+            0.0
+        } else {
+            ((point - axis.0).dot(&direction) / direction.norm_squared()).clamp(0.0, 1.0)
+        };
+        (point - (axis.0 + direction * t)).norm()
+    };
+    let radius = segments
+        .iter()
+        .map(|(a, b, radius)| distance(a).max(distance(b)) + radius)
+        .fold(0.0, f64::max);
+    let (start, end) = axis;
+    Capsule::from_vec(vec![start.x, start.y, start.z, end.x, end.y, end.z, radius])
+}
+
+impl DSeriseRobot {
+    /// 读取 URDF 文件生成串联机器人
+    pub fn from_file(name: String, path: &str, base: Pose) -> Result<DSeriseRobot, RobotError> {
+        let text = std::fs::read_to_string(path)
+            .map_err(|error| RobotError::ReadFile(format!("{}: {}", path, error)))?;
+        DSeriseRobot::from_urdf(name, &text, base)
+    }
+
+    /// 由 URDF 描述生成串联机器人。转动关节转换为改进 DH 参数，第一个关节之前的固定变换并入基座位姿，
+    /// 固定关节连接的连杆并入其前一个转动连杆；URDF 不提供的加速度、加加速度与力矩变化率限制视为无穷大
+    pub fn from_urdf(name: String, urdf: &str, base: Pose) -> Result<DSeriseRobot, RobotError> {
+        let document =
+            roxmltree::Document::parse(urdf).map_err(|error| invalid(error.to_string()))?;
+        let robot = document.root_element();
+        let links = robot
+            .children()
+            .filter(|node| node.has_tag_name("link"))
+            .map(parse_link)
+            .collect::<Result<Vec<_>, _>>()?;
+        let joints = robot
+            .children()
+            .filter(|node| node.has_tag_name("joint"))
+            .map(parse_joint)
+            .collect::<Result<Vec<_>, _>>()?;
+        let chain = serial_chain(&links, &joints)?;
+
+        // 零位下各连杆在根连杆坐标系中的位姿，以及所属的转动连杆序号（0 表示基座）
+        let mut link_poses = vec![(Pose::identity(), 0)];
+        let mut axes = Vec::new();
+        for joint in chain.iter() {
+            let (parent, body) = *link_poses.last().unwrap();
+            let pose = parent * joint.origin;
+            if joint.is_revolute() {
+                axes.push((pose.translation.vector, pose.rotation * joint.axis));
+                link_poses.push((pose, body + 1));
+            } else {
+                link_poses.push((pose, body));
+            }
+        }
+        let nlink = axes.len();
+        if nlink == 0 {
+            return Err(invalid("no revolute joint in chain"));
+        }
+        let tip = link_poses.last().unwrap().0;
+        let tip_origin = tip.translation.vector;
+        let tip_z = tip.rotation * na::Vector3::z();
+
+        // 改进 DH 坐标系：第 0 个坐标系与第一个关节轴重合，中间坐标系的 x 轴沿相邻关节轴的公垂线，
+        // 最后一个坐标系的选取使得末端连杆坐标系同样可以由一行 DH 参数表示
+        let (p1, z1) = axes[0];
+        let root_origin = p1 + z1 * z1.dot(&(-p1));
+        let mut frames = vec![frame(
+            root_origin,
+            perpendicular(&na::Vector3::x(), &z1),
+            z1,
+        )];
+        for i in 0..nlink {
+            let (p, z) = axes[i];
+            let last = *frames.last().unwrap();
+            let last_origin = last.translation.vector;
+            let last_x = last.rotation * na::Vector3::x();
+            let project = |point: &na::Vector3<f64>| p + z * z.dot(&(point - p));
+            let (origin, x) = if i + 1 < nlink {
+                let (p_next, z_next) = axes[i + 1];
+                match common_normal(&p, &z, &p_next, &z_next) {
+                    Some(normal) => normal,
+                    None => {
+                        let origin = project(&last_origin);
+                        let offset = p_next - origin;
+                        let x = if (offset - z * z.dot(&offset)).norm() > EPSILON {
+                            perpendicular(&offset, &z)
+                        } else {
+                            perpendicular(&last_x, &z)
+                        };
+                        (origin, x)
+                    }
+                }
+            } else {
+                match common_normal(&p, &z, &tip_origin, &tip_z) {
+                    // 末端连杆的 z 轴与最后一个关节轴不平行时，沿关节轴移动原点使末端原点落在 x 与末端 z 张成的平面内
+                    Some(_) => {
+                        let x = z.cross(&tip_z).normalize();
+                        let normal = x.cross(&tip_z);
+                        let t = (tip_origin - p).dot(&normal) / z.dot(&normal);
+                        (p + z * t, x)
+                    }
+                    None => {
+                        let origin = project(&last_origin);
+                        let offset = tip_origin - origin;
+                        let x = if (offset - z * z.dot(&offset)).norm() > EPSILON {
+                            perpendicular(&offset, &z)
+                        } else {
+                            perpendicular(&(tip.rotation * na::Vector3::x()), &z)
+                        };
+                        (origin, x)
+                    }
+                }
+            };
+            frames.push(frame(origin, x, z));
+        }
+        frames.push(tip);
+
+        let mut dh = na::DMatrix::zeros(nlink + 1, 4);
+        for i in 0..=nlink {
+            let row = dh_row(&frames[i], &frames[i + 1]);
+            dh.row_mut(i).copy_from_slice(&row);
+        }
+
+        // 关节限制
+        let revolute: Vec<&UrdfJoint> = chain
+            .iter()
+            .copied()
+            .filter(|joint| joint.is_revolute())
+            .collect();
+        let limit = |index: usize| {
+            let joint = revolute[index];
+            let [lower, upper, effort, velocity] = joint.limit.unwrap_or([0.0; 4]);
+            let (lower, upper) = if joint.joint_type == "continuous" || lower >= upper {
+                (-PI, PI)
+            } else {
+                (lower, upper)
+            };
+            let or_infinity = |value: f64| if value > 0.0 { value } else { f64::INFINITY };
+            [lower, upper, or_infinity(velocity), or_infinity(effort)]
+        };
+        let bound = |column: usize| na::DVector::from_fn(nlink, |i, _| limit(i)[column]);
+        let infinity = na::DVector::from_element(nlink, f64::INFINITY);
+        let q_min_bound = bound(0);
+        let q_max_bound = bound(1);
+        let q_default = na::DVector::from_fn(nlink, |i, _| {
+            if q_min_bound[i] <= 0.0 && 0.0 <= q_max_bound[i] {
+                0.0
+            } else {
+                (q_min_bound[i] + q_max_bound[i]) / 2.0
+            }
+        });
+
+        // 惯性参数与胶囊体，均转换到所属的改进 DH 坐标系下
+        let mut inertials = Vec::with_capacity(nlink);
+        let mut capsules = Vec::with_capacity(nlink);
+        for body in 1..=nlink {
+            let dh_frame = frames[body];
+            let to_local = dh_frame.inverse();
+            let mut parts = Vec::new();
+            let mut segments = Vec::new();
+            for (index, (pose, _)) in link_poses
+                .iter()
+                .enumerate()
+                .filter(|(_, (_, b))| *b == body)
+            {
+                let link_name = &chain[index - 1].child;
+                let Some(link) = links.iter().find(|link| &link.name == link_name) else {
+                    continue;
+                };
+                let local = to_local * pose;
+                if let Some(inertial) = &link.inertial {
+                    let origin = local * inertial.origin;
+                    let rotation = origin.rotation.to_rotation_matrix().into_inner();
+                    parts.push((
+                        inertial.mass,
+                        origin.translation.vector,
+                        rotation * inertial.inertia * rotation.transpose(),
+                    ));
+                }
+                for (origin, geometry) in link.collisions.iter() {
+                    let origin = local * origin;
+                    let point = |x: f64, y: f64, z: f64| (origin * na::Point3::new(x, y, z)).coords;
+                    match geometry {
+                        Geometry::Cylinder(radius, length) => segments.push((
+                            point(0.0, 0.0, -length / 2.0),
+                            point(0.0, 0.0, length / 2.0),
+                            *radius,
+                        )),
+                        Geometry::Sphere(radius) => {
+                            segments.push((point(0.0, 0.0, 0.0), point(0.0, 0.0, 0.0), *radius))
+                        }
+                        Geometry::Box(size) => {
+                            let axis = size.imax();
+                            let mut end = na::Vector3::zeros();
+                            end[axis] = size[axis] / 2.0;
+                            let others: Vec<f64> =
+                                (0..3).filter(|i| *i != axis).map(|i| size[i]).collect();
+                            let radius = (others[0].powi(2) + others[1].powi(2)).sqrt() / 2.0;
+                            segments.push((
+                                point(-end.x, -end.y, -end.z),
+                                point(end.x, end.y, end.z),
+                                radius,
+                            ));
+                        }
+                        Geometry::Other => {}
+                    }
+                }
+            }
+            inertials.push(combine_inertials(&parts));
+
+            // 没有可用的几何体时，沿本关节到下一个关节（或末端）的连线生成胶囊体
+            if segments.is_empty() {
+                let next = if body < nlink {
+                    axes[body].0
+                } else {
+                    tip_origin
+                };
+                let next = to_local * na::Point3::from(next);
+                let start = to_local * na::Point3::from(axes[body - 1].0);
+                segments.push((start.coords, next.coords, DEFAULT_CAPSULE_RADIUS));
+            }
+            capsules.push(enclosing_capsule(&segments));
+        }
+
+        Ok(DSeriseRobot {
+            name,
+            state: SeriseRobotState {
+                q: q_default.clone(),
+                q_dot: na::DVector::zeros(nlink),
+                q_ddot: na::DVector::zeros(nlink),
+                q_jerk: na::DVector::zeros(nlink),
+                base: base * frames[0],
+                control_message: NodeMessage::NoneNodeMessage,
+            },
+            params: SeriseRobotParams {
+                nlink,
+                q_default,
+                q_min_bound,
+                q_max_bound,
+                q_dot_bound: bound(2),
+                q_ddot_bound: infinity.clone(),
+                q_jerk_bound: infinity.clone(),
+                tau_bound: bound(3),
+                tau_dot_bound: infinity,
+                convention: DhConvention::Modified,
+                dh,
+                tool: Pose::identity(),
+                capsules,
+                acm: AllowedCollisionMatrix::adjacent(nlink, 1),
+                inertials,
+            },
+        })
+    }
+}
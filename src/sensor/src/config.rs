@@ -2,7 +2,7 @@ use serde::Deserialize;
 use serde_json::Value;
 use std::sync::{Arc, RwLock};
 
-use crate::{ObstacleList, Sensor};
+use crate::{ObstacleList, Sensor, SensorError};
 
 #[derive(Debug, Deserialize)]
 pub struct SensorConfig {
@@ -11,12 +11,13 @@ pub struct SensorConfig {
     pub params: Value,
 }
 
-pub fn from_config(sensor_config: &SensorConfig) -> Arc<RwLock<Sensor>> {
-    match sensor_config.sensor_type.as_str() {
-        "obstacle_list" => Arc::new(RwLock::new(Sensor::ObstacleList(ObstacleList::new(
+pub fn from_config(sensor_config: &SensorConfig) -> Result<Arc<RwLock<Sensor>>, SensorError> {
+    let sensor = match sensor_config.sensor_type.as_str() {
+        "obstacle_list" => Sensor::ObstacleList(ObstacleList::new(
             sensor_config.name.clone(),
             sensor_config.params.clone(),
-        )))),
-        _ => panic!("Unknown sensor type: {}", sensor_config.sensor_type),
-    }
+        )?),
+        _ => return Err(SensorError::UnknownType(sensor_config.sensor_type.clone())),
+    };
+    Ok(Arc::new(RwLock::new(sensor)))
 }
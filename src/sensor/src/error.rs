@@ -0,0 +1,36 @@
+use std::fmt;
+
+/// 传感器创建过程中的错误
+#[derive(Debug)]
+pub enum SensorError {
+    /// 配置文件中的传感器类型不受支持
+    UnknownType(String),
+    /// 传感器参数无法被反序列化
+    Params(serde_json::Error),
+}
+
+impl fmt::Display for SensorError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SensorError::UnknownType(sensor_type) => {
+                write!(f, "unknown sensor type: {}", sensor_type)
+            }
+            SensorError::Params(error) => write!(f, "invalid sensor params: {}", error),
+        }
+    }
+}
+
+impl std::error::Error for SensorError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            SensorError::Params(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl From<serde_json::Error> for SensorError {
+    fn from(error: serde_json::Error) -> Self {
+        SensorError::Params(error)
+    }
+}
@@ -1,7 +1,9 @@
 mod config;
+mod error;
 mod sensor_trait;
 mod sensors;
 
 pub use config::*;
+pub use error::*;
 pub use sensor_trait::*;
 pub use sensors::*;
@@ -1,7 +1,7 @@
 use serde_json::Value;
 
 use crate::ObstacleList;
-use message::CollisionObject;
+use message::{Aabb, CollisionObject};
 
 pub enum Sensor {
     ObstacleList(ObstacleList),
@@ -16,7 +16,14 @@ impl Sensor {
 
     pub fn collision(&self) -> Vec<CollisionObject> {
         match self {
-            Sensor::ObstacleList(obstacle_list) => obstacle_list.obstacle.clone(),
+            Sensor::ObstacleList(obstacle_list) => obstacle_list.obstacle().to_vec(),
+        }
+    }
+
+    /// 与包围盒 aabb 距离不超过 max_distance 的碰撞体
+    pub fn collision_near(&self, aabb: &Aabb, max_distance: f64) -> Vec<CollisionObject> {
+        match self {
+            Sensor::ObstacleList(obstacle_list) => obstacle_list.obstacle_near(aabb, max_distance),
         }
     }
 
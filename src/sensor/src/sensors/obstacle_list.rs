@@ -1,33 +1,92 @@
 use generate_tools::get_fn;
+use nalgebra as na;
 use serde_json::Value;
 
-use message::{CollisionObject, Pose};
+use message::{Aabb, AabbTree, CollisionObject, Pose};
 
 #[derive(Default)]
 pub struct ObstacleList {
     pub name: String,
-    pub obstacle: Vec<CollisionObject>,
+    /// 障碍物只能通过 new、set_obstacle 与 update_pose 修改，以保持包围盒树同步
+    obstacle: Vec<CollisionObject>,
+    /// 障碍物包围盒组成的动态包围盒树，键为障碍物在 obstacle 中的下标
+    tree: AabbTree,
+    /// 各障碍物在包围盒树中的叶节点编号
+    leaves: Vec<usize>,
 }
 
 impl ObstacleList {
     get_fn!((name: String));
-    pub fn new(name: String, obstacle: Value) -> ObstacleList {
-        ObstacleList {
+    pub fn new(name: String, obstacle: Value) -> Result<ObstacleList, serde_json::Error> {
+        let mut obstacle_list = ObstacleList {
             name,
-            obstacle: serde_json::from_value(obstacle).unwrap(),
-        }
+            ..ObstacleList::default()
+        };
+        obstacle_list.set_obstacle(serde_json::from_value(obstacle)?);
+        Ok(obstacle_list)
+    }
+
+    /// 当前的全部障碍物
+    pub fn obstacle(&self) -> &[CollisionObject] {
+        &self.obstacle
     }
 
     pub fn update_pose(&mut self, id: usize, pose: Pose) {
-        for col_obj in self.obstacle.iter_mut() {
+        for (index, col_obj) in self.obstacle.iter_mut().enumerate() {
             if col_obj.id() == id {
                 col_obj.set_pose(pose);
+                self.tree.update(self.leaves[index], col_obj.aabb());
                 return;
             }
         }
     }
 
     pub fn set_params(&mut self, params: Value) {
-        self.obstacle = serde_json::from_value(params).unwrap();
+        self.set_obstacle(serde_json::from_value(params).unwrap());
+    }
+
+    /// 替换全部障碍物并重建包围盒树
+    pub fn set_obstacle(&mut self, obstacle: Vec<CollisionObject>) {
+        self.tree = AabbTree::default();
+        self.leaves = obstacle
+            .iter()
+            .enumerate()
+            .map(|(index, col_obj)| self.tree.insert(index, col_obj.aabb()))
+            .collect();
+        self.obstacle = obstacle;
+    }
+
+    /// 包围盒与 aabb 距离不超过 max_distance 的障碍物，
+    /// 规划器只需处理机器人扫过区域附近的障碍物
+    pub fn obstacle_near(&self, aabb: &Aabb, max_distance: f64) -> Vec<CollisionObject> {
+        self.tree
+            .query_distance(aabb, max_distance)
+            .into_iter()
+            .map(|index| self.obstacle[index])
+            .filter(|col_obj| col_obj.aabb().distance(aabb) <= max_distance)
+            .collect()
+    }
+
+    /// 射线 origin + t * dir 首先击中的障碍物 id 以及对应的 t
+    pub fn cast_ray(
+        &self,
+        origin: &na::Point3<f64>,
+        dir: &na::Vector3<f64>,
+        max_toi: f64,
+    ) -> Option<(usize, f64)> {
+        let dir = dir.normalize();
+        let mut hit: Option<(usize, f64)> = None;
+        for (index, toi) in self.tree.cast_ray(origin, &dir, max_toi) {
+            // 候选按进入包围盒的先后排列，之后的障碍物不会更早被击中
+            if hit.is_some_and(|(_, best)| toi > best) {
+                break;
+            }
+            let col_obj = &self.obstacle[index];
+            let max_toi = hit.map_or(max_toi, |(_, best)| best);
+            if let Some(toi) = col_obj.cast_ray(origin, &dir, max_toi) {
+                hit = Some((col_obj.id(), toi));
+            }
+        }
+        hit
     }
 }
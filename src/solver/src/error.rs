@@ -0,0 +1,84 @@
+use std::fmt;
+
+/// 求解器创建与更新过程中的错误
+#[derive(Debug, Clone, PartialEq)]
+pub enum SolverError {
+    /// 求解器类型未注册
+    UnknownType(String),
+    /// 求解器不支持该类型的问题
+    UnsupportedProblem {
+        solver: &'static str,
+        problem: &'static str,
+    },
+    /// 求解器无法处理该约束
+    UnsupportedConstraint {
+        solver: &'static str,
+        constraint: &'static str,
+    },
+    /// 更新或初值的维数与问题不符
+    DimensionMismatch {
+        solver: &'static str,
+        expected: usize,
+        found: usize,
+    },
+    /// 求解器无法建立问题，例如约束的下界大于上界
+    Setup {
+        solver: &'static str,
+        reason: String,
+    },
+}
+
+impl fmt::Display for SolverError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SolverError::UnknownType(solver_type) => {
+                write!(f, "unknown or unregistered solver type: {}", solver_type)
+            }
+            SolverError::UnsupportedProblem { solver, problem } => {
+                write!(f, "solver {} does not support {} problems", solver, problem)
+            }
+            SolverError::UnsupportedConstraint { solver, constraint } => {
+                write!(
+                    f,
+                    "solver {} cannot handle {} constraints",
+                    solver, constraint
+                )
+            }
+            SolverError::DimensionMismatch {
+                solver,
+                expected,
+                found,
+            } => write!(
+                f,
+                "solver {} expects dimension {}, found {}",
+                solver, expected, found
+            ),
+            SolverError::Setup { solver, reason } => {
+                write!(
+                    f,
+                    "solver {} failed to set up the problem: {}",
+                    solver, reason
+                )
+            }
+        }
+    }
+}
+
+impl std::error::Error for SolverError {}
+
+/// 检查更新或初值的维数
+pub(crate) fn check_dimension(
+    solver: &'static str,
+    expected: usize,
+    found: usize,
+) -> Result<(), SolverError> {
+    if expected == found {
+        Ok(())
+    } else {
+        Err(SolverError::DimensionMismatch {
+            solver,
+            expected,
+            found,
+        })
+    }
+}
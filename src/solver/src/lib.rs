@@ -1,8 +1,13 @@
+pub mod error;
+pub mod solve_result;
 pub mod solver_trait;
 pub mod solvers;
 
-pub use solver_trait::Solver;
+pub use error::SolverError;
+pub use solve_result::{SolveResult, SolveStatus};
+pub use solver_trait::{factory, solver_types, Solver, SolverRegister};
 pub use solvers::osqp::OsqpSolver;
+pub use solvers::panoc::PanocSolver;
 
 #[cfg(test)]
 mod tests {
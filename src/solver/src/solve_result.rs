@@ -0,0 +1,95 @@
+use std::fmt;
+use std::time::Duration;
+
+/// 求解结束时的状态
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SolveStatus {
+    /// 达到收敛精度
+    Solved,
+    /// 收敛但精度不足
+    SolvedInaccurate,
+    /// 原问题不可行，约束之间相互矛盾
+    PrimalInfeasible,
+    /// 对偶问题不可行，目标函数无下界
+    DualInfeasible,
+    /// 达到最大迭代次数仍未收敛
+    MaxIterationsReached,
+    /// 达到时间限制仍未收敛
+    TimeLimitReached,
+    /// 目标函数非凸
+    NonConvex,
+    /// 求解过程中出现非有限的数值
+    NumericalError,
+}
+
+impl SolveStatus {
+    /// 是否得到了满足约束的解，只有此时的 x 可以直接使用
+    pub fn is_solved(&self) -> bool {
+        matches!(self, SolveStatus::Solved | SolveStatus::SolvedInaccurate)
+    }
+}
+
+impl fmt::Display for SolveStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let status = match self {
+            SolveStatus::Solved => "solved",
+            SolveStatus::SolvedInaccurate => "solved inaccurate",
+            SolveStatus::PrimalInfeasible => "primal infeasible",
+            SolveStatus::DualInfeasible => "dual infeasible",
+            SolveStatus::MaxIterationsReached => "max iterations reached",
+            SolveStatus::TimeLimitReached => "time limit reached",
+            SolveStatus::NonConvex => "non convex",
+            SolveStatus::NumericalError => "numerical error",
+        };
+        write!(f, "{}", status)
+    }
+}
+
+/// 一次求解的结果与诊断信息
+#[derive(Debug, Clone, PartialEq)]
+pub struct SolveResult {
+    pub status: SolveStatus,
+    /// 原问题的解，不可行时为空
+    pub x: Vec<f64>,
+    /// 约束的对偶变量，求解器不提供时为空
+    pub y: Vec<f64>,
+    /// 目标函数值，没有解时为 NaN
+    pub objective: f64,
+    pub iterations: usize,
+    pub solve_time: Duration,
+    /// 约束违反量的无穷范数
+    pub primal_residual: f64,
+    /// 最优性条件残差的无穷范数
+    pub dual_residual: f64,
+}
+
+impl SolveResult {
+    /// 没有解时的结果
+    pub fn failed(status: SolveStatus, iterations: usize, solve_time: Duration) -> SolveResult {
+        SolveResult {
+            status,
+            x: Vec::new(),
+            y: Vec::new(),
+            objective: f64::NAN,
+            iterations,
+            solve_time,
+            primal_residual: f64::NAN,
+            dual_residual: f64::NAN,
+        }
+    }
+}
+
+impl fmt::Display for SolveResult {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} after {} iterations in {:?}, objective {:.6e}, primal residual {:.3e}, dual residual {:.3e}",
+            self.status,
+            self.iterations,
+            self.solve_time,
+            self.objective,
+            self.primal_residual,
+            self.dual_residual
+        )
+    }
+}
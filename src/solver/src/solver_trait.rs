@@ -1,7 +1,54 @@
-use message::Constraint;
+use crate::{SolveResult, SolverError};
+use message::{Constraint, Problem};
+use osqp::CscMatrix;
 
+/// 求解器在多次求解之间保留已有的分解，并以上一次的解作为下一次求解的初值
 pub trait Solver {
-    fn solve(&mut self) -> Vec<f64>;
+    fn solve(&mut self) -> SolveResult;
 
-    fn update_constraints(&mut self, constraints: Constraint);
+    /// 替换全部约束，求解器无法处理的约束返回错误，原有的约束保持不变
+    fn update_constraints(&mut self, constraints: Constraint) -> Result<(), SolverError>;
+
+    /// 更新目标函数的一次项 f
+    fn update_linear_cost(&mut self, f: &[f64]) -> Result<(), SolverError>;
+
+    /// 更新目标函数的二次项 h，非零元的位置不变时只更新数值
+    fn update_quadratic_cost(&mut self, h: &CscMatrix) -> Result<(), SolverError>;
+
+    /// 按 Constraint::to_cscmatrix 的行顺序更新线性约束的上下界，l 与 u 的长度须等于约束的行数
+    fn update_bounds(&mut self, l: &[f64], u: &[f64]) -> Result<(), SolverError>;
+
+    /// 指定下一次求解的初值，y 为约束的对偶变量，维数不符时只使用 x
+    fn warm_start(&mut self, x: &[f64], y: &[f64]) -> Result<(), SolverError>;
+}
+
+/// 求解器注册表中的一项，由优化问题创建对应的求解器
+pub struct SolverRegister {
+    pub solver_type: &'static str,
+    pub solver_creator: for<'a> fn(Problem<'a>) -> Result<Box<dyn Solver + 'a>, SolverError>,
+}
+
+inventory::collect!(SolverRegister);
+
+/// 在注册表中查找求解器类型并由优化问题创建求解器
+pub fn factory<'a>(
+    solver_type: &str,
+    problem: Problem<'a>,
+) -> Result<Box<dyn Solver + 'a>, SolverError> {
+    for reg in inventory::iter::<SolverRegister> {
+        if reg.solver_type == solver_type {
+            return (reg.solver_creator)(problem);
+        }
+    }
+    Err(SolverError::UnknownType(solver_type.to_string()))
+}
+
+/// 列出所有已经注册的求解器类型
+pub fn solver_types() -> Vec<&'static str> {
+    let mut solver_types: Vec<&'static str> = inventory::iter::<SolverRegister>
+        .into_iter()
+        .map(|reg| reg.solver_type)
+        .collect();
+    solver_types.sort_unstable();
+    solver_types
 }
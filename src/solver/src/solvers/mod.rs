@@ -1 +1,2 @@
 pub mod osqp;
+pub mod panoc;
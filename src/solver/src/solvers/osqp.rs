@@ -1,17 +1,29 @@
-use crate::Solver;
-use message::{Constraint, QuadraticProgramming};
+use osqp::CscMatrix;
+
+use crate::{
+    error::check_dimension, SolveResult, SolveStatus, Solver, SolverError, SolverRegister,
+};
+use message::{csc_mul, Constraint, Problem, QuadraticProgramming};
 
 pub struct OsqpSolver {
     problem: osqp::Problem,
+    /// 完整的 h 与 f，用于计算残差以及重新建立问题
+    h: CscMatrix<'static>,
+    f: Vec<f64>,
+    /// 当前的线性约束 l <= a x <= u
+    a: CscMatrix<'static>,
+    l: Vec<f64>,
+    u: Vec<f64>,
+    /// 上一次求解得到的原始解与对偶解，重新建立问题后作为初值
+    solution: Option<(Vec<f64>, Vec<f64>)>,
 }
 
 impl OsqpSolver {
-    pub fn from_problem(problem: QuadraticProgramming) -> OsqpSolver {
+    /// 建立 OSQP 问题，约束无法转换为线性约束或者 OSQP 无法建立问题时返回错误
+    pub fn from_problem(problem: QuadraticProgramming) -> Result<OsqpSolver, SolverError> {
+        check_linear(&problem.constraints)?;
         let (_, _, a, l, u) = problem.constraints.to_cscmatrix();
 
-        let p = problem.h.clone().into_upper_tri();
-        let q = problem.f;
-
         // let (_, _, _a, _l, _u) = problem.constraints.to_namatrix();
         // println!("p: {:?}", p);
         // println!("q: {:?}", q);
@@ -19,23 +31,224 @@ impl OsqpSolver {
         // println!("l: {:?}", _l);
         // println!("u: {:?}", _u);
 
-        let settings = osqp::Settings::default().verbose(false);
+        let h = problem.h.clone().into_owned();
+        let f = problem.f.to_vec();
+        let a = a.into_owned();
+        Ok(OsqpSolver {
+            problem: setup(&h, &f, &a, &l, &u)?,
+            h,
+            f,
+            a,
+            l,
+            u,
+            solution: None,
+        })
+    }
+
+    /// 非零元的位置发生变化时无法沿用原有的分解，重新建立问题并以上一次的解作为初值
+    /// 建立失败时保留原有的问题
+    fn rebuild(
+        &mut self,
+        h: CscMatrix<'static>,
+        a: CscMatrix<'static>,
+        l: Vec<f64>,
+        u: Vec<f64>,
+    ) -> Result<(), SolverError> {
+        self.problem = setup(&h, &self.f, &a, &l, &u)?;
+        (self.h, self.a, self.l, self.u) = (h, a, l, u);
+        if let Some((x, y)) = self.solution.take() {
+            self.start_from(x, y);
+        }
+        Ok(())
+    }
 
-        OsqpSolver {
-            problem: osqp::Problem::new(p, q, &a, &l, &u, &settings)
-                .expect("failed to setup problem"),
+    /// 设置初值，y 的维数与约束行数不符时只使用 x
+    fn start_from(&mut self, x: Vec<f64>, y: Vec<f64>) {
+        if y.len() == self.a.nrows {
+            self.problem.warm_start(&x, &y);
+        } else {
+            self.problem.warm_start_x(&x);
         }
+        self.solution = Some((x, y));
+    }
+
+    /// 约束违反量 |a x - proj_[l, u](a x)| 的无穷范数
+    fn primal_residual(&self, x: &[f64]) -> f64 {
+        csc_mul(&self.a, x)
+            .iter()
+            .zip(self.l.iter().zip(&self.u))
+            .map(|(ax, (l, u))| (ax - u).max(l - ax).max(0.0))
+            .fold(0.0, f64::max)
+    }
+
+    /// 最优性条件 h x + f + a' y 的无穷范数
+    fn dual_residual(&self, x: &[f64], y: &[f64]) -> f64 {
+        csc_mul(&self.h, x)
+            .iter()
+            .zip(self.f.iter().zip(csc_tr_mul(&self.a, y)))
+            .map(|(hx, (f, aty))| (hx + f + aty).abs())
+            .fold(0.0, f64::max)
     }
 }
 
 impl Solver for OsqpSolver {
-    fn solve(&mut self) -> Vec<f64> {
-        self.problem.solve().x().unwrap().to_vec()
+    fn solve(&mut self) -> SolveResult {
+        let status = self.problem.solve();
+        let iterations = status.iter() as usize;
+        let solve_time = status.solve_time();
+        let (status, solution) = match status {
+            osqp::Status::Solved(solution) => (SolveStatus::Solved, solution),
+            osqp::Status::SolvedInaccurate(solution) => (SolveStatus::SolvedInaccurate, solution),
+            osqp::Status::MaxIterationsReached(solution) => {
+                (SolveStatus::MaxIterationsReached, solution)
+            }
+            osqp::Status::TimeLimitReached(solution) => (SolveStatus::TimeLimitReached, solution),
+            osqp::Status::PrimalInfeasible(_) | osqp::Status::PrimalInfeasibleInaccurate(_) => {
+                return SolveResult::failed(SolveStatus::PrimalInfeasible, iterations, solve_time)
+            }
+            osqp::Status::DualInfeasible(_) | osqp::Status::DualInfeasibleInaccurate(_) => {
+                return SolveResult::failed(SolveStatus::DualInfeasible, iterations, solve_time)
+            }
+            osqp::Status::NonConvex(_) => {
+                return SolveResult::failed(SolveStatus::NonConvex, iterations, solve_time)
+            }
+            _ => return SolveResult::failed(SolveStatus::NumericalError, iterations, solve_time),
+        };
+
+        let (x, y) = (solution.x().to_vec(), solution.y().to_vec());
+        self.solution = Some((x.clone(), y.clone()));
+        SolveResult {
+            status,
+            objective: solution.obj_val(),
+            iterations,
+            solve_time,
+            primal_residual: self.primal_residual(&x),
+            dual_residual: self.dual_residual(&x, &y),
+            x,
+            y,
+        }
     }
 
-    fn update_constraints(&mut self, constraints: Constraint) {
+    fn update_constraints(&mut self, constraints: Constraint) -> Result<(), SolverError> {
+        check_linear(&constraints)?;
         let (_, _, a, l, u) = constraints.to_cscmatrix();
-        self.problem.update_A(a);
+        check_dimension("osqp", self.f.len(), a.ncols)?;
+        let a = a.into_owned();
+        if !same_pattern(&a, &self.a) {
+            return self.rebuild(self.h.clone(), a, l, u);
+        }
+        self.problem.update_A(&a);
         self.problem.update_bounds(&l, &u);
+        (self.a, self.l, self.u) = (a, l, u);
+        Ok(())
+    }
+
+    fn update_linear_cost(&mut self, f: &[f64]) -> Result<(), SolverError> {
+        check_dimension("osqp", self.f.len(), f.len())?;
+        self.problem.update_lin_cost(f);
+        self.f = f.to_vec();
+        Ok(())
+    }
+
+    fn update_quadratic_cost(&mut self, h: &CscMatrix) -> Result<(), SolverError> {
+        check_dimension("osqp", self.f.len(), h.nrows)?;
+        check_dimension("osqp", self.f.len(), h.ncols)?;
+        let h = h.clone().into_owned();
+        if !same_pattern(&h, &self.h) {
+            return self.rebuild(h, self.a.clone(), self.l.clone(), self.u.clone());
+        }
+        self.problem.update_P(h.clone().into_upper_tri());
+        self.h = h;
+        Ok(())
+    }
+
+    fn update_bounds(&mut self, l: &[f64], u: &[f64]) -> Result<(), SolverError> {
+        check_dimension("osqp", self.a.nrows, l.len())?;
+        check_dimension("osqp", self.a.nrows, u.len())?;
+        self.problem.update_bounds(l, u);
+        self.l = l.to_vec();
+        self.u = u.to_vec();
+        Ok(())
+    }
+
+    fn warm_start(&mut self, x: &[f64], y: &[f64]) -> Result<(), SolverError> {
+        check_dimension("osqp", self.f.len(), x.len())?;
+        self.start_from(x.to_vec(), y.to_vec());
+        Ok(())
+    }
+}
+
+/// 建立 OSQP 问题，OSQP 会在建立时检查数据，例如约束的下界不能大于上界
+fn setup(
+    h: &CscMatrix,
+    f: &[f64],
+    a: &CscMatrix,
+    l: &[f64],
+    u: &[f64],
+) -> Result<osqp::Problem, SolverError> {
+    let p = h.clone().into_upper_tri();
+    let settings = osqp::Settings::default().verbose(false);
+    osqp::Problem::new(p, f, a, l, u, &settings).map_err(|error| SolverError::Setup {
+        solver: "osqp",
+        reason: error.to_string(),
+    })
+}
+
+/// 两个稀疏矩阵的非零元位置是否相同
+fn same_pattern(a: &CscMatrix, b: &CscMatrix) -> bool {
+    a.nrows == b.nrows && a.ncols == b.ncols && a.indptr == b.indptr && a.indices == b.indices
+}
+
+/// 稀疏矩阵的转置与向量的乘积 m' y
+fn csc_tr_mul(m: &CscMatrix, y: &[f64]) -> Vec<f64> {
+    (0..m.ncols)
+        .map(|j| {
+            (m.indptr[j]..m.indptr[j + 1])
+                .map(|k| m.data[k] * y[m.indices[k]])
+                .sum()
+        })
+        .collect()
+}
+
+/// OSQP 只能处理线性约束，其余约束无法转换为 l <= a x <= u
+fn check_linear(constraint: &Constraint) -> Result<(), SolverError> {
+    let constraint = match constraint {
+        Constraint::NoConstraint
+        | Constraint::Zero
+        | Constraint::Equared(_)
+        | Constraint::Halfspace(..)
+        | Constraint::Hyperplane(..)
+        | Constraint::Rectangle(..) => return Ok(()),
+        Constraint::Intersection(_, _, constraints)
+        | Constraint::CartesianProduct(_, _, constraints) => {
+            return constraints.iter().try_for_each(check_linear);
+        }
+        Constraint::AffineSpace => "affine space",
+        Constraint::Union(..) => "union",
+        Constraint::EpigraphSquaredNorm(..) => "epigraph squared norm",
+        Constraint::FiniteSet(_) => "finite set",
+        Constraint::Simplex(..) => "simplex",
+        Constraint::SecondOrderCone(..) => "second order cone",
+    };
+    Err(SolverError::UnsupportedConstraint {
+        solver: "osqp",
+        constraint,
+    })
+}
+
+fn create(problem: Problem) -> Result<Box<dyn Solver + '_>, SolverError> {
+    match problem {
+        Problem::QuadraticProgramming(problem) => Ok(Box::new(OsqpSolver::from_problem(problem)?)),
+        problem => Err(SolverError::UnsupportedProblem {
+            solver: "osqp",
+            problem: problem.kind(),
+        }),
+    }
+}
+
+inventory::submit! {
+    SolverRegister {
+        solver_type: "osqp",
+        solver_creator: create,
     }
 }
@@ -0,0 +1,446 @@
+use nalgebra as na;
+use optimization_engine::{
+    constraints,
+    panoc::{PANOCCache, PANOCOptimizer},
+    ExitStatus, Optimizer, SolverError as OpenError,
+};
+use osqp::CscMatrix;
+use std::{mem, time::Duration};
+
+use crate::{
+    error::check_dimension, SolveResult, SolveStatus, Solver, SolverError, SolverRegister,
+};
+use message::{Constraint, NonlinearProgramming, Problem};
+
+/// PANOC 的收敛精度、L-BFGS 记忆长度与最大迭代次数
+const PANOC_TOLERANCE: f64 = 1e-6;
+const PANOC_LBFGS_MEMORY: usize = 10;
+const PANOC_MAX_ITER: usize = 500;
+/// 罚函数法的初始罚系数、每轮的放大倍数、最大轮数以及允许的约束违反量
+const PENALTY_INIT: f64 = 1e2;
+const PENALTY_GROWTH: f64 = 10.0;
+const PENALTY_MAX_ROUND: usize = 8;
+const VIOLATION_TOLERANCE: f64 = 1e-4;
+
+/// 能够直接投影的集合，各变量块上的集合组成笛卡尔积
+#[derive(Debug, Clone, PartialEq)]
+enum Projection {
+    Free,
+    Rectangle(Vec<f64>, Vec<f64>),
+    Halfspace(Vec<f64>, f64),
+    Hyperplane(Vec<f64>, f64),
+    Ball2(f64),
+    FiniteSet(Vec<Vec<f64>>),
+    Simplex(f64),
+    SecondOrderCone(f64),
+}
+
+/// 各变量块的结束下标及其上的集合
+type Blocks = Vec<(usize, Projection)>;
+
+/// 无法投影的线性约束 l <= a x[offset..] <= u，以二次罚函数的形式加入目标函数
+#[derive(Debug, Clone)]
+struct LinearRows {
+    offset: usize,
+    a: na::DMatrix<f64>,
+    l: na::DVector<f64>,
+    u: na::DVector<f64>,
+}
+
+/// 基于 OpEn 的 PANOC 求解器。集合类约束直接投影，
+/// 同一变量块上的其余线性约束用逐轮增大罚系数的二次罚函数处理
+pub struct PanocSolver<'a> {
+    problem: NonlinearProgramming<'a>,
+    /// 由二次规划创建时的 h 与 f，用于更新目标函数
+    quadratic: Option<(CscMatrix<'static>, Vec<f64>)>,
+    blocks: Blocks,
+    rows: Vec<LinearRows>,
+    /// 下一次求解的初值，默认为上一次求解得到的解
+    x0: Option<Vec<f64>>,
+}
+
+impl<'a> PanocSolver<'a> {
+    pub fn from_problem(problem: Problem<'a>) -> Result<PanocSolver<'a>, SolverError> {
+        let (problem, quadratic) = match problem {
+            Problem::QuadraticProgramming(problem) => {
+                let quadratic = (problem.h.clone().into_owned(), problem.f.to_vec());
+                (problem.into(), Some(quadratic))
+            }
+            Problem::NonlinearProgramming(problem) => (problem, None),
+        };
+        let (blocks, rows) = decompose_all(&problem.constraints, problem.dim)?;
+        Ok(PanocSolver {
+            problem,
+            quadratic,
+            blocks,
+            rows,
+            x0: None,
+        })
+    }
+
+    /// 以新的 h 与 f 重新建立二次规划的目标函数，一般的非线性规划没有可更新的二次项与一次项
+    fn update_quadratic(
+        &mut self,
+        h: Option<&CscMatrix>,
+        f: Option<&[f64]>,
+    ) -> Result<(), SolverError> {
+        let dim = self.problem.dim;
+        let Some((h0, f0)) = self.quadratic.as_mut() else {
+            return Err(SolverError::UnsupportedProblem {
+                solver: "panoc",
+                problem: "nonlinear programming",
+            });
+        };
+        if let Some(h) = h {
+            check_dimension("panoc", dim, h.nrows)?;
+            check_dimension("panoc", dim, h.ncols)?;
+            *h0 = h.clone().into_owned();
+        }
+        if let Some(f) = f {
+            check_dimension("panoc", dim, f.len())?;
+            *f0 = f.to_vec();
+        }
+        let constraints = mem::take(&mut self.problem.constraints);
+        self.problem = NonlinearProgramming::quadratic(h0.clone(), f0.clone(), constraints);
+        Ok(())
+    }
+
+    /// 线性约束的最大违反量
+    fn violation(&self, x: &[f64]) -> f64 {
+        self.rows
+            .iter()
+            .flat_map(|rows| row_violations(rows, x))
+            .map(f64::abs)
+            .fold(0.0, f64::max)
+    }
+}
+
+impl Solver for PanocSolver<'_> {
+    fn solve(&mut self) -> SolveResult {
+        let dim = self.problem.dim;
+        let finite_sets: Vec<Vec<&[f64]>> = self
+            .blocks
+            .iter()
+            .map(|(_, set)| match set {
+                Projection::FiniteSet(points) => {
+                    points.iter().map(|point| point.as_slice()).collect()
+                }
+                _ => Vec::new(),
+            })
+            .collect();
+        let set = cartesian_product(&self.blocks, &finite_sets);
+
+        let mut x = self.x0.clone().unwrap_or_else(|| vec![0.0; dim]);
+        let mut cache = PANOCCache::new(dim, PANOC_TOLERANCE, PANOC_LBFGS_MEMORY);
+        let mut penalty = PENALTY_INIT;
+        let mut iterations = 0;
+        let mut solve_time = Duration::ZERO;
+        let mut status = SolveStatus::Solved;
+        let mut fpr = f64::NAN;
+        for _ in 0..PENALTY_MAX_ROUND {
+            let cost = |x: &[f64], cost: &mut f64| -> Result<(), OpenError> {
+                let violation: f64 = self
+                    .rows
+                    .iter()
+                    .flat_map(|rows| row_violations(rows, x))
+                    .map(|v| v * v)
+                    .sum();
+                *cost = (self.problem.cost)(x) + 0.5 * penalty * violation;
+                Ok(())
+            };
+            let grad = |x: &[f64], grad: &mut [f64]| -> Result<(), OpenError> {
+                (self.problem.grad)(x, grad);
+                for rows in self.rows.iter() {
+                    add_penalty_grad(rows, x, penalty, grad);
+                }
+                Ok(())
+            };
+            let problem = optimization_engine::Problem::new(&set, grad, cost);
+            let Ok(round) = PANOCOptimizer::new(problem, &mut cache)
+                .with_max_iter(PANOC_MAX_ITER)
+                .solve(&mut x)
+            else {
+                return SolveResult::failed(SolveStatus::NumericalError, iterations, solve_time);
+            };
+            iterations += round.iterations();
+            solve_time += round.solve_time();
+            fpr = round.norm_fpr();
+            status = match round.exit_status() {
+                ExitStatus::Converged => SolveStatus::Solved,
+                ExitStatus::NotConvergedIterations => SolveStatus::MaxIterationsReached,
+                ExitStatus::NotConvergedOutOfTime => SolveStatus::TimeLimitReached,
+            };
+
+            if self.violation(&x) < VIOLATION_TOLERANCE {
+                break;
+            }
+            penalty *= PENALTY_GROWTH;
+        }
+
+        // 罚系数增大到上限仍未满足线性约束，视为不可行
+        let violation = self.violation(&x);
+        if violation >= VIOLATION_TOLERANCE {
+            return SolveResult {
+                primal_residual: violation,
+                dual_residual: fpr,
+                ..SolveResult::failed(SolveStatus::PrimalInfeasible, iterations, solve_time)
+            };
+        }
+        self.x0 = Some(x.clone());
+        SolveResult {
+            status,
+            objective: (self.problem.cost)(&x),
+            x,
+            y: Vec::new(),
+            iterations,
+            solve_time,
+            primal_residual: violation,
+            dual_residual: fpr,
+        }
+    }
+
+    fn update_constraints(&mut self, constraints: Constraint) -> Result<(), SolverError> {
+        let (blocks, rows) = decompose_all(&constraints, self.problem.dim)?;
+        self.blocks = blocks;
+        self.rows = rows;
+        self.problem.constraints = constraints;
+        Ok(())
+    }
+
+    fn update_linear_cost(&mut self, f: &[f64]) -> Result<(), SolverError> {
+        self.update_quadratic(None, Some(f))
+    }
+
+    fn update_quadratic_cost(&mut self, h: &CscMatrix) -> Result<(), SolverError> {
+        self.update_quadratic(Some(h), None)
+    }
+
+    fn update_bounds(&mut self, l: &[f64], u: &[f64]) -> Result<(), SolverError> {
+        let nrows = self.problem.constraints.nrows();
+        check_dimension("panoc", nrows, l.len())?;
+        check_dimension("panoc", nrows, u.len())?;
+        let mut constraints = self.problem.constraints.clone();
+        constraints.set_bounds(l, u);
+        self.update_constraints(constraints)
+    }
+
+    /// 对偶变量不参与 PANOC 的迭代
+    fn warm_start(&mut self, x: &[f64], _y: &[f64]) -> Result<(), SolverError> {
+        check_dimension("panoc", self.problem.dim, x.len())?;
+        self.x0 = Some(x.to_vec());
+        Ok(())
+    }
+}
+
+/// 将约束拆分为覆盖全部 dim 个变量的集合块以及需要罚函数处理的线性约束
+fn decompose_all(
+    constraint: &Constraint,
+    dim: usize,
+) -> Result<(Blocks, Vec<LinearRows>), SolverError> {
+    let mut blocks = Vec::new();
+    let mut rows = Vec::new();
+    decompose(constraint, 0, &mut blocks, &mut rows)?;
+    let end = blocks.last().map_or(0, |(end, _)| *end);
+    // 约束可以只覆盖前面的变量，其余变量不受约束
+    if end > dim {
+        check_dimension("panoc", dim, end)?;
+    } else if end < dim {
+        blocks.push((dim, Projection::Free));
+    }
+    Ok((blocks, rows))
+}
+
+fn decompose(
+    constraint: &Constraint,
+    offset: usize,
+    blocks: &mut Blocks,
+    rows: &mut Vec<LinearRows>,
+) -> Result<(), SolverError> {
+    match constraint {
+        Constraint::NoConstraint => (),
+        Constraint::CartesianProduct(_, _, constraints) => {
+            let mut offset = offset;
+            for constraint in constraints {
+                decompose(constraint, offset, blocks, rows)?;
+                offset += constraint.ncols();
+            }
+        }
+        Constraint::Intersection(_, ncols, constraints) => {
+            let mut leaves = Vec::new();
+            flatten(constraints, &mut leaves);
+
+            // 优先投影到无法线性化的集合上，其次是矩形，其余约束都转为线性约束
+            let projected = leaves
+                .iter()
+                .position(|leaf| to_set(leaf).is_some() && !is_linear(leaf))
+                .or_else(|| {
+                    leaves
+                        .iter()
+                        .position(|leaf| matches!(to_set(leaf), Some(Projection::Rectangle(..))))
+                })
+                .or_else(|| leaves.iter().position(|leaf| to_set(leaf).is_some()));
+            let set = projected
+                .and_then(|index| to_set(leaves[index]))
+                .unwrap_or(Projection::Free);
+            for (index, leaf) in leaves.iter().enumerate() {
+                if Some(index) == projected {
+                    continue;
+                }
+                if !is_linear(leaf) {
+                    return Err(unsupported(leaf));
+                }
+                rows.push(linear_rows(leaf, offset));
+            }
+            blocks.push((offset + ncols, set));
+        }
+        constraint => {
+            let set = match to_set(constraint) {
+                Some(set) => set,
+                None if is_linear(constraint) => {
+                    rows.push(linear_rows(constraint, offset));
+                    Projection::Free
+                }
+                None => return Err(unsupported(constraint)),
+            };
+            blocks.push((offset + constraint.ncols(), set));
+        }
+    }
+    Ok(())
+}
+
+/// 展开嵌套的交集约束，例如由 += 逐个加入的约束
+fn flatten<'c>(constraints: &'c [Constraint], leaves: &mut Vec<&'c Constraint>) {
+    for constraint in constraints {
+        match constraint {
+            Constraint::Intersection(_, _, constraints) => flatten(constraints, leaves),
+            Constraint::NoConstraint => (),
+            constraint => leaves.push(constraint),
+        }
+    }
+}
+
+/// 单个约束对应的可投影集合
+fn to_set(constraint: &Constraint) -> Option<Projection> {
+    match constraint {
+        Constraint::Zero => Some(Projection::Rectangle(vec![0.0], vec![0.0])),
+        Constraint::Equared(b) => Some(Projection::Rectangle(b.clone(), b.clone())),
+        Constraint::Rectangle(a, b) => Some(Projection::Rectangle(a.clone(), b.clone())),
+        Constraint::Halfspace(a, b) => Some(Projection::Halfspace(a.clone(), *b)),
+        Constraint::Hyperplane(1, _, a, b) => Some(Projection::Hyperplane(a.clone(), b[0])),
+        Constraint::EpigraphSquaredNorm(_, b) => Some(Projection::Ball2(b.sqrt())),
+        Constraint::FiniteSet(points) => Some(Projection::FiniteSet(points.clone())),
+        Constraint::Simplex(_, a) => Some(Projection::Simplex(*a)),
+        Constraint::SecondOrderCone(_, a) => Some(Projection::SecondOrderCone(*a)),
+        _ => None,
+    }
+}
+
+/// 能否写成 l <= a x <= u 的形式
+fn is_linear(constraint: &Constraint) -> bool {
+    match constraint {
+        Constraint::Zero
+        | Constraint::Equared(_)
+        | Constraint::Rectangle(..)
+        | Constraint::Halfspace(..)
+        | Constraint::Hyperplane(..) => true,
+        Constraint::Intersection(_, _, constraints)
+        | Constraint::CartesianProduct(_, _, constraints) => constraints.iter().all(is_linear),
+        _ => false,
+    }
+}
+
+fn linear_rows(constraint: &Constraint, offset: usize) -> LinearRows {
+    let (nrows, ncols, a, l, u) = constraint.to_cscmatrix();
+    let mut dense = na::DMatrix::zeros(nrows, ncols);
+    for j in 0..ncols {
+        for k in a.indptr[j]..a.indptr[j + 1] {
+            dense[(a.indices[k], j)] = a.data[k];
+        }
+    }
+    LinearRows {
+        offset,
+        a: dense,
+        l: na::DVector::from_vec(l),
+        u: na::DVector::from_vec(u),
+    }
+}
+
+/// 各行约束的违反量，满足时为 0，超出上界为正，低于下界为负
+fn row_violations<'r>(rows: &'r LinearRows, x: &[f64]) -> impl Iterator<Item = f64> + 'r {
+    let x = na::DVectorView::from_slice(
+        &x[rows.offset..rows.offset + rows.a.ncols()],
+        rows.a.ncols(),
+    );
+    let ax = &rows.a * x;
+    (0..rows.a.nrows()).map(move |i| (ax[i] - rows.u[i]).max(0.0) + (ax[i] - rows.l[i]).min(0.0))
+}
+
+/// 罚函数 0.5 * penalty * |violation|^2 的梯度
+fn add_penalty_grad(rows: &LinearRows, x: &[f64], penalty: f64, grad: &mut [f64]) {
+    let violation = na::DVector::from_iterator(rows.a.nrows(), row_violations(rows, x));
+    let grad_rows = rows.a.tr_mul(&violation) * penalty;
+    for (g, value) in grad[rows.offset..].iter_mut().zip(grad_rows.iter()) {
+        *g += value;
+    }
+}
+
+/// 由各变量块上的集合组成 OpEn 的笛卡尔积约束
+fn cartesian_product<'s>(
+    blocks: &'s [(usize, Projection)],
+    finite_sets: &'s [Vec<&'s [f64]>],
+) -> constraints::CartesianProduct<'s> {
+    let mut product = constraints::CartesianProduct::new();
+    for ((end, set), finite_set) in blocks.iter().zip(finite_sets) {
+        product = match set {
+            Projection::Free => product.add_constraint(*end, constraints::NoConstraints::new()),
+            Projection::Rectangle(a, b) => product.add_constraint(
+                *end,
+                constraints::Rectangle::new(Some(a.as_slice()), Some(b.as_slice())),
+            ),
+            Projection::Halfspace(a, b) => {
+                product.add_constraint(*end, constraints::Halfspace::new(a, *b))
+            }
+            Projection::Hyperplane(a, b) => {
+                product.add_constraint(*end, constraints::Hyperplane::new(a, *b))
+            }
+            Projection::Ball2(radius) => {
+                product.add_constraint(*end, constraints::Ball2::new(None, *radius))
+            }
+            Projection::FiniteSet(_) => {
+                product.add_constraint(*end, constraints::FiniteSet::new(finite_set))
+            }
+            Projection::Simplex(a) => product.add_constraint(*end, constraints::Simplex::new(*a)),
+            Projection::SecondOrderCone(a) => {
+                product.add_constraint(*end, constraints::SecondOrderCone::new(*a))
+            }
+        };
+    }
+    product
+}
+
+fn unsupported(constraint: &Constraint) -> SolverError {
+    let constraint = match constraint {
+        Constraint::AffineSpace => "affine space",
+        Constraint::Union(..) => "union",
+        Constraint::EpigraphSquaredNorm(..) => "epigraph squared norm",
+        Constraint::FiniteSet(_) => "finite set",
+        Constraint::Simplex(..) => "simplex",
+        Constraint::SecondOrderCone(..) => "second order cone",
+        _ => "this kind of",
+    };
+    SolverError::UnsupportedConstraint {
+        solver: "panoc",
+        constraint,
+    }
+}
+
+fn create(problem: Problem) -> Result<Box<dyn Solver + '_>, SolverError> {
+    Ok(Box::new(PanocSolver::from_problem(problem)?))
+}
+
+inventory::submit! {
+    SolverRegister {
+        solver_type: "panoc",
+        solver_creator: create,
+    }
+}
@@ -0,0 +1,178 @@
+#[cfg(test)]
+mod tests {
+    use message::{Aabb, AabbTree, Collision, CollisionObject, Pose};
+    use nalgebra as na;
+    use sensor::ObstacleList;
+    use std::f64::consts::{FRAC_PI_2, FRAC_PI_4};
+
+    fn sphere(id: usize, x: f64, y: f64, z: f64, radius: f64) -> CollisionObject {
+        CollisionObject::Sphere(Collision::new(id, Pose::translation(x, y, z), radius))
+    }
+
+    fn aabb(mins: [f64; 3], maxs: [f64; 3]) -> Aabb {
+        Aabb::new(mins.into(), maxs.into())
+    }
+
+    fn is_close(a: &Aabb, b: &Aabb) -> bool {
+        (a.mins - b.mins).norm() < 1e-9 && (a.maxs - b.maxs).norm() < 1e-9
+    }
+
+    /// 5 x 5 x 2 个网格排列的球体障碍物
+    fn grid() -> Vec<CollisionObject> {
+        let mut obstacle = Vec::new();
+        for i in 0..5 {
+            for j in 0..5 {
+                for k in 0..2 {
+                    let id = obstacle.len();
+                    let (x, y, z) = (i as f64 * 0.5, j as f64 * 0.5, k as f64 * 0.5);
+                    obstacle.push(sphere(id, x, y, z, 0.1));
+                }
+            }
+        }
+        obstacle
+    }
+
+    #[test]
+    fn collision_object_aabb() {
+        let expected = aabb([0.8, -0.2, -0.2], [1.2, 0.2, 0.2]);
+        assert!(is_close(&sphere(0, 1.0, 0.0, 0.0, 0.2).aabb(), &expected));
+
+        // 沿世界 x 轴放置的胶囊体
+        let capsule = CollisionObject::Capsule(Collision::new(
+            0,
+            Pose::new(na::Vector3::zeros(), na::Vector3::z() * -FRAC_PI_2),
+            (0.1, 0.4),
+        ));
+        let expected = aabb([-0.1, -0.1, -0.1], [0.5, 0.1, 0.1]);
+        assert!(is_close(&capsule.aabb(), &expected));
+
+        // 绕 z 轴旋转 45 度的立方体
+        let cuboid = CollisionObject::Cuboid(Collision::new(
+            0,
+            Pose::new(na::Vector3::zeros(), na::Vector3::z() * FRAC_PI_4),
+            (1.0, 1.0, 1.0),
+        ));
+        let half = 0.5f64.sqrt();
+        let expected = aabb([-half, -half, -0.5], [half, half, 0.5]);
+        assert!(is_close(&cuboid.aabb(), &expected));
+
+        let cylinder = CollisionObject::Cylinder(Collision::new(0, Pose::identity(), (0.5, 1.0)));
+        let expected = aabb([-0.5, 0.0, -0.5], [0.5, 1.0, 0.5]);
+        assert!(is_close(&cylinder.aabb(), &expected));
+    }
+
+    #[test]
+    fn aabb_tree() {
+        let obstacle = grid();
+        let mut tree = AabbTree::new(0.05);
+        let leaves: Vec<usize> = obstacle
+            .iter()
+            .enumerate()
+            .map(|(index, col_obj)| tree.insert(index, col_obj.aabb()))
+            .collect();
+
+        // 树的查询结果包含全部满足条件的物体，且不超出扩张量
+        let check = |tree: &AabbTree, obstacle: &[CollisionObject], query: &Aabb, range: f64| {
+            let mut keys = tree.query_distance(query, range);
+            keys.sort();
+            for (index, col_obj) in obstacle.iter().enumerate() {
+                let distance = col_obj.aabb().distance(query);
+                if distance <= range {
+                    assert!(keys.contains(&index));
+                } else if distance > range + 0.05 {
+                    assert!(!keys.contains(&index));
+                }
+            }
+        };
+        let query = aabb([0.4, 0.4, 0.0], [0.6, 1.1, 0.1]);
+        check(&tree, &obstacle, &query, 0.0);
+        check(&tree, &obstacle, &query, 0.3);
+        check(&tree, &obstacle, &query, 10.0);
+
+        // 小幅移动时不需要重新插入
+        let mut obstacle = obstacle;
+        obstacle[0].set_pose(Pose::translation(0.02, 0.0, 0.0));
+        assert!(!tree.update(leaves[0], obstacle[0].aabb()));
+        obstacle[0].set_pose(Pose::translation(0.5, 0.8, 0.05));
+        assert!(tree.update(leaves[0], obstacle[0].aabb()));
+        check(&tree, &obstacle, &query, 0.0);
+        assert!(tree.query_distance(&query, 0.0).contains(&0));
+
+        tree.remove(leaves[0]);
+        assert!(!tree.query_distance(&query, 0.0).contains(&0));
+        for leaf in &leaves[1..] {
+            tree.remove(*leaf);
+        }
+        assert!(tree.is_empty());
+    }
+
+    #[test]
+    fn obstacle_list() {
+        let obstacle = serde_json::to_value(grid()).unwrap();
+        let mut obstacle_list = ObstacleList::new("obstacle_list".to_string(), obstacle).unwrap();
+
+        let query = aabb([0.9, 0.9, 0.0], [1.1, 1.1, 0.0]);
+        let mut near: Vec<usize> = obstacle_list
+            .obstacle_near(&query, 0.0)
+            .iter()
+            .map(|col_obj| col_obj.id())
+            .collect();
+        near.sort();
+        // 位于 (1.0, 1.0, 0.0) 的球体
+        assert_eq!(near, vec![24]);
+        assert_eq!(obstacle_list.obstacle_near(&query, 0.35).len(), 5);
+
+        // 移动障碍物后包围盒树随之更新
+        obstacle_list.update_pose(0, Pose::translation(1.0, 1.2, 0.0));
+        let near = obstacle_list.obstacle_near(&query, 0.0);
+        assert_eq!(near.len(), 2);
+        assert!(near.iter().any(|col_obj| col_obj.id() == 0));
+        let origin = aabb([-0.1, -0.1, -0.1], [0.1, 0.1, 0.1]);
+        assert!(obstacle_list.obstacle_near(&origin, 0.0).is_empty());
+    }
+
+    #[test]
+    fn ray_cast() {
+        let cuboid = CollisionObject::Cuboid(Collision::new(
+            7,
+            Pose::translation(2.0, 0.0, 0.0),
+            (1.0, 1.0, 1.0),
+        ));
+        let origin = na::Point3::origin();
+        let toi = cuboid.cast_ray(&origin, &na::Vector3::x(), 10.0).unwrap();
+        assert!((toi - 1.5).abs() < 1e-6);
+        assert!(cuboid.cast_ray(&origin, &na::Vector3::x(), 1.0).is_none());
+        assert!(cuboid.cast_ray(&origin, &na::Vector3::y(), 10.0).is_none());
+        let inside = na::Point3::new(2.0, 0.0, 0.0);
+        assert_eq!(cuboid.cast_ray(&inside, &na::Vector3::x(), 10.0), Some(0.0));
+
+        // 斜向击中球面
+        let ball = sphere(0, 1.0, 0.0, 0.0, 0.5);
+        let dir = na::Vector3::new(1.0, 0.3, 0.0).normalize();
+        let toi = ball.cast_ray(&origin, &dir, 10.0).unwrap();
+        let hit = origin + dir * toi;
+        assert!(((hit - na::Point3::new(1.0, 0.0, 0.0)).norm() - 0.5).abs() < 1e-6);
+
+        let obstacle = serde_json::to_value(grid()).unwrap();
+        let mut obstacle_list = ObstacleList::new("obstacle_list".to_string(), obstacle).unwrap();
+        let origin = na::Point3::new(-1.0, 0.5, 0.0);
+        let (id, toi) = obstacle_list
+            .cast_ray(&origin, &na::Vector3::x(), 10.0)
+            .unwrap();
+        assert_eq!(id, 2);
+        assert!((toi - 0.9).abs() < 1e-6);
+
+        // 挡在前面的障碍物优先被击中，方向不必是单位向量
+        obstacle_list.update_pose(3, Pose::translation(-0.5, 0.5, 0.0));
+        let (id, toi) = obstacle_list
+            .cast_ray(&origin, &(na::Vector3::x() * 2.0), 10.0)
+            .unwrap();
+        assert_eq!(id, 3);
+        assert!((toi - 0.4).abs() < 1e-6);
+        assert!(
+            obstacle_list
+                .cast_ray(&origin, &na::Vector3::z(), 10.0)
+                .is_none()
+        );
+    }
+}
@@ -0,0 +1,238 @@
+#[cfg(test)]
+mod tests {
+    use message::{Collision, CollisionObject, Pose};
+    use nalgebra as na;
+    use std::f64::consts::{FRAC_PI_2, FRAC_PI_4, PI};
+
+    fn pose(x: f64, y: f64, z: f64) -> Pose {
+        Pose::translation(x, y, z)
+    }
+
+    /// 局部 y 轴对齐到世界 x 轴
+    fn along_x(x: f64, y: f64, z: f64) -> Pose {
+        Pose::new(na::Vector3::new(x, y, z), na::Vector3::z() * -FRAC_PI_2)
+    }
+
+    /// 局部 y 轴翻转为世界 -y 轴
+    fn upside_down(x: f64, y: f64, z: f64) -> Pose {
+        Pose::new(na::Vector3::new(x, y, z), na::Vector3::x() * PI)
+    }
+
+    fn sphere(pose: Pose, radius: f64) -> CollisionObject {
+        CollisionObject::Sphere(Collision::new(0, pose, radius))
+    }
+
+    fn capsule(pose: Pose, radius: f64, length: f64) -> CollisionObject {
+        CollisionObject::Capsule(Collision::new(0, pose, (radius, length)))
+    }
+
+    fn cylinder(pose: Pose, radius: f64, length: f64) -> CollisionObject {
+        CollisionObject::Cylinder(Collision::new(0, pose, (radius, length)))
+    }
+
+    fn cone(pose: Pose, radius: f64, length: f64) -> CollisionObject {
+        CollisionObject::Cone(Collision::new(0, pose, (radius, length)))
+    }
+
+    fn cuboid(pose: Pose, x: f64, y: f64, z: f64) -> CollisionObject {
+        CollisionObject::Cuboid(Collision::new(0, pose, (x, y, z)))
+    }
+
+    /// 检查带符号距离、最近点与法向量的一致性，以及交换两个物体后的结果
+    fn check(a: &CollisionObject, b: &CollisionObject, expected: f64) {
+        let result = CollisionObject::get_distance_result(a, b);
+        assert!(
+            (result.distance - expected).abs() < 1e-6,
+            "{:?} vs {:?}: {} != {}",
+            a,
+            b,
+            result.distance,
+            expected
+        );
+        assert!((result.normal.norm() - 1.0).abs() < 1e-9);
+        let offset = result.point_b - result.point_a;
+        assert!((offset - result.normal * result.distance).norm() < 1e-6);
+        assert_eq!(CollisionObject::get_distance(a, b), result.distance);
+
+        let swapped = CollisionObject::get_distance_result(b, a);
+        assert!((swapped.distance - result.distance).abs() < 1e-6);
+        assert!((swapped.normal + result.normal).norm() < 1e-3);
+    }
+
+    #[test]
+    fn sphere_sphere() {
+        let a = sphere(pose(0.0, 0.0, 0.0), 0.2);
+        check(&a, &sphere(pose(1.0, 0.0, 0.0), 0.3), 0.5);
+        check(&a, &sphere(pose(0.3, 0.0, 0.0), 0.3), -0.2);
+
+        let result = CollisionObject::get_distance_result(&a, &sphere(pose(1.0, 0.0, 0.0), 0.3));
+        assert!((result.point_a - na::Point3::new(0.2, 0.0, 0.0)).norm() < 1e-12);
+        assert!((result.point_b - na::Point3::new(0.7, 0.0, 0.0)).norm() < 1e-12);
+        assert!((result.normal - na::Vector3::x()).norm() < 1e-12);
+    }
+
+    #[test]
+    fn sphere_capsule() {
+        // 胶囊体中心线段为 (-0.2, 0, 0) 到 (0.2, 0, 0)
+        let b = capsule(along_x(-0.2, 0.0, 0.0), 0.1, 0.4);
+        check(&sphere(pose(0.0, 0.5, 0.0), 0.2), &b, 0.2);
+        check(&sphere(pose(0.6, 0.0, 0.0), 0.2), &b, 0.1);
+        check(&sphere(pose(0.0, 0.1, 0.0), 0.2), &b, -0.2);
+    }
+
+    #[test]
+    fn capsule_capsule() {
+        let a = capsule(along_x(-0.2, 0.0, 0.0), 0.1, 0.4);
+        let b = capsule(
+            Pose::new(
+                na::Vector3::new(0.0, 0.5, -0.2),
+                na::Vector3::x() * FRAC_PI_2,
+            ),
+            0.1,
+            0.4,
+        );
+        check(&a, &b, 0.3);
+
+        // 中心线段相交，法向量取两条线段的公垂线方向
+        let b = capsule(
+            Pose::new(
+                na::Vector3::new(0.0, 0.0, -0.2),
+                na::Vector3::x() * FRAC_PI_2,
+            ),
+            0.1,
+            0.4,
+        );
+        check(&a, &b, -0.2);
+        let result = CollisionObject::get_distance_result(&a, &b);
+        assert!(result.normal.x.abs() < 1e-12 && result.normal.z.abs() < 1e-12);
+    }
+
+    #[test]
+    fn sphere_cylinder() {
+        let b = cylinder(pose(0.0, 0.0, 0.0), 0.5, 1.0);
+        check(&sphere(pose(1.0, 0.5, 0.0), 0.2), &b, 0.3);
+        check(&sphere(pose(0.0, 1.5, 0.0), 0.2), &b, 0.3);
+        check(&sphere(pose(1.0, 1.5, 0.0), 0.2), &b, 0.5f64.sqrt() - 0.2);
+        check(&sphere(pose(0.0, 0.5, 0.3), 0.1), &b, -0.3);
+    }
+
+    #[test]
+    fn sphere_cuboid() {
+        let b = cuboid(pose(0.0, 0.0, 0.0), 1.0, 1.0, 1.0);
+        check(&sphere(pose(1.0, 0.0, 0.0), 0.2), &b, 0.3);
+        check(&sphere(pose(1.0, 1.0, 1.0), 0.2), &b, 0.75f64.sqrt() - 0.2);
+        check(&sphere(pose(0.4, 0.0, 0.0), 0.2), &b, -0.3);
+
+        let result = CollisionObject::get_distance_result(&sphere(pose(0.4, 0.0, 0.0), 0.2), &b);
+        assert!((result.point_a - na::Point3::new(0.2, 0.0, 0.0)).norm() < 1e-9);
+        assert!((result.point_b - na::Point3::new(0.5, 0.0, 0.0)).norm() < 1e-9);
+        assert!((result.normal + na::Vector3::x()).norm() < 1e-9);
+    }
+
+    #[test]
+    fn sphere_cone() {
+        let b = cone(pose(0.0, 0.0, 0.0), 0.5, 1.0);
+        check(&sphere(pose(0.0, -0.5, 0.0), 0.2), &b, 0.3);
+        check(&sphere(pose(0.0, 2.0, 0.0), 0.2), &b, 0.8);
+
+        // 沿侧面母线的外法向偏离 0.5
+        let side =
+            na::Vector3::new(0.25, 0.5, 0.0) + na::Vector3::new(2.0, 1.0, 0.0) * 0.5 / 5f64.sqrt();
+        check(&sphere(pose(side.x, side.y, side.z), 0.2), &b, 0.3);
+        check(&sphere(pose(0.0, 0.2, 0.0), 0.1), &b, -0.3);
+    }
+
+    #[test]
+    fn capsule_cylinder() {
+        let b = cylinder(pose(0.0, 0.0, 0.0), 0.5, 1.0);
+        check(&capsule(along_x(-0.2, 1.3, 0.0), 0.1, 0.4), &b, 0.2);
+        check(&capsule(along_x(-0.2, 1.05, 0.0), 0.1, 0.4), &b, -0.05);
+        check(&capsule(along_x(-0.2, 0.9, 0.0), 0.1, 0.4), &b, -0.2);
+    }
+
+    #[test]
+    fn capsule_cuboid() {
+        let b = cuboid(pose(0.0, 0.0, 0.0), 1.0, 1.0, 1.0);
+        check(&capsule(along_x(-0.2, 1.0, 0.0), 0.1, 0.4), &b, 0.4);
+        check(&capsule(along_x(-0.2, 0.55, 0.0), 0.1, 0.4), &b, -0.05);
+        check(&capsule(along_x(-0.2, 0.4, 0.0), 0.1, 0.4), &b, -0.2);
+    }
+
+    #[test]
+    fn capsule_cone() {
+        let b = cone(pose(0.0, 0.0, 0.0), 0.5, 1.0);
+        check(&capsule(along_x(-0.2, -0.3, 0.0), 0.1, 0.4), &b, 0.2);
+        check(&capsule(pose(0.0, 1.5, 0.0), 0.1, 0.4), &b, 0.4);
+        check(&capsule(along_x(-0.1, 0.1, 0.0), 0.05, 0.2), &b, -0.15);
+    }
+
+    #[test]
+    fn cylinder_cylinder() {
+        let a = cylinder(pose(0.0, 0.0, 0.0), 0.5, 1.0);
+        check(&a, &cylinder(pose(0.0, 1.3, 0.0), 0.5, 1.0), 0.3);
+        check(&a, &cylinder(pose(1.3, 0.0, 0.0), 0.5, 1.0), 0.3);
+        check(&a, &cylinder(along_x(-0.5, 1.6, 0.0), 0.5, 1.0), 0.1);
+        check(&a, &cylinder(pose(0.9, 0.0, 0.0), 0.5, 1.0), -0.1);
+    }
+
+    #[test]
+    fn cylinder_cuboid() {
+        let b = cuboid(pose(0.0, 0.0, 0.0), 1.0, 1.0, 1.0);
+        check(&cylinder(pose(0.0, 0.8, 0.0), 0.5, 1.0), &b, 0.3);
+        check(&cylinder(pose(0.0, 0.4, 0.0), 0.5, 1.0), &b, -0.1);
+
+        // 绕 z 轴旋转 45 度的长方体，最近处为其竖直棱边
+        let b = cuboid(
+            Pose::new(na::Vector3::zeros(), na::Vector3::z() * FRAC_PI_4),
+            1.0,
+            1.0,
+            1.0,
+        );
+        check(
+            &cylinder(pose(1.5, -0.5, 0.0), 0.5, 1.0),
+            &b,
+            1.0 - 0.5f64.sqrt(),
+        );
+    }
+
+    #[test]
+    fn cylinder_cone() {
+        let a = cylinder(pose(0.0, 0.0, 0.0), 0.5, 1.0);
+        check(&a, &cone(pose(0.0, 1.2, 0.0), 0.5, 1.0), 0.2);
+        check(&a, &cone(upside_down(0.0, 2.5, 0.0), 0.5, 1.0), 0.5);
+        check(&a, &cone(upside_down(0.0, 1.8, 0.0), 0.5, 1.0), -0.2);
+    }
+
+    #[test]
+    fn cuboid_cuboid() {
+        let a = cuboid(pose(0.0, 0.0, 0.0), 1.0, 1.0, 1.0);
+        check(&a, &cuboid(pose(1.5, 0.2, 0.1), 1.0, 1.0, 1.0), 0.5);
+        check(&a, &cuboid(pose(0.8, 0.1, 0.0), 1.0, 1.0, 1.0), -0.2);
+        let b = cuboid(
+            Pose::new(
+                na::Vector3::new(2.0, 0.0, 0.0),
+                na::Vector3::z() * FRAC_PI_4,
+            ),
+            1.0,
+            1.0,
+            1.0,
+        );
+        check(&a, &b, 1.5 - 0.5f64.sqrt());
+    }
+
+    #[test]
+    fn cuboid_cone() {
+        let a = cuboid(pose(0.0, 0.0, 0.0), 1.0, 1.0, 1.0);
+        check(&a, &cone(pose(0.0, 0.7, 0.0), 0.5, 1.0), 0.2);
+        check(&a, &cone(pose(0.0, 0.4, 0.0), 0.5, 1.0), -0.1);
+        check(&a, &cone(upside_down(0.0, 1.8, 0.0), 0.5, 1.0), 0.3);
+    }
+
+    #[test]
+    fn cone_cone() {
+        let a = cone(pose(0.0, 0.0, 0.0), 0.5, 1.0);
+        check(&a, &cone(upside_down(0.0, -0.3, 0.0), 0.5, 1.0), 0.3);
+        check(&a, &cone(upside_down(0.0, 3.0, 0.0), 0.5, 1.0), 1.0);
+        check(&a, &cone(upside_down(0.0, 0.2, 0.0), 0.5, 1.0), -0.2);
+    }
+}
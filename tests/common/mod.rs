@@ -0,0 +1,99 @@
+//! 多个测试共用的机器人构造与数值微分，每个测试只用到其中一部分
+#![allow(dead_code)]
+
+use message::{NodeMessage, Pose};
+use nalgebra as na;
+use robot::{
+    AllowedCollisionMatrix, DPanda, DRobot, DSeriseRobot, DhConvention, LinkInertia,
+    SeriseRobotParams, SeriseRobotState,
+};
+
+/// 中心差分的步长
+pub const EPSILON: f64 = 1e-6;
+
+/// 基座位于 base 的 Panda 机器人
+pub fn panda(name: &str, base: Pose) -> DSeriseRobot {
+    DPanda::new_panda(name.to_string(), base)
+}
+
+/// 绕三个轴都有转动的基座，用于检查与基座位姿有关的计算
+pub fn tilted_base() -> Pose {
+    Pose::from_parts(
+        na::Translation3::new(0.3, -0.2, 0.1),
+        na::UnitQuaternion::from_euler_angles(0.2, -0.1, 0.7),
+    )
+}
+
+/// 由 DH 参数与连杆惯性参数构造串联机器人，关节位于零位，各项限位均为 10
+pub fn serise_robot(
+    convention: DhConvention,
+    dh: na::DMatrix<f64>,
+    inertials: Vec<LinkInertia>,
+    base: Pose,
+) -> DSeriseRobot {
+    let nlink = inertials.len();
+    let zeros = na::DVector::zeros(nlink);
+    let bound = na::DVector::from_element(nlink, 10.0);
+    DSeriseRobot {
+        name: "robot".to_string(),
+        state: SeriseRobotState {
+            q: zeros.clone(),
+            q_dot: zeros.clone(),
+            q_ddot: zeros.clone(),
+            q_jerk: zeros.clone(),
+            base,
+            control_message: NodeMessage::NoneNodeMessage,
+        },
+        params: SeriseRobotParams {
+            nlink,
+            q_default: zeros,
+            q_min_bound: -&bound,
+            q_max_bound: bound.clone(),
+            q_dot_bound: bound.clone(),
+            q_ddot_bound: bound.clone(),
+            q_jerk_bound: bound.clone(),
+            tau_bound: bound.clone(),
+            tau_dot_bound: bound,
+            convention,
+            dh,
+            tool: Pose::identity(),
+            capsules: Vec::new(),
+            acm: AllowedCollisionMatrix::new(),
+            inertials,
+        },
+    }
+}
+
+/// 世界坐标系下末端线速度与角速度对关节角的中心差分
+pub fn numeric_jacobian(robot: &DSeriseRobot, q: &na::DVector<f64>) -> na::DMatrix<f64> {
+    let mut jacobian = na::DMatrix::zeros(6, q.len());
+    for i in 0..q.len() {
+        let mut q_plus = q.clone();
+        q_plus[i] += EPSILON;
+        let mut q_minus = q.clone();
+        q_minus[i] -= EPSILON;
+        let pose_plus = robot.cul_end_pose(&q_plus);
+        let pose_minus = robot.cul_end_pose(&q_minus);
+        let linear =
+            (pose_plus.translation.vector - pose_minus.translation.vector) / (2.0 * EPSILON);
+        let angular =
+            (pose_plus.rotation * pose_minus.rotation.inverse()).scaled_axis() / (2.0 * EPSILON);
+        jacobian.fixed_view_mut::<3, 1>(0, i).copy_from(&linear);
+        jacobian.fixed_view_mut::<3, 1>(3, i).copy_from(&angular);
+    }
+    jacobian
+}
+
+/// 标量函数对关节角的中心差分
+pub fn numeric_grad(
+    q: &na::DVector<f64>,
+    func: impl Fn(&na::DVector<f64>) -> f64,
+) -> na::DVector<f64> {
+    na::DVector::from_fn(q.len(), |i, _| {
+        let mut q_plus = q.clone();
+        q_plus[i] += EPSILON;
+        let mut q_minus = q.clone();
+        q_minus[i] -= EPSILON;
+        (func(&q_plus) - func(&q_minus)) / (2.0 * EPSILON)
+    })
+}
@@ -1,7 +1,13 @@
+mod common;
+
 #[cfg(test)]
 mod tests {
+    use message::Pose;
     use nalgebra as na;
-    use std::f64::consts::FRAC_PI_2;
+    use robot::{DPanda, DRobot, DhConvention, JacobianFrame, LinkInertia, SPanda, SRobot};
+    use std::f64::consts::{FRAC_1_SQRT_2, FRAC_PI_2, FRAC_PI_4};
+
+    use crate::common::{numeric_jacobian, serise_robot};
 
     const PANDA_DOF: usize = 7;
 
@@ -86,4 +92,162 @@ mod tests {
             transform.to_homogeneous().transpose() * transform.to_homogeneous()
         );
     }
+
+    const L1: f64 = 1.0;
+    const L2: f64 = 0.8;
+    const LC1: f64 = 0.5;
+    const LC2: f64 = 0.4;
+
+    /// 由 Panda 官方 URDF 中各关节的原点逐个相乘得到的法兰位姿，与 DH 参数无关
+    #[rustfmt::skip]
+    const PANDA_REFERENCE: [([f64; 7], [f64; 3], [f64; 9]); 2] = [
+        (
+            [0.0, -FRAC_PI_4, 0.0, -3.0 * FRAC_PI_4, 0.0, FRAC_PI_2, FRAC_PI_4],
+            [0.306890566593, 0.0, 0.590282052303],
+            [
+                FRAC_1_SQRT_2, -FRAC_1_SQRT_2, 0.0,
+                -FRAC_1_SQRT_2, -FRAC_1_SQRT_2, 0.0,
+                0.0, 0.0, -1.0,
+            ],
+        ),
+        (
+            [0.1, -0.6, 0.3, -2.0, 0.2, 1.5, 0.7],
+            [0.309950392757, 0.199028956718, 0.675320607446],
+            [
+                0.947564281337, -0.313851508161, 0.060159484364,
+                -0.31714441203, -0.946700295262, 0.056373512116,
+                0.039260089812, -0.072496770792, -0.996595636942,
+            ],
+        ),
+    ];
+
+    fn assert_pose(pose: &Pose, position: &[f64; 3], rotation: &[f64; 9]) {
+        let expected = na::Matrix3::from_row_slice(rotation);
+        let rotation = pose.rotation.to_rotation_matrix().into_inner();
+        assert!((pose.translation.vector - na::Vector3::from(*position)).amax() < 1e-9);
+        assert!((rotation - expected).amax() < 1e-9);
+    }
+
+    #[test]
+    fn panda_reference_poses() {
+        let dynamic = DPanda::new_panda("panda_1".to_string(), Pose::identity());
+        let fixed = SPanda::new_panda("panda_1".to_string(), Pose::identity());
+        for (q, position, rotation) in PANDA_REFERENCE.iter() {
+            assert_pose(
+                &dynamic.cul_end_pose(&na::DVector::from_row_slice(q)),
+                position,
+                rotation,
+            );
+            assert_pose(
+                &fixed.cul_end_pose(&na::SVector::from(*q)),
+                position,
+                rotation,
+            );
+        }
+
+        // 动态与静态版本的胶囊体一致
+        let q = PANDA_REFERENCE[1].0;
+        let dynamic_capsules = dynamic.cul_capsules(&na::DVector::from_row_slice(&q));
+        let fixed_capsules = fixed.cul_capsules(&na::SVector::from(q));
+        assert_eq!(dynamic_capsules.len(), 7);
+        for (a, b) in dynamic_capsules.iter().zip(fixed_capsules.iter()) {
+            assert_eq!(a.pose, b.pose);
+            assert_eq!(a.params, b.params);
+        }
+    }
+
+    #[test]
+    fn panda_tool_frame() {
+        let mut panda = DPanda::new_panda("panda_1".to_string(), Pose::identity());
+        panda.params.tool = Pose::translation(0.0, 0.0, 0.1034);
+        let (q, position, _) = PANDA_REFERENCE[0];
+        let q = na::DVector::from_row_slice(&q);
+        let pose = panda.cul_end_pose(&q);
+        let expected = na::Vector3::new(position[0], position[1], position[2] - 0.1034);
+        assert!((pose.translation.vector - expected).amax() < 1e-9);
+
+        // 雅可比矩阵的参考点随工具坐标系移动
+        let jacobian = panda.cul_jacobian(&q, JacobianFrame::World);
+        assert!((jacobian - numeric_jacobian(&panda, &q)).amax() < 1e-6);
+    }
+
+    /// UR5 使用标准 DH 参数，零位时的末端位置为 (a2 + a3, -(d4 + d6), d1 - d5)
+    #[test]
+    fn standard_convention_ur5() {
+        #[rustfmt::skip]
+        let dh = na::DMatrix::from_row_slice(6, 4, &[
+            0.0, 0.089159, 0.0,      FRAC_PI_2,
+            0.0, 0.0,      -0.425,   0.0,
+            0.0, 0.0,      -0.39225, 0.0,
+            0.0, 0.10915,  0.0,      FRAC_PI_2,
+            0.0, 0.09465,  0.0,      -FRAC_PI_2,
+            0.0, 0.0823,   0.0,      0.0,
+        ]);
+        let inertials = vec![LinkInertia::new(1.0, [0.0; 3], [0.01, 0.0, 0.0, 0.01, 0.0, 0.01]); 6];
+        let ur5 = serise_robot(DhConvention::Standard, dh, inertials, Pose::identity());
+
+        let pose = ur5.cul_end_pose(&na::DVector::zeros(6));
+        let expected = na::Vector3::new(-0.81725, -0.19145, 0.089159 - 0.09465);
+        assert!((pose.translation.vector - expected).amax() < 1e-12);
+
+        // 标准 DH 下关节绕上一个连杆坐标系的 z 轴转动，雅可比矩阵与数值微分一致
+        let q = na::dvector![0.3, -0.8, 1.1, -0.4, 0.6, 0.2];
+        let jacobian = ur5.cul_jacobian(&q, JacobianFrame::World);
+        assert!((jacobian - numeric_jacobian(&ur5, &q)).amax() < 1e-6);
+    }
+
+    /// 同一个竖直平面二连杆分别用标准与改进 DH 参数描述，运动学与动力学结果一致
+    #[test]
+    fn standard_matches_modified() {
+        let base = Pose::from_parts(
+            na::Translation3::new(0.1, 0.2, 0.3),
+            na::UnitQuaternion::from_axis_angle(&na::Vector3::x_axis(), FRAC_PI_2),
+        );
+        let inertia =
+            |lc: f64| LinkInertia::new(1.5 + lc, [lc, 0.0, 0.0], [0.01, 0.0, 0.0, 0.02, 0.0, 0.1]);
+
+        #[rustfmt::skip]
+        let modified_dh = na::DMatrix::from_row_slice(3, 4, &[
+            0.0, 0.0, 0.0, 0.0,
+            0.0, 0.0, L1,  0.0,
+            0.0, 0.0, L2,  0.0,
+        ]);
+        let modified = serise_robot(
+            DhConvention::Modified,
+            modified_dh,
+            vec![inertia(LC1), inertia(LC2)],
+            base,
+        );
+
+        // 标准 DH 的连杆坐标系位于连杆末端，质心位置相应平移
+        #[rustfmt::skip]
+        let standard_dh = na::DMatrix::from_row_slice(2, 4, &[
+            0.0, 0.0, L1, 0.0,
+            0.0, 0.0, L2, 0.0,
+        ]);
+        let mut inertials = vec![inertia(LC1), inertia(LC2)];
+        inertials[0].com.x -= L1;
+        inertials[1].com.x -= L2;
+        let standard = serise_robot(DhConvention::Standard, standard_dh, inertials, base);
+
+        let q = na::dvector![0.3, 0.7];
+        let q_dot = na::dvector![0.5, -1.2];
+        let a = modified.cul_end_pose(&q);
+        let b = standard.cul_end_pose(&q);
+        assert!((a.translation.vector - b.translation.vector).amax() < 1e-12);
+        assert!(a.rotation.angle_to(&b.rotation) < 1e-12);
+        for frame in [JacobianFrame::World, JacobianFrame::End] {
+            let difference = modified.cul_jacobian(&q, frame) - standard.cul_jacobian(&q, frame);
+            assert!(difference.amax() < 1e-12);
+            let difference = modified.cul_jacobian_dot(&q, &q_dot, frame)
+                - standard.cul_jacobian_dot(&q, &q_dot, frame);
+            assert!(difference.amax() < 1e-12);
+        }
+        assert!((modified.cul_mass_matrix(&q) - standard.cul_mass_matrix(&q)).amax() < 1e-12);
+        assert!((modified.cul_gravity(&q) - standard.cul_gravity(&q)).amax() < 1e-12);
+        let tau = na::dvector![1.0, -2.0];
+        let difference = modified.cul_forward_dynamics(&q, &q_dot, &tau)
+            - standard.cul_forward_dynamics(&q, &q_dot, &tau);
+        assert!(difference.amax() < 1e-9);
+    }
 }
@@ -0,0 +1,97 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use message::Pose;
+    use nalgebra as na;
+    use robot::{DPanda, DRobot, DSeriseRobot, DhConvention, GRAVITY, LinkInertia};
+    use std::f64::consts::FRAC_PI_2;
+
+    use crate::common::serise_robot;
+
+    const L1: f64 = 1.0;
+    const LC1: f64 = 0.5;
+    const LC2: f64 = 0.4;
+    const M1: f64 = 2.0;
+    const M2: f64 = 1.5;
+    const I1: f64 = 0.1;
+    const I2: f64 = 0.05;
+
+    /// 在竖直平面内运动的二连杆，基座绕 x 轴旋转 90 度，使重力沿基座坐标系的 -y 方向
+    fn two_link() -> DSeriseRobot {
+        let base = Pose::from_parts(
+            na::Translation3::identity(),
+            na::UnitQuaternion::from_axis_angle(&na::Vector3::x_axis(), FRAC_PI_2),
+        );
+        #[rustfmt::skip]
+        let dh = na::DMatrix::from_row_slice(2, 4, &[
+            0.0, 0.0, 0.0, 0.0,
+            0.0, 0.0, L1,  0.0,
+        ]);
+        let inertials = vec![
+            LinkInertia::new(M1, [LC1, 0.0, 0.0], [0.01, 0.0, 0.0, 0.01, 0.0, I1]),
+            LinkInertia::new(M2, [LC2, 0.0, 0.0], [0.01, 0.0, 0.0, 0.01, 0.0, I2]),
+        ];
+        serise_robot(DhConvention::Modified, dh, inertials, base)
+    }
+
+    fn assert_close(a: &na::DVector<f64>, b: &na::DVector<f64>) {
+        assert!((a - b).amax() < 1e-9, "{} != {}", a, b);
+    }
+
+    #[test]
+    fn two_link_closed_form() {
+        let robot = two_link();
+        let q: na::DVector<f64> = na::dvector![0.3, 0.7];
+        let q_dot = na::dvector![0.5, -1.2];
+        let q_ddot = na::dvector![0.8, 0.4];
+        let (c2, s2) = (q[1].cos(), q[1].sin());
+
+        let m11 = M1 * LC1 * LC1 + I1 + M2 * (L1 * L1 + LC2 * LC2 + 2.0 * L1 * LC2 * c2) + I2;
+        let m12 = M2 * (LC2 * LC2 + L1 * LC2 * c2) + I2;
+        let m22 = M2 * LC2 * LC2 + I2;
+        let mass_matrix = robot.cul_mass_matrix(&q);
+        let expected = na::dmatrix![m11, m12; m12, m22];
+        assert!((mass_matrix - &expected).amax() < 1e-9);
+
+        let h = M2 * L1 * LC2 * s2;
+        let coriolis = na::dvector![
+            -h * (2.0 * q_dot[0] * q_dot[1] + q_dot[1] * q_dot[1]),
+            h * q_dot[0] * q_dot[0]
+        ];
+        assert_close(&robot.cul_coriolis(&q, &q_dot), &coriolis);
+
+        let cos12 = (q[0] + q[1]).cos();
+        let gravity = na::dvector![
+            (M1 * LC1 + M2 * L1) * GRAVITY * q[0].cos() + M2 * LC2 * GRAVITY * cos12,
+            M2 * LC2 * GRAVITY * cos12
+        ];
+        assert_close(&robot.cul_gravity(&q), &gravity);
+
+        let tau = &expected * &q_ddot + &coriolis + &gravity;
+        assert_close(&robot.cul_inverse_dynamics(&q, &q_dot, &q_ddot), &tau);
+        assert_close(&robot.cul_forward_dynamics(&q, &q_dot, &tau), &q_ddot);
+    }
+
+    #[test]
+    fn panda_dynamics() {
+        let robot = DPanda::new_panda("panda_1".to_string(), Pose::identity());
+        let q = na::dvector![0.1, -0.6, 0.3, -2.0, 0.2, 1.5, 0.7];
+        let q_dot = na::dvector![0.3, -0.2, 0.5, 0.1, -0.4, 0.6, -0.3];
+        let q_ddot = na::dvector![1.0, -0.5, 0.2, 0.8, -1.2, 0.3, 0.6];
+
+        // 质量矩阵对称正定
+        let mass_matrix = robot.cul_mass_matrix(&q);
+        assert!((&mass_matrix - mass_matrix.transpose()).amax() < 1e-9);
+        assert!(mass_matrix.clone().cholesky().is_some());
+
+        // 第一个关节的转轴竖直向上，重力不产生力矩
+        let gravity = robot.cul_gravity(&q);
+        assert!(gravity[0].abs() < 1e-9);
+
+        let tau = robot.cul_inverse_dynamics(&q, &q_dot, &q_ddot);
+        let coriolis = robot.cul_coriolis(&q, &q_dot);
+        assert_close(&(&mass_matrix * &q_ddot + coriolis + gravity), &tau);
+        assert_close(&robot.cul_forward_dynamics(&q, &q_dot, &tau), &q_ddot);
+    }
+}
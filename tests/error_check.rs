@@ -0,0 +1,56 @@
+#[cfg(test)]
+mod tests {
+    use manager::{Config, ManagerError};
+    use node::NodeError;
+    use robot::{RobotConfig, RobotError};
+    use sensor::{SensorConfig, SensorError};
+
+    #[test]
+    fn config_errors() {
+        let error = Config::from_file("./config/not_exist.json").unwrap_err();
+        assert!(matches!(error, ManagerError::Io { .. }));
+    }
+
+    #[test]
+    fn loader_errors() {
+        let robot_config: RobotConfig = serde_json::from_str(
+            r#"{ "name": "ur_1", "robot_type": "ur5", "base_pose": { "rotation": [1.0, 0.0, 0.0, 0.0], "translation": [0.0, 0.0, 0.0] } }"#,
+        )
+        .unwrap();
+        assert_eq!(
+            robot::from_config(&robot_config).err(),
+            Some(RobotError::UnknownType("ur5".to_string()))
+        );
+
+        let sensor_config: SensorConfig = serde_json::from_str(
+            r#"{ "name": "obstacle_list_1", "sensor_type": "obstacle_list", "params": [{ "Sphere": { "id": 1 } }] }"#,
+        )
+        .unwrap();
+        assert!(matches!(
+            sensor::from_config(&sensor_config),
+            Err(SensorError::Params(_))
+        ));
+
+        assert!(matches!(
+            node::factory::<nalgebra::DVector<f64>>(
+                "not_exist",
+                "panda_1",
+                serde_json::Value::Null
+            ),
+            Err(NodeError::UnknownType(_))
+        ));
+
+        let params = serde_json::json!({
+            "period": 0.1,
+            "ninterp": 10,
+            "niter": 5,
+            "cost_weight": [1.0, 1.0, 1.0],
+            "solver": "ipopt"
+        });
+        let error = node::factory::<nalgebra::DVector<f64>>("cfs", "panda_1", params)
+            .err()
+            .unwrap();
+        assert!(matches!(error, NodeError::Params { .. }));
+        assert!(error.to_string().contains("ipopt"));
+    }
+}
@@ -0,0 +1,44 @@
+#[cfg(test)]
+mod tests {
+    use std::{fs, path::PathBuf, process::Command};
+
+    /// 正式配置与 example 目录下所有 *_task.json 及其同名的 *_config.json
+    fn exp_pairs() -> Vec<(PathBuf, PathBuf)> {
+        let mut pairs = vec![(
+            PathBuf::from("./config/config.json"),
+            PathBuf::from("./config/task.json"),
+        )];
+        for entry in fs::read_dir("./example").unwrap() {
+            let task = entry.unwrap().path();
+            let name = task.file_name().unwrap().to_str().unwrap().to_string();
+            if let Some(prefix) = name.strip_suffix("_task.json") {
+                pairs.push((task.with_file_name(format!("{}_config.json", prefix)), task));
+            }
+        }
+        pairs.sort();
+        pairs
+    }
+
+    #[test]
+    fn validate_examples() {
+        let pairs = exp_pairs();
+        assert!(pairs.len() > 1);
+        for (config, task) in pairs {
+            let output = Command::new(env!("CARGO_BIN_EXE_robot_platform"))
+                .arg("validate")
+                .arg("-c")
+                .arg(&config)
+                .arg("-t")
+                .arg(&task)
+                .output()
+                .unwrap();
+            assert!(
+                output.status.success(),
+                "{} 与 {} 未通过检查:\n{}",
+                config.display(),
+                task.display(),
+                String::from_utf8_lossy(&output.stdout)
+            );
+        }
+    }
+}
@@ -0,0 +1,68 @@
+#[cfg(test)]
+mod tests {
+    use manager::{FileFormat, NodeConfig, Task};
+    use std::path::Path;
+
+    #[test]
+    fn format_from_extension() {
+        let format = |path: &str| FileFormat::from_path(Path::new(path));
+        assert_eq!(format("./config/task.json"), Some(FileFormat::Json));
+        assert_eq!(format("./config/task.yml"), Some(FileFormat::Yaml));
+        assert_eq!(format("./config/task.YAML"), Some(FileFormat::Yaml));
+        assert_eq!(format("./config/config.toml"), Some(FileFormat::Toml));
+        assert_eq!(format("./config/task"), None);
+    }
+
+    #[test]
+    fn node_schema() {
+        // 旧版元组写法与具名字段写法得到相同的节点配置
+        let tuple: Vec<Task> = FileFormat::Json
+            .parse(
+                r#"[{ "id": 1, "rely": [], "target": [],
+                      "nodes": [["obstacle_releaser", [], ["obstacle_list_1"], { "period": 0.1 }]],
+                      "edges": [[0, 1]] }]"#,
+            )
+            .unwrap();
+        let named: Vec<Task> = FileFormat::Yaml
+            .parse(
+                r#"
+- id: 1
+  rely: []
+  target: []
+  nodes:
+    - type: obstacle_releaser
+      sensors: [obstacle_list_1]
+      params: { period: 0.1 }
+  edges: [[0, 1]]
+"#,
+            )
+            .unwrap();
+        let expected = NodeConfig {
+            node_type: "obstacle_releaser".to_string(),
+            robots: vec![],
+            sensors: vec!["obstacle_list_1".to_string()],
+            params: serde_json::json!({ "period": 0.1 }),
+            id: None,
+            ..Default::default()
+        };
+        assert_eq!(tuple[0].nodes, vec![expected.clone()]);
+        assert_eq!(named[0].nodes, vec![expected]);
+    }
+
+    #[test]
+    fn toml_node() {
+        let node: NodeConfig = FileFormat::Toml
+            .parse(
+                r#"
+type = "interp"
+id = "interp"
+robots = ["panda_1"]
+params = { period = 0.1, interp_fn = "lerp", ninter = 25 }
+"#,
+            )
+            .unwrap();
+        assert_eq!(node.node_type, "interp");
+        assert_eq!(node.id.as_deref(), Some("interp"));
+        assert!(node.sensors.is_empty());
+    }
+}
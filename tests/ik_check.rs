@@ -0,0 +1,85 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use message::Pose;
+    use nalgebra as na;
+    use robot::{
+        DRobot, DSeriseRobot, IkMethod, IkMode, IkParams, Robot, best_ik, sample_ik, solve_ik,
+    };
+
+    use crate::common::tilted_base;
+
+    fn panda() -> DSeriseRobot {
+        crate::common::panda("panda_1", tilted_base())
+    }
+
+    fn within_bounds(robot: &DSeriseRobot, q: &na::DVector<f64>) -> bool {
+        let q_min = robot.q_min_bound();
+        let q_max = robot.q_max_bound();
+        (0..q.len()).all(|i| q_min[i] <= q[i] && q[i] <= q_max[i])
+    }
+
+    #[test]
+    fn pose_ik() {
+        let robot = panda();
+        let target = robot.cul_end_pose(&na::dvector![0.4, -0.3, 0.2, -1.8, 0.3, 1.8, 0.5]);
+        for method in [IkMethod::Dls, IkMethod::Qp] {
+            let params = IkParams {
+                method,
+                ..Default::default()
+            };
+            let result = solve_ik(&robot, &target, &robot.q(), &params);
+            assert!(result.converged, "{:?}: {}", method, result.residual);
+            assert!(within_bounds(&robot, &result.q));
+
+            let pose = robot.cul_end_pose(&result.q);
+            assert!((pose.translation.vector - target.translation.vector).norm() < 1e-6);
+            assert!(pose.rotation.angle_to(&target.rotation) < 1e-6);
+        }
+    }
+
+    #[test]
+    fn position_ik() {
+        let robot = panda();
+        let target = Pose::from_parts(
+            na::Translation3::new(0.7, 0.1, 0.5),
+            na::UnitQuaternion::identity(),
+        );
+        let params = IkParams {
+            mode: IkMode::Position,
+            ..Default::default()
+        };
+        let result = best_ik(&robot, &target, &params);
+        assert!(result.converged);
+        let position = robot.cul_end_pose(&result.q).translation.vector;
+        assert!((position - target.translation.vector).norm() < 1e-6);
+        assert!((result.residual - (position - target.translation.vector).norm()).abs() < 1e-12);
+    }
+
+    #[test]
+    fn joint_limits_and_sampling() {
+        let robot = panda();
+        let target = robot.cul_end_pose(&na::dvector![-0.5, 0.4, -0.3, -1.2, 0.6, 2.2, -0.4]);
+        let solutions = sample_ik(&robot, &target, &IkParams::default());
+        assert!(!solutions.is_empty());
+        let q = robot.q();
+        for pair in solutions.windows(2) {
+            assert!((&pair[0].q - &q).norm() <= (&pair[1].q - &q).norm());
+        }
+        for solution in solutions.iter() {
+            assert!(solution.converged);
+            assert!(within_bounds(&robot, &solution.q));
+        }
+
+        // 目标在工作空间之外，结果仍然在关节边界内并给出残差
+        let unreachable = Pose::from_parts(
+            na::Translation3::new(3.0, 0.0, 0.5),
+            na::UnitQuaternion::identity(),
+        );
+        let result = best_ik(&robot, &unreachable, &IkParams::default());
+        assert!(!result.converged);
+        assert!(result.residual > 1.0);
+        assert!(within_bounds(&robot, &result.q));
+    }
+}
@@ -0,0 +1,162 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use message::{Collision, CollisionObject, Pose};
+    use nalgebra as na;
+    use robot::{DRobot, DSeriseRobot, JacobianFrame, SPanda, SRobot};
+
+    use crate::common::{EPSILON, numeric_grad, numeric_jacobian, tilted_base};
+
+    fn panda() -> DSeriseRobot {
+        crate::common::panda("panda_1", tilted_base())
+    }
+
+    fn q() -> na::DVector<f64> {
+        na::dvector![0.1, -0.6, 0.3, -2.0, 0.2, 1.5, 0.7]
+    }
+
+    #[test]
+    fn jacobian_frames() {
+        let robot = panda();
+        let q = q();
+        let world = robot.cul_jacobian(&q, JacobianFrame::World);
+        assert!((&world - numeric_jacobian(&robot, &q)).amax() < 1e-6);
+
+        // 其他坐标系下的雅可比矩阵为世界坐标系下的结果分块旋转
+        let rotate = |rotation: na::UnitQuaternion<f64>| {
+            let rotation = rotation.to_rotation_matrix().into_inner();
+            let mut rotated = world.clone();
+            rotated
+                .rows_mut(0, 3)
+                .copy_from(&(rotation * world.rows(0, 3)));
+            rotated
+                .rows_mut(3, 3)
+                .copy_from(&(rotation * world.rows(3, 3)));
+            rotated
+        };
+        let base = robot.cul_jacobian(&q, JacobianFrame::Base);
+        assert!((base - rotate(robot.state.base.rotation.inverse())).amax() < 1e-12);
+        let end = robot.cul_jacobian(&q, JacobianFrame::End);
+        assert!((end - rotate(robot.cul_end_pose(&q).rotation.inverse())).amax() < 1e-12);
+    }
+
+    #[test]
+    fn jacobian_dot() {
+        let robot = panda();
+        let q = q();
+        let q_dot = na::dvector![0.3, -0.2, 0.5, 0.1, -0.4, 0.6, -0.3];
+        for frame in [
+            JacobianFrame::World,
+            JacobianFrame::Base,
+            JacobianFrame::End,
+        ] {
+            let numeric = (robot.cul_jacobian(&(&q + &q_dot * EPSILON), frame)
+                - robot.cul_jacobian(&(&q - &q_dot * EPSILON), frame))
+                / (2.0 * EPSILON);
+            let analytic = robot.cul_jacobian_dot(&q, &q_dot, frame);
+            assert!((analytic - numeric).amax() < 1e-6, "{:?}", frame);
+        }
+    }
+
+    /// 第一个为与机械臂相交的球体，第二个为斜放在机械臂附近的长方体
+    fn obstacles(robot: &DSeriseRobot) -> Vec<CollisionObject> {
+        let end = robot.cul_end_pose(&q()).translation.vector;
+        vec![
+            CollisionObject::Sphere(Collision::new(
+                1,
+                Pose::translation(end.x, end.y, end.z),
+                0.1,
+            )),
+            CollisionObject::Cuboid(Collision::new(
+                2,
+                Pose::new(
+                    end + na::Vector3::new(0.2, 0.1, 0.3),
+                    na::Vector3::new(0.3, 0.5, 0.2),
+                ),
+                (0.2, 0.3, 0.1),
+            )),
+        ]
+    }
+
+    #[test]
+    fn distance_grad() {
+        let robot = panda();
+        let q = q();
+        for obstacle in obstacles(&robot) {
+            let distances = robot.cul_link_distances(&q, &obstacle);
+            assert_eq!(distances.len(), 7);
+            for distance in distances.iter() {
+                let result = distance.result;
+                let offset = result.point_b - result.point_a;
+                assert!((offset - result.normal * result.distance).norm() < 1e-6);
+
+                // 按连杆分别做中心差分
+                let link_distance = |q: &na::DVector<f64>| {
+                    robot.cul_link_distances(q, &obstacle)[distance.link]
+                        .result
+                        .distance
+                };
+                let numeric = numeric_grad(&q, link_distance);
+                let analytic = robot.cul_distance_grad(&q, distance);
+                assert!(
+                    (&analytic - &numeric).amax() < 1e-5,
+                    "link {}: {} vs {}",
+                    distance.link,
+                    analytic,
+                    numeric
+                );
+            }
+
+            // 最小距离即最近连杆的距离
+            let closest = distances
+                .iter()
+                .min_by(|a, b| a.result.distance.total_cmp(&b.result.distance))
+                .unwrap();
+            assert_eq!(
+                robot.cul_dis_to_collision(&q, &obstacle)[0],
+                closest.result.distance
+            );
+        }
+        let sphere = &obstacles(&robot)[0];
+        assert!(robot.cul_dis_to_collision(&q, sphere)[0] < 0.0);
+    }
+
+    #[test]
+    fn static_distance_grad() {
+        let robot = panda();
+        let s_robot = SPanda::new_panda("panda_1".to_string(), robot.state.base);
+        let q = q();
+        let s_q = na::SVector::<f64, 7>::from_column_slice(q.as_slice());
+        for obstacle in obstacles(&robot) {
+            let closest = robot
+                .cul_link_distances(&q, &obstacle)
+                .into_iter()
+                .min_by(|a, b| a.result.distance.total_cmp(&b.result.distance))
+                .unwrap();
+            let grad = s_robot.cul_dis_grad_to_collision(&s_q, &obstacle);
+            assert!((grad - robot.cul_distance_grad(&q, &closest)).amax() < 1e-12);
+            assert_eq!(
+                s_robot.cul_link_distances(&s_q, &obstacle),
+                robot.cul_link_distances(&q, &obstacle)
+            );
+        }
+    }
+
+    #[test]
+    fn static_to_dynamic() {
+        // 定长机器人转换为变长机器人后，运动学与自碰撞结果保持一致
+        let robot = panda();
+        let mut s_robot = SPanda::new_panda("panda_1".to_string(), robot.state.base);
+        let q = q();
+        s_robot.state.q = na::SVector::from_column_slice(q.as_slice());
+        let d_robot = s_robot.to_dynamic();
+        assert_eq!(d_robot.state.q, q);
+        assert_eq!(d_robot.end_pose(), robot.cul_end_pose(&q));
+        assert_eq!(
+            d_robot.cul_jacobian(&q, JacobianFrame::World),
+            robot.cul_jacobian(&q, JacobianFrame::World)
+        );
+        assert_eq!(d_robot.cul_self_distances(&q), robot.cul_self_distances(&q));
+    }
+}
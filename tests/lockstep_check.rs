@@ -0,0 +1,139 @@
+#[cfg(test)]
+mod tests {
+    use std::{
+        sync::{Arc, Mutex, mpsc},
+        time::Duration,
+    };
+
+    use manager::{LockstepError, LockstepExecutor};
+    use message::TaskState;
+    use node::{NodeBehavior, NodeState};
+
+    /// 每次 update 都把自己的名字记录下来，执行 updates 次后结束
+    struct RecordNode {
+        name: String,
+        period: Duration,
+        updates: usize,
+        trace: Arc<Mutex<Vec<String>>>,
+    }
+
+    impl NodeBehavior for RecordNode {
+        fn update(&mut self) {
+            self.updates -= 1;
+            self.trace.lock().unwrap().push(self.name.clone());
+        }
+        fn state(&mut self) -> NodeState {
+            match self.updates {
+                0 => NodeState::Finished,
+                _ => NodeState::Running,
+            }
+        }
+        fn period(&self) -> Duration {
+            self.period
+        }
+        fn node_name(&self) -> String {
+            self.name.clone()
+        }
+    }
+
+    fn record(
+        name: &str,
+        period_ms: u64,
+        trace: &Arc<Mutex<Vec<String>>>,
+    ) -> Box<dyn NodeBehavior> {
+        Box::new(RecordNode {
+            name: name.to_string(),
+            period: Duration::from_millis(period_ms),
+            updates: 3,
+            trace: trace.clone(),
+        })
+    }
+
+    fn run() -> (Vec<String>, Vec<TaskState>, Duration) {
+        let (sender, receiver) = mpsc::channel();
+        let trace = Arc::new(Mutex::new(Vec::new()));
+        let mut executor = LockstepExecutor::new(sender, Duration::from_millis(1));
+        // 节点 1 是节点 0 的上游，因此先执行
+        let nodes = vec![
+            record("controller", 1, &trace),
+            record("planner", 2, &trace),
+        ];
+        executor.add_nodes(nodes, &[(1, 0)]).unwrap();
+        executor.run_for(Duration::from_secs(1));
+
+        let trace = trace.lock().unwrap().clone();
+        (trace, receiver.try_iter().collect(), executor.clock())
+    }
+
+    #[test]
+    fn lockstep_order() {
+        let (trace, task_states, clock) = run();
+        assert_eq!(
+            trace,
+            vec![
+                "planner",
+                "controller",
+                "controller",
+                "planner",
+                "controller",
+                "planner",
+            ]
+        );
+        assert!(matches!(
+            task_states.as_slice(),
+            [TaskState::NodeEnd(first), TaskState::NodeEnd(second)]
+                if first == "controller" && second == "planner"
+        ));
+        assert_eq!(clock, Duration::from_millis(6));
+
+        // 同样的输入每次运行都得到同样的结果
+        assert_eq!(run().0, trace);
+    }
+
+    #[test]
+    fn period_not_multiple() {
+        let (sender, _receiver) = mpsc::channel();
+        let trace = Arc::new(Mutex::new(Vec::new()));
+        let mut executor = LockstepExecutor::new(sender, Duration::from_millis(2));
+        let error = executor
+            .add_nodes(vec![record("controller", 3, &trace)], &[])
+            .unwrap_err();
+        assert_eq!(
+            error,
+            LockstepError::PeriodNotMultiple {
+                node: "controller".to_string(),
+                period: Duration::from_millis(3),
+                base_tick: Duration::from_millis(2),
+            }
+        );
+        assert!(executor.is_idle());
+    }
+
+    /// 一直处于释放约束状态的节点
+    struct ReleaseNode;
+
+    impl NodeBehavior for ReleaseNode {
+        fn state(&mut self) -> NodeState {
+            NodeState::RelyRelease
+        }
+        fn node_name(&self) -> String {
+            "simulator".to_string()
+        }
+    }
+
+    #[test]
+    fn rely_release_once() {
+        let (sender, receiver) = mpsc::channel();
+        let mut executor = LockstepExecutor::new(sender, Duration::from_millis(1));
+        executor
+            .add_nodes(vec![Box::new(ReleaseNode)], &[])
+            .unwrap();
+        executor.run_for(Duration::from_millis(5));
+
+        let task_states: Vec<TaskState> = receiver.try_iter().collect();
+        assert!(matches!(
+            task_states.as_slice(),
+            [TaskState::RelyRelease(name)] if name == "simulator"
+        ));
+    }
+}
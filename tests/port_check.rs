@@ -0,0 +1,75 @@
+#[cfg(test)]
+mod tests {
+    use message::{DNodeMessage, MessageKind};
+    use nalgebra as na;
+    use node::{NodeError, NodeHandle, OutputQueue, connect, create_node};
+    use std::sync::Arc;
+
+    fn position() -> NodeHandle {
+        create_node(
+            "position",
+            "panda_1",
+            serde_json::json!({ "period": 0.1 }),
+            node::MessageType::DVector,
+        )
+        .unwrap()
+    }
+
+    fn queued(node: &mut NodeHandle) -> usize {
+        match node {
+            NodeHandle::DVector(node) => node.input_queue("in").len(),
+            _ => unreachable!(),
+        }
+    }
+
+    fn update(node: &mut NodeHandle) {
+        match node {
+            NodeHandle::DVector(node) => node.update(),
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn output_broadcast() {
+        let mut output = OutputQueue::default();
+        let queues = [Arc::default(), Arc::default()];
+        for queue in &queues {
+            output.connect(Arc::clone(queue));
+        }
+        output.push(DNodeMessage::Joint(na::dvector![1.0]));
+        assert!(queues.iter().all(|queue| queue.len() == 1));
+        assert!(output.pop().is_some());
+        assert!(queues.iter().all(|queue| queue.is_empty()));
+    }
+
+    #[test]
+    fn fan_in_fan_out() {
+        // 0 与 1 合并到 2，0 同时广播到 3
+        let mut nodes = vec![position(), position(), position(), position()];
+        connect(&mut nodes, (0, "out"), (2, "in")).unwrap();
+        connect(&mut nodes, (1, "out"), (2, "in")).unwrap();
+        connect(&mut nodes, (0, "out"), (3, "in")).unwrap();
+
+        let target = [DNodeMessage::Joint(na::dvector![0.0])];
+        nodes[0].push_targets("in", &target).unwrap();
+        nodes[1].push_targets("in", &target).unwrap();
+        update(&mut nodes[0]);
+        update(&mut nodes[1]);
+
+        assert_eq!(queued(&mut nodes[2]), 2);
+        assert_eq!(queued(&mut nodes[3]), 1);
+    }
+
+    #[test]
+    fn port_errors() {
+        let mut nodes = vec![position(), position()];
+        assert!(matches!(
+            connect(&mut nodes, (0, "track"), (1, "in")),
+            Err(NodeError::UnknownPort { .. })
+        ));
+
+        let ports = node::node_ports("impedence", node::MessageType::DVector).unwrap();
+        assert!(ports.input("force").unwrap().accepts(MessageKind::Tau));
+        assert!(!ports.input("force").unwrap().accepts(MessageKind::Joint));
+    }
+}
@@ -0,0 +1,132 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use message::Pose;
+    use nalgebra as na;
+    use robot::{AllowedCollisionMatrix, DRobot, Robot, RobotBranch};
+    use std::sync::{Arc, RwLock};
+
+    use crate::common::{numeric_grad, panda};
+
+    #[test]
+    fn allowed_collision_matrix() {
+        let acm = AllowedCollisionMatrix::adjacent(4, 1);
+        assert!(acm.is_allowed(0, 1) && acm.is_allowed(2, 1) && acm.is_allowed(3, 3));
+        assert!(!acm.is_allowed(0, 2));
+        assert_eq!(acm.checked_pairs(4), vec![(0, 2), (0, 3), (1, 3)]);
+        assert_eq!(
+            AllowedCollisionMatrix::adjacent(4, 2).checked_pairs(4),
+            vec![(0, 3)]
+        );
+
+        // 配置文件中的连杆对不区分先后
+        let mut acm: AllowedCollisionMatrix = serde_json::from_str("[[3, 0], [1, 3]]").unwrap();
+        assert_eq!(acm.checked_pairs(4), vec![(0, 1), (0, 2), (1, 2), (2, 3)]);
+        acm.disallow(0, 3);
+        acm.extend(&AllowedCollisionMatrix::adjacent(4, 1));
+        assert_eq!(acm.checked_pairs(4), vec![(0, 2), (0, 3)]);
+    }
+
+    #[test]
+    fn panda_self_collision() {
+        let robot = panda("panda_1", Pose::identity());
+
+        // 默认位姿下没有自碰撞，相邻连杆与腕部连杆不做检查
+        let distances = robot.cul_self_distances(&robot.q_default());
+        assert_eq!(distances.len(), 14);
+        assert!(
+            distances
+                .iter()
+                .all(|distance| distance.result.distance > 0.0)
+        );
+        assert!(distances.iter().all(|distance| {
+            distance.link_b > distance.link_a + 1 && (distance.link_a, distance.link_b) != (4, 6)
+        }));
+
+        // 前臂折回基座附近
+        let q = na::dvector![-0.7, 0.6, 1.5, -2.5, -0.4, 2.9, 0.3];
+        let distances = robot.cul_self_distances(&q);
+        assert!(
+            distances
+                .iter()
+                .any(|distance| distance.result.distance < 0.0)
+        );
+        for distance in distances.iter() {
+            let pair = |q: &na::DVector<f64>| {
+                robot
+                    .cul_self_distances(q)
+                    .into_iter()
+                    .find(|d| (d.link_a, d.link_b) == (distance.link_a, distance.link_b))
+                    .unwrap()
+                    .result
+                    .distance
+            };
+            let analytic = robot.cul_pair_distance_grad(&q, distance);
+            let numeric = numeric_grad(&q, pair);
+            assert!(
+                (&analytic - &numeric).amax() < 1e-5,
+                "({}, {}): {} vs {}",
+                distance.link_a,
+                distance.link_b,
+                analytic,
+                numeric
+            );
+        }
+    }
+
+    #[test]
+    fn branch_collision() {
+        let mut branch = RobotBranch::new();
+        branch.push(Arc::new(RwLock::new(panda("panda_1", Pose::identity()))));
+        branch.push(Arc::new(RwLock::new(panda(
+            "panda_2",
+            Pose::translation(0.0, 0.3, 0.0),
+        ))));
+
+        // 两台机器人各自 14 对连杆，机器人之间 49 对连杆
+        let q_default = branch.read_robot(0).read().unwrap().q_default();
+        let q = na::DVector::from_iterator(14, q_default.iter().chain(q_default.iter()).copied());
+        let distances = branch.cul_self_distances(&q);
+        assert_eq!(distances.len(), 77);
+        let base = distances
+            .iter()
+            .find(|distance| (distance.link_a, distance.link_b) == (0, 7))
+            .unwrap();
+        // 两台机器人的基座胶囊体相互重叠
+        assert!((base.result.distance + 0.14).abs() < 1e-9);
+
+        let mut acm = AllowedCollisionMatrix::new();
+        acm.allow(7, 0);
+        branch.set_acm(acm);
+        assert_eq!(branch.cul_self_distances(&q).len(), 76);
+
+        // 机器人之间与机器人自身的连杆对梯度
+        let q = na::dvector![
+            0.3, 0.2, 0.1, -2.0, 0.2, 1.5, 0.7, -0.4, 0.5, 0.2, -1.8, 0.1, 1.2, 0.3
+        ];
+        for (link_a, link_b) in [(3, 10), (6, 13), (2, 12), (1, 5), (8, 12)] {
+            let pair = |q: &na::DVector<f64>| {
+                branch
+                    .cul_self_distances(q)
+                    .into_iter()
+                    .find(|d| (d.link_a, d.link_b) == (link_a, link_b))
+                    .unwrap()
+            };
+            let analytic = branch.cul_pair_distance_grad(&q, &pair(&q));
+            let numeric = numeric_grad(&q, |q| pair(q).result.distance);
+            assert!(
+                (&analytic - &numeric).amax() < 1e-5,
+                "({}, {}): {} vs {}",
+                link_a,
+                link_b,
+                analytic,
+                numeric
+            );
+            // 同一机器人的连杆对与另一台机器人的关节角无关
+            if link_a < 7 && link_b < 7 {
+                assert!(analytic.rows(7, 7).iter().all(|g| *g == 0.0));
+            }
+        }
+    }
+}
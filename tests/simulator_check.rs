@@ -0,0 +1,136 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use message::{Collision, CollisionObject, DNodeMessage, Pose};
+    use nalgebra as na;
+    use node::{NodeBehavior, NodeExt, Simulator};
+    use robot::{DRobot, DSeriseRobot, Robot, RobotType};
+    use sensor::{ObstacleList, Sensor};
+    use std::sync::{Arc, RwLock};
+
+    fn simulator(robot: &Arc<RwLock<DSeriseRobot>>) -> Simulator {
+        simulator_with(robot, serde_json::json!({ "period": 0.001 }))
+    }
+
+    fn simulator_with(robot: &Arc<RwLock<DSeriseRobot>>, params: serde_json::Value) -> Simulator {
+        let mut simulator =
+            Simulator::from_params("simulator:panda_1".to_string(), params).unwrap();
+        simulator.set_robot(RobotType::DSeriseRobot(robot.clone()));
+        simulator.init();
+        simulator
+    }
+
+    fn panda() -> Arc<RwLock<DSeriseRobot>> {
+        Arc::new(RwLock::new(crate::common::panda(
+            "panda_1",
+            Pose::identity(),
+        )))
+    }
+
+    #[test]
+    fn servo_to_joint() {
+        let robot = panda();
+        let mut simulator = simulator(&robot);
+        let target = robot.read().unwrap().q().add_scalar(0.2);
+        simulator
+            .input_queue("in")
+            .push(DNodeMessage::Joint(target.clone()));
+        for _ in 0..3000 {
+            simulator.update();
+        }
+        let robot = robot.read().unwrap();
+        assert!((robot.q() - target).amax() < 1e-3);
+        assert!(robot.q_dot().amax() < 1e-2);
+    }
+
+    #[test]
+    fn wrong_dimension_command() {
+        let robot = panda();
+        let mut simulator = simulator(&robot);
+        let target = robot.read().unwrap().q().add_scalar(0.2);
+        simulator
+            .input_queue("in")
+            .push(DNodeMessage::Joint(target.clone()));
+        simulator.update();
+
+        // 维数不符的指令被丢弃，仿真器沿用之前的指令
+        simulator
+            .input_queue("in")
+            .push(DNodeMessage::Tau(na::DVector::zeros(6)));
+        for _ in 0..3000 {
+            simulator.update();
+        }
+        assert!((robot.read().unwrap().q() - target).amax() < 1e-3);
+    }
+
+    #[test]
+    fn rigid_body_dynamics() {
+        let robot = panda();
+        let mut simulator = simulator(&robot);
+        let q = robot.read().unwrap().q();
+        let zeros = na::DVector::zeros(7);
+
+        // 没有指令时补偿重力，机器人保持静止
+        for _ in 0..100 {
+            simulator.update();
+        }
+        assert!((robot.read().unwrap().q() - &q).amax() < 1e-9);
+
+        // 零力矩时机器人在重力作用下运动，关节加速度由正动力学给出
+        robot
+            .write()
+            .unwrap()
+            .set_control_message(DNodeMessage::Tau(zeros.clone()));
+        simulator.update();
+        let robot = robot.read().unwrap();
+        let q_ddot = robot.cul_forward_dynamics(&q, &zeros, &zeros);
+        assert!(q_ddot.amax() > 1.0);
+        assert!((robot.q_ddot() - q_ddot).amax() < 1e-9);
+    }
+
+    #[test]
+    fn joint_limits() {
+        let robot = panda();
+        // 解耦模型中单位惯量的关节加速度等于力矩
+        let params = serde_json::json!({ "period": 0.001, "model": "decoupled" });
+        let mut simulator = simulator_with(&robot, params);
+        // 远超力矩限制的指令
+        let tau = na::DVector::from_element(7, 1000.0);
+        robot
+            .write()
+            .unwrap()
+            .set_control_message(DNodeMessage::Tau(tau));
+
+        simulator.update();
+        let tau_bound = robot.read().unwrap().tau_bound();
+        assert_eq!(robot.read().unwrap().q_ddot(), tau_bound);
+
+        for _ in 0..5000 {
+            simulator.update();
+        }
+        let robot = robot.read().unwrap();
+        let q = robot.q();
+        assert_eq!(q, robot.q_max_bound());
+        assert_eq!(robot.q_dot(), na::DVector::zeros(7));
+    }
+
+    #[test]
+    fn contact_with_obstacle() {
+        let robot = panda();
+        let mut simulator = simulator(&robot);
+        // 障碍物放在第一个连杆的位置
+        let pose = robot.read().unwrap().capsules()[0].pose;
+        let obstacle = CollisionObject::Sphere(Collision::new(3, pose, 0.1));
+        let obstacle = serde_json::to_value(vec![obstacle]).unwrap();
+        let sensor = Sensor::ObstacleList(
+            ObstacleList::new("obstacle_list_1".to_string(), obstacle).unwrap(),
+        );
+        simulator.set_sensor(Arc::new(RwLock::new(sensor)));
+
+        simulator.update();
+        assert!(simulator.contacts().iter().any(|contact| {
+            contact.robot == "panda_1" && contact.link == 0 && contact.obstacle == 3
+        }));
+    }
+}
@@ -0,0 +1,368 @@
+#[cfg(test)]
+mod tests {
+    use message::{Constraint, NonlinearProgramming, Problem, QuadraticProgramming};
+    use osqp::CscMatrix;
+    use solver::{OsqpSolver, PanocSolver, SolveStatus, Solver, SolverError};
+
+    fn assert_close(x: &[f64], expected: &[f64], tolerance: f64) {
+        assert_eq!(x.len(), expected.len());
+        assert!(
+            x.iter()
+                .zip(expected)
+                .all(|(x, expected)| (x - expected).abs() < tolerance),
+            "{:?} != {:?}",
+            x,
+            expected
+        );
+    }
+
+    /// min |x - target|^2
+    fn distance_to(target: Vec<f64>, constraints: Constraint) -> NonlinearProgramming<'static> {
+        let grad_target = target.clone();
+        NonlinearProgramming {
+            dim: target.len(),
+            cost: Box::new(move |x| x.iter().zip(&target).map(|(x, t)| (x - t).powi(2)).sum()),
+            grad: Box::new(move |x, grad| {
+                for (grad, (x, t)) in grad.iter_mut().zip(x.iter().zip(&grad_target)) {
+                    *grad = 2.0 * (x - t);
+                }
+            }),
+            constraints,
+        }
+    }
+
+    fn panoc(problem: NonlinearProgramming) -> Vec<f64> {
+        let result = PanocSolver::from_problem(Problem::NonlinearProgramming(problem))
+            .unwrap()
+            .solve();
+        assert!(result.status.is_solved(), "{}", result);
+        result.x
+    }
+
+    #[test]
+    fn solver_registry() {
+        assert!(solver::solver_types().contains(&"osqp"));
+        assert!(solver::solver_types().contains(&"panoc"));
+
+        let problem = distance_to(vec![0.0], Constraint::NoConstraint);
+        assert!(matches!(
+            solver::factory("ipopt", Problem::NonlinearProgramming(problem)),
+            Err(SolverError::UnknownType(_))
+        ));
+        let problem = distance_to(vec![0.0], Constraint::NoConstraint);
+        assert!(matches!(
+            solver::factory("osqp", Problem::NonlinearProgramming(problem)),
+            Err(SolverError::UnsupportedProblem { solver: "osqp", .. })
+        ));
+        let problem = distance_to(vec![3.0, 4.0], Constraint::EpigraphSquaredNorm(2, 1.0));
+        let mut solver = solver::factory("panoc", Problem::NonlinearProgramming(problem)).unwrap();
+        assert_close(&solver.solve().x, &[0.6, 0.8], 1e-4);
+    }
+
+    #[test]
+    fn panoc_quadratic_programming() {
+        // min 0.5 |x|^2 - 2 x0 - 2 x1，盒约束与半空间约束的交集上最优解为 (1, 1)
+        let h = CscMatrix::from(&[[1.0, 0.0], [0.0, 1.0]]);
+        let f = [-2.0, -2.0];
+        let mut constraints = Constraint::Rectangle(vec![-1.0, -1.0], vec![3.0, 3.0]);
+        constraints += Constraint::Halfspace(vec![1.0, 1.0], 2.0);
+        let problem = QuadraticProgramming {
+            h: &h,
+            f: &f,
+            constraints,
+        };
+        let mut solver = PanocSolver::from_problem(Problem::QuadraticProgramming(problem)).unwrap();
+        assert_close(&solver.solve().x, &[1.0, 1.0], 1e-3);
+
+        // 更新约束后重新求解
+        solver
+            .update_constraints(Constraint::Rectangle(vec![-1.0, -1.0], vec![0.5, 3.0]))
+            .unwrap();
+        assert_close(&solver.solve().x, &[0.5, 2.0], 1e-4);
+    }
+
+    #[test]
+    fn panoc_set_constraints() {
+        assert_close(
+            &panoc(distance_to(
+                vec![1.0, 2.0, 3.0],
+                Constraint::Simplex(3, 1.0),
+            )),
+            &[0.0, 0.0, 1.0],
+            1e-4,
+        );
+        assert_close(
+            &panoc(distance_to(
+                vec![0.9, 0.2],
+                Constraint::FiniteSet(vec![vec![0.0, 0.0], vec![1.0, 0.0], vec![0.0, 1.0]]),
+            )),
+            &[1.0, 0.0],
+            1e-9,
+        );
+        // 二阶锥 |x0| <= x1 外的点投影到锥面上
+        assert_close(
+            &panoc(distance_to(
+                vec![2.0, 0.0],
+                Constraint::SecondOrderCone(2, 1.0),
+            )),
+            &[1.0, 1.0],
+            1e-4,
+        );
+
+        // 各变量块上的集合组成笛卡尔积
+        let mut constraints = Constraint::CartesianProduct(0, 0, Vec::new());
+        constraints.push(Constraint::Equared(vec![0.5]));
+        constraints.push(Constraint::EpigraphSquaredNorm(2, 1.0));
+        constraints.push(Constraint::Simplex(2, 1.0));
+        assert_close(
+            &panoc(distance_to(vec![2.0, 3.0, 4.0, 1.0, 0.0], constraints)),
+            &[0.5, 0.6, 0.8, 1.0, 0.0],
+            1e-4,
+        );
+    }
+
+    #[test]
+    fn panoc_linear_penalty() {
+        // 两行的超平面约束无法直接投影，由罚函数处理：x0 + x1 = 1，x0 - x1 = 0.5
+        let mut constraints = Constraint::Rectangle(vec![-2.0, -2.0], vec![2.0, 2.0]);
+        constraints += Constraint::Hyperplane(2, 2, vec![1.0, 1.0, 1.0, -1.0], vec![1.0, 0.5]);
+        assert_close(
+            &panoc(distance_to(vec![0.0, 0.0], constraints)),
+            &[0.75, 0.25],
+            1e-3,
+        );
+
+        // 与单位圆的交集，投影到圆上，半空间 x0 + x1 <= -1 由罚函数处理
+        let mut constraints = Constraint::EpigraphSquaredNorm(2, 1.0);
+        constraints += Constraint::Halfspace(vec![1.0, 1.0], -1.0);
+        let x = panoc(distance_to(vec![1.0, 0.0], constraints));
+        assert!(x[0] * x[0] + x[1] * x[1] <= 1.0 + 1e-9);
+        assert!(x[0] + x[1] <= -1.0 + 1e-3);
+        assert_close(&x, &[0.0, -1.0], 1e-3);
+    }
+
+    #[test]
+    fn panoc_unsupported_constraint() {
+        let constraints = Constraint::Union(1, 1, vec![Constraint::Zero, Constraint::Zero]);
+        let problem = distance_to(vec![0.0], constraints);
+        assert!(matches!(
+            PanocSolver::from_problem(Problem::NonlinearProgramming(problem)),
+            Err(SolverError::UnsupportedConstraint {
+                solver: "panoc",
+                constraint: "union",
+            })
+        ));
+
+        // 交集中只能有一个非线性的集合
+        let mut constraints = Constraint::EpigraphSquaredNorm(2, 1.0);
+        constraints += Constraint::Simplex(2, 1.0);
+        let problem = distance_to(vec![0.0, 0.0], constraints);
+        assert!(PanocSolver::from_problem(Problem::NonlinearProgramming(problem)).is_err());
+    }
+
+    #[test]
+    fn osqp_unsupported_constraint() {
+        let h = CscMatrix::from(&[[1.0, 0.0], [0.0, 1.0]]);
+        let f = [0.0, 0.0];
+        let quadratic = |constraints| {
+            Problem::QuadraticProgramming(QuadraticProgramming {
+                h: &h,
+                f: &f,
+                constraints,
+            })
+        };
+
+        // 笛卡尔积中的非线性约束同样无法转换为线性约束
+        let mut constraints = Constraint::CartesianProduct(0, 0, vec![]);
+        constraints.push(Constraint::Rectangle(vec![-1.0], vec![1.0]));
+        constraints.push(Constraint::SecondOrderCone(1, 1.0));
+        for (constraints, constraint) in [
+            (Constraint::Simplex(2, 1.0), "simplex"),
+            (
+                Constraint::EpigraphSquaredNorm(2, 1.0),
+                "epigraph squared norm",
+            ),
+            (Constraint::FiniteSet(vec![]), "finite set"),
+            (constraints, "second order cone"),
+        ] {
+            assert_eq!(
+                solver::factory("osqp", quadratic(constraints)).err(),
+                Some(SolverError::UnsupportedConstraint {
+                    solver: "osqp",
+                    constraint,
+                })
+            );
+        }
+        assert_eq!(Constraint::FiniteSet(vec![]).nrows(), 0);
+    }
+
+    #[test]
+    fn osqp_setup_error() {
+        let h = CscMatrix::from(&[[1.0, 0.0], [0.0, 1.0]]);
+        let f = [0.0, 0.0];
+        let problem = Problem::QuadraticProgramming(QuadraticProgramming {
+            h: &h,
+            f: &f,
+            constraints: Constraint::Halfspace(vec![1.0, 1.0], 2.0),
+        });
+        let mut solver = solver::factory("osqp", problem).unwrap();
+
+        // 下界大于上界的约束无法建立问题，工厂函数返回错误而不会 panic
+        let rectangle = Constraint::Rectangle(vec![1.0, 1.0], vec![0.0, 0.0]);
+        let problem = Problem::QuadraticProgramming(QuadraticProgramming {
+            h: &h,
+            f: &f,
+            constraints: rectangle.clone(),
+        });
+        assert!(matches!(
+            solver::factory("osqp", problem).err(),
+            Some(SolverError::Setup { solver: "osqp", .. })
+        ));
+
+        // 非零元位置改变时重新建立问题，同样返回错误
+        let error = solver.update_constraints(rectangle).unwrap_err();
+        assert!(matches!(error, SolverError::Setup { solver: "osqp", .. }));
+    }
+
+    #[test]
+    fn solve_result() {
+        // min 0.5 |x|^2 - 2 x0 - 2 x1，x0 + x1 <= 2，最优解为 (1, 1)，目标函数值为 -3
+        let h = CscMatrix::from(&[[1.0, 0.0], [0.0, 1.0]]);
+        let f = [-2.0, -2.0];
+        let quadratic = |constraints| QuadraticProgramming {
+            h: &h,
+            f: &f,
+            constraints,
+        };
+        let mut constraints = Constraint::Rectangle(vec![-1.0, -1.0], vec![3.0, 3.0]);
+        constraints += Constraint::Halfspace(vec![1.0, 1.0], 2.0);
+
+        let result = OsqpSolver::from_problem(quadratic(constraints.clone()))
+            .unwrap()
+            .solve();
+        assert_eq!(result.status, SolveStatus::Solved);
+        assert_close(&result.x, &[1.0, 1.0], 1e-2);
+        assert_eq!(result.y.len(), 3);
+        assert!((result.objective + 3.0).abs() < 1e-2);
+        assert!(result.iterations > 0);
+        assert!(result.primal_residual < 1e-2 && result.dual_residual < 1e-2);
+
+        let result =
+            PanocSolver::from_problem(Problem::QuadraticProgramming(quadratic(constraints)))
+                .unwrap()
+                .solve();
+        assert_eq!(result.status, SolveStatus::Solved);
+        assert!((result.objective + 3.0).abs() < 1e-3);
+        assert!(result.primal_residual < 1e-4);
+
+        // x0 >= 1，x1 >= 1 与 x0 + x1 <= 1 相互矛盾
+        let mut constraints = Constraint::Rectangle(vec![1.0, 1.0], vec![2.0, 2.0]);
+        constraints += Constraint::Halfspace(vec![1.0, 1.0], 1.0);
+        let result = OsqpSolver::from_problem(quadratic(constraints.clone()))
+            .unwrap()
+            .solve();
+        assert_eq!(result.status, SolveStatus::PrimalInfeasible);
+        assert!(!result.status.is_solved());
+        assert!(result.x.is_empty() && result.objective.is_nan());
+
+        let result =
+            PanocSolver::from_problem(Problem::QuadraticProgramming(quadratic(constraints)))
+                .unwrap()
+                .solve();
+        assert_eq!(result.status, SolveStatus::PrimalInfeasible);
+        assert!(result.primal_residual > 0.1);
+    }
+
+    #[test]
+    fn incremental_updates() {
+        // min 0.5 |x|^2 + f'x，-1 <= x <= 3，x0 + x1 <= 2
+        let h = CscMatrix::from(&[[1.0, 0.0], [0.0, 1.0]]);
+        let f = [-2.0, -2.0];
+        let mut constraints = Constraint::Rectangle(vec![-1.0, -1.0], vec![3.0, 3.0]);
+        constraints += Constraint::Halfspace(vec![1.0, 1.0], 2.0);
+
+        // 约束的上下界按 to_cscmatrix 的行顺序修改
+        let (l, u) = ([-1.0, -1.0, f64::NEG_INFINITY], [3.0, 3.0, 1.0]);
+        let mut tightened = constraints.clone();
+        tightened.set_bounds(&l, &u);
+        let (_, _, _, l_new, u_new) = tightened.to_cscmatrix();
+        assert_eq!((l_new.as_slice(), u_new.as_slice()), (&l[..], &u[..]));
+
+        let problem = |constraints| {
+            Problem::QuadraticProgramming(QuadraticProgramming {
+                h: &h,
+                f: &f,
+                constraints,
+            })
+        };
+        let solvers: Vec<(Box<dyn Solver + '_>, f64)> = vec![
+            (
+                solver::factory("osqp", problem(constraints.clone())).unwrap(),
+                1e-2,
+            ),
+            (
+                solver::factory("panoc", problem(constraints.clone())).unwrap(),
+                1e-3,
+            ),
+        ];
+        for (mut solver, tolerance) in solvers {
+            let cold = solver.solve();
+            assert_close(&cold.x, &[1.0, 1.0], tolerance);
+
+            // 更新一次项，最优解为 (3, -1)
+            solver.update_linear_cost(&[-4.0, 0.0]).unwrap();
+            assert_close(&solver.solve().x, &[3.0, -1.0], tolerance);
+
+            // 收紧半空间约束，最优解为 (2, -1)
+            solver.update_bounds(&l, &u).unwrap();
+            assert_close(&solver.solve().x, &[2.0, -1.0], tolerance);
+
+            // 非零元位置不变的二次项，最优解为 (1.5, -0.5)
+            solver
+                .update_quadratic_cost(&CscMatrix::from(&[[2.0, 0.0], [0.0, 2.0]]))
+                .unwrap();
+            assert_close(&solver.solve().x, &[1.5, -0.5], tolerance);
+
+            // 非零元位置改变的二次项，最优解为 (2, -1)
+            solver
+                .update_quadratic_cost(&CscMatrix::from(&[[2.0, 1.0], [1.0, 2.0]]))
+                .unwrap();
+            assert_close(&solver.solve().x, &[2.0, -1.0], tolerance);
+
+            // 维数不符的更新返回错误，不影响已有的问题
+            assert!(matches!(
+                solver.update_bounds(&l[..2], &u[..2]),
+                Err(SolverError::DimensionMismatch {
+                    expected: 3,
+                    found: 2,
+                    ..
+                })
+            ));
+            assert!(matches!(
+                solver.update_linear_cost(&[0.0]),
+                Err(SolverError::DimensionMismatch { .. })
+            ));
+            assert!(matches!(
+                solver.warm_start(&[0.0; 3], &[]),
+                Err(SolverError::DimensionMismatch { .. })
+            ));
+            assert!(matches!(
+                solver.update_constraints(Constraint::Rectangle(vec![-1.0; 3], vec![1.0; 3])),
+                Err(SolverError::DimensionMismatch { .. })
+            ));
+            assert_close(&solver.solve().x, &[2.0, -1.0], tolerance);
+        }
+
+        // 以最优解为初值时迭代次数不多于冷启动
+        for solver_type in ["osqp", "panoc"] {
+            let cold = solver::factory(solver_type, problem(constraints.clone()))
+                .unwrap()
+                .solve();
+            let mut solver = solver::factory(solver_type, problem(constraints.clone())).unwrap();
+            solver.warm_start(&cold.x, &cold.y).unwrap();
+            let warm = solver.solve();
+            assert!(warm.iterations <= cold.iterations);
+            assert_close(&warm.x, &cold.x, 1e-3);
+        }
+    }
+}
@@ -0,0 +1,250 @@
+#[cfg(test)]
+mod tests {
+    use std::{sync::mpsc, time::Duration};
+
+    use manager::{NodeConfig, Task, TaskEvent, TaskManager, ThreadManager};
+    use message::TaskState;
+    use node::{NodeBehavior, NodeState};
+
+    fn task(id: usize, rely: Vec<usize>, nodes: &[&str]) -> Task {
+        Task {
+            id,
+            rely,
+            nodes: nodes
+                .iter()
+                .map(|node| NodeConfig {
+                    node_type: node.to_string(),
+                    ..Default::default()
+                })
+                .collect(),
+            edges: vec![(0.into(), 1.into())],
+            ..Default::default()
+        }
+    }
+
+    fn open_ids(task_manager: &mut TaskManager) -> Vec<usize> {
+        let mut ids: Vec<usize> = task_manager
+            .get_open_tasks()
+            .iter()
+            .map(|task| task.id)
+            .collect();
+        ids.sort_unstable();
+        ids
+    }
+
+    #[test]
+    fn independent_chains() {
+        // 两条互不相关的任务链 0 -> 2 与 1 -> 3
+        let mut task_manager = TaskManager::default();
+        task_manager.add_task(task(0, vec![], &["planner_a", "controller_a"]));
+        task_manager.add_task(task(1, vec![], &["planner_b"]));
+        task_manager.add_task(task(2, vec![0], &["planner_c"]));
+        task_manager.add_task(task(3, vec![1], &["planner_d"]));
+
+        assert_eq!(open_ids(&mut task_manager), vec![0, 1]);
+        task_manager.register_node(0, "planner_a".to_string(), true);
+        task_manager.register_node(0, "controller_a".to_string(), false);
+        task_manager.register_node(1, "planner_b".to_string(), true);
+
+        // 非标志节点结束不会完成任务
+        let state = TaskState::NodeEnd("controller_a".to_string());
+        assert_eq!(task_manager.handle_task_state(state), None);
+
+        // 任务 1 完成后只解锁任务 3
+        let state = TaskState::RelyRelease("planner_b".to_string());
+        assert_eq!(
+            task_manager.handle_task_state(state),
+            Some(TaskEvent::Completed(1))
+        );
+        assert_eq!(open_ids(&mut task_manager), vec![3]);
+
+        // 重复的汇报不会重复完成任务
+        let state = TaskState::RelyRelease("planner_b".to_string());
+        assert_eq!(task_manager.handle_task_state(state), None);
+
+        let state = TaskState::NodeEnd("planner_a".to_string());
+        assert_eq!(
+            task_manager.handle_task_state(state),
+            Some(TaskEvent::Completed(0))
+        );
+        assert_eq!(open_ids(&mut task_manager), vec![2]);
+        assert!(!task_manager.is_all_finished());
+    }
+
+    #[test]
+    fn robot_leases() {
+        // 任务 0 与任务 1 都需要 panda_1 的指令权，任务 2 只读取 panda_1 的状态
+        let leased_task = |id: usize, readonly: bool| {
+            let mut task = task(id, vec![], &["node"]);
+            task.nodes[0].node_type = format!("node_{}", id);
+            task.nodes[0].robots = vec!["panda_1".to_string()];
+            task.nodes[0].readonly = readonly;
+            task
+        };
+        let mut task_manager = TaskManager::default();
+        task_manager.add_task(leased_task(0, false));
+        task_manager.add_task(leased_task(1, false));
+        task_manager.add_task(leased_task(2, true));
+
+        assert_eq!(open_ids(&mut task_manager), vec![0, 2]);
+        assert_eq!(task_manager.lease_holder("panda_1"), Some(0));
+
+        // 仿真器写入机器人状态但不需要租约，不会与任务 0 冲突
+        let mut simulator = leased_task(3, false);
+        simulator.nodes[0].node_type = "simulator".to_string();
+        assert!(simulator.leased_robots().is_empty());
+        task_manager.add_task(simulator);
+        assert_eq!(open_ids(&mut task_manager), vec![3]);
+
+        // 任务 0 完成后释放租约，排队的任务 1 获得指令权
+        task_manager.register_node(0, "node_0".to_string(), true);
+        let state = TaskState::NodeEnd("node_0".to_string());
+        assert_eq!(
+            task_manager.handle_task_state(state),
+            Some(TaskEvent::Completed(0))
+        );
+        assert_eq!(open_ids(&mut task_manager), vec![1]);
+        assert_eq!(task_manager.lease_holder("panda_1"), Some(1));
+    }
+
+    #[test]
+    fn lease_outlives_task() {
+        // 任务 0 的规划器标志任务完成，控制器在任务完成后仍在向 panda_1 下发指令
+        let mut first = task(0, vec![], &["planner", "controller"]);
+        let mut second = task(1, vec![], &["planner"]);
+        for node in first.nodes.iter_mut().chain(second.nodes.iter_mut()) {
+            node.robots = vec!["panda_1".to_string()];
+        }
+        let mut task_manager = TaskManager::default();
+        task_manager.add_task(first);
+        task_manager.add_task(second);
+        assert_eq!(open_ids(&mut task_manager), vec![0]);
+        task_manager.register_node(0, "planner".to_string(), true);
+        task_manager.register_node(0, "controller".to_string(), false);
+
+        // 任务完成但控制器仍在运行，租约不会释放
+        let state = TaskState::NodeEnd("planner".to_string());
+        assert_eq!(
+            task_manager.handle_task_state(state),
+            Some(TaskEvent::Completed(0))
+        );
+        assert_eq!(open_ids(&mut task_manager), Vec::<usize>::new());
+        assert_eq!(task_manager.lease_holder("panda_1"), Some(0));
+
+        // 任务 1 排队等待 panda_1，仍在运行的控制器需要让出指令权
+        assert_eq!(
+            task_manager.preempted_nodes(),
+            vec!["controller".to_string()]
+        );
+
+        // 控制器停止后释放租约
+        let state = TaskState::NodeStop("controller".to_string());
+        assert_eq!(
+            task_manager.handle_task_state(state),
+            Some(TaskEvent::LeaseReleased(0))
+        );
+        assert_eq!(open_ids(&mut task_manager), vec![1]);
+        assert_eq!(task_manager.lease_holder("panda_1"), Some(1));
+    }
+
+    /// 执行 updates 次后结束的节点，updates 为 None 时永不结束
+    struct CountNode {
+        name: String,
+        updates: Option<usize>,
+    }
+
+    impl NodeBehavior for CountNode {
+        fn update(&mut self) {
+            if let Some(updates) = &mut self.updates {
+                *updates = updates.saturating_sub(1);
+            }
+        }
+        fn state(&mut self) -> NodeState {
+            match self.updates {
+                Some(0) => NodeState::Finished,
+                _ => NodeState::Running,
+            }
+        }
+        fn period(&self) -> Duration {
+            Duration::from_millis(1)
+        }
+        fn node_name(&self) -> String {
+            self.name.clone()
+        }
+    }
+
+    #[test]
+    fn preempt_never_ending_node() {
+        // 任务 0 的控制器永不结束，任务 1 需要同一个机器人，按照实验状态机的流程在线程中执行
+        let mut first = task(0, vec![], &["planner", "controller"]);
+        let mut second = task(1, vec![], &["planner"]);
+        for node in first.nodes.iter_mut().chain(second.nodes.iter_mut()) {
+            node.robots = vec!["panda_1".to_string()];
+        }
+        let mut task_manager = TaskManager::default();
+        task_manager.add_task(first);
+        task_manager.add_task(second);
+        let (sender, receiver) = mpsc::channel();
+        let mut thread_manager = ThreadManager::new(sender);
+
+        let spawn = |thread_manager: &mut ThreadManager, name: &str, updates| {
+            thread_manager.add_node(Box::new(CountNode {
+                name: name.to_string(),
+                updates,
+            }));
+        };
+        let mut started = Vec::new();
+        let mut sort = |task_manager: &mut TaskManager, thread_manager: &mut ThreadManager| {
+            for name in task_manager.preempted_nodes() {
+                thread_manager.stop_node(&name);
+            }
+            for task in task_manager.get_open_tasks() {
+                started.push(task.id);
+                let names = [format!("planner_{}", task.id), "controller".to_string()];
+                let updates = [Some(3), None];
+                for (name, updates) in names.iter().zip(updates).take(task.nodes.len()) {
+                    task_manager.register_node(task.id, name.clone(), name.starts_with("planner"));
+                    spawn(thread_manager, name, updates);
+                }
+            }
+        };
+
+        sort(&mut task_manager, &mut thread_manager);
+        let mut events = Vec::new();
+        while !task_manager.is_all_finished() {
+            let task_state = receiver.recv_timeout(Duration::from_secs(1)).unwrap();
+            if let Some(event) = task_manager.handle_task_state(task_state) {
+                events.push(event);
+                sort(&mut task_manager, &mut thread_manager);
+            }
+        }
+
+        assert_eq!(started, vec![0, 1]);
+        assert_eq!(
+            events,
+            vec![
+                TaskEvent::Completed(0),
+                TaskEvent::LeaseReleased(0),
+                TaskEvent::Completed(1),
+            ]
+        );
+        thread_manager.stop_all();
+        assert!(thread_manager.join_all(Duration::from_secs(1)).is_clean());
+    }
+
+    #[test]
+    fn node_labels() {
+        // 同一机器人上先后执行的两个任务使用同类型的节点，节点名称互不相同
+        let robot_task = |id: usize| {
+            let mut task = task(id, vec![], &["cfs", "cfs"]);
+            for node in task.nodes.iter_mut() {
+                node.robots = vec!["panda_1".to_string()];
+            }
+            task
+        };
+        let (first, second) = (robot_task(0), robot_task(1));
+        assert_eq!(first.node_label(0), "panda_1@0.1");
+        assert_ne!(first.node_label(0), first.node_label(1));
+        assert_ne!(first.node_label(0), second.node_label(0));
+    }
+}
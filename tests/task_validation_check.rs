@@ -0,0 +1,203 @@
+#[cfg(test)]
+mod tests {
+    use manager::{Config, NodeConfig, Task, TaskError, validate_tasks};
+    use message::{DNodeMessage, MessageKind};
+    use nalgebra as na;
+    use node::MessageType;
+
+    fn config() -> Config {
+        serde_json::from_str(
+            r#"{
+                "robots": [{ "name": "panda_1", "robot_type": "panda", "base_pose": { "rotation": [1.0, 0.0, 0.0, 0.0], "translation": [0.0, 0.0, 0.0] } }],
+                "sensors": []
+            }"#,
+        )
+        .unwrap()
+    }
+
+    fn task(id: usize, rely: Vec<usize>, robots: &[&str]) -> Task {
+        Task {
+            id,
+            rely,
+            nodes: vec![NodeConfig {
+                node_type: "interp".to_string(),
+                robots: robots.iter().map(|robot| robot.to_string()).collect(),
+                ..Default::default()
+            }],
+            edges: vec![(0.into(), 1.into()), (1.into(), 0.into())],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn valid_tasks() {
+        let tasks = vec![
+            task(0, vec![], &["panda_1"]),
+            task(1, vec![0], &["panda_1"]),
+        ];
+        assert_eq!(validate_tasks(&tasks, &config()), Ok(()));
+    }
+
+    #[test]
+    fn invalid_tasks() {
+        let mut edge_task = task(3, vec![], &["panda_2"]);
+        edge_task.edges.push((1.into(), 2.into()));
+        let tasks = vec![
+            task(0, vec![1], &[]),
+            task(1, vec![0], &[]),
+            task(2, vec![4], &[]),
+            task(2, vec![], &[]),
+            edge_task,
+        ];
+
+        let errors = validate_tasks(&tasks, &config()).unwrap_err();
+        assert!(errors.contains(&TaskError::DuplicateId(2)));
+        assert!(errors.contains(&TaskError::UnknownRely { task: 2, rely: 4 }));
+        assert!(errors.contains(&TaskError::Cycle(vec![0, 1, 0])));
+        assert!(errors.contains(&TaskError::EdgeOutOfRange {
+            task: 3,
+            edge: (1, 2),
+            nodes: 1,
+        }));
+        assert!(errors.contains(&TaskError::UnknownRobot {
+            task: 3,
+            node: 1,
+            robot: "panda_2".to_string(),
+        }));
+    }
+
+    #[test]
+    fn named_edges() {
+        let mut task = task(0, vec![], &["panda_1"]);
+        task.nodes[0].id = Some("interp".to_string());
+        task.nodes.push(NodeConfig {
+            node_type: "pid".to_string(),
+            id: Some("pid".to_string()),
+            ..Default::default()
+        });
+        task.edges = vec![
+            ("$target".into(), "interp".into()),
+            ("interp".into(), "pid".into()),
+            ("pid".into(), "$end".into()),
+        ];
+        assert_eq!(task.resolve_edges(), Ok(vec![(0, 1), (1, 2), (2, 0)]));
+        assert_eq!(task.finish_nodes(), vec![0]);
+        task.finish = vec!["$end".into()];
+        assert_eq!(task.finish_nodes(), vec![1]);
+        assert_eq!(validate_tasks(&[task.clone()], &config()), Ok(()));
+
+        // 任务目标只能作为边的起点，系统末端只能作为边的终点或出现在 finish 中
+        let mut misplaced = task.clone();
+        misplaced.edges = vec![("$end".into(), 1.into()), (1.into(), "$target".into())];
+        misplaced.finish = vec!["$target".into()];
+        let misplaced_node = |node: &str| TaskError::MisplacedNode {
+            task: 0,
+            node: node.to_string(),
+        };
+        assert_eq!(
+            validate_tasks(&[misplaced], &config()),
+            Err(vec![
+                misplaced_node("$end"),
+                misplaced_node("$target"),
+                misplaced_node("$target"),
+            ])
+        );
+        task.finish = vec![];
+
+        // 任务目标直接连接到系统末端的边不经过任何节点，序号与名称两种写法都会被拒绝
+        for edge in [(0.into(), 0.into()), ("$target".into(), "$end".into())] {
+            let mut direct = task.clone();
+            direct.edges.push(edge);
+            assert_eq!(
+                validate_tasks(&[direct], &config()),
+                Err(vec![TaskError::EdgeOutOfRange {
+                    task: 0,
+                    edge: (0, 0),
+                    nodes: 2,
+                }])
+            );
+        }
+
+        // 未知的节点名称与重复的节点 id
+        task.edges.push(("interp".into(), "planner".into()));
+        task.nodes[1].id = Some("interp".to_string());
+        let errors = validate_tasks(&[task], &config()).unwrap_err();
+        assert!(errors.contains(&TaskError::UnknownNode {
+            task: 0,
+            node: "planner".to_string(),
+        }));
+        assert!(errors.contains(&TaskError::DuplicateNodeId {
+            task: 0,
+            id: "interp".to_string(),
+        }));
+    }
+
+    #[test]
+    fn message_mismatch() {
+        let mut task = task(0, vec![], &["panda_1"]);
+        task.nodes.push(NodeConfig {
+            node_type: "pid".to_string(),
+            message: MessageType::SVector7,
+            ..Default::default()
+        });
+        task.edges.push((1.into(), 2.into()));
+        let errors = validate_tasks(&[task], &config()).unwrap_err();
+        assert_eq!(
+            errors,
+            vec![TaskError::MessageMismatch {
+                task: 0,
+                edge: (1, 2),
+                from: MessageType::DVector,
+                to: MessageType::SVector7,
+            }]
+        );
+    }
+
+    #[test]
+    fn port_edges() {
+        let mut task = task(0, vec![], &["panda_1"]);
+        task.nodes[0].id = Some("interp".to_string());
+        task.nodes.push(NodeConfig {
+            node_type: "impedence".to_string(),
+            id: Some("impedence".to_string()),
+            ..Default::default()
+        });
+        task.edges = vec![
+            ("$target".into(), "interp.in".into()),
+            ("interp.out".into(), "impedence".into()),
+        ];
+        task.target = vec![DNodeMessage::Joint(na::dvector![0.0])];
+        assert_eq!(validate_tasks(&[task.clone()], &config()), Ok(()));
+        assert_eq!(task.edge_ports(&task.edges[1]), ("out", "in"));
+
+        // 不存在的端口、消息种类不相交的端口以及不被端口接受的任务目标
+        task.edges = vec![
+            ("$target".into(), "impedence.force".into()),
+            ("interp.track".into(), "2.in".into()),
+            ("1.out".into(), "impedence.force".into()),
+        ];
+        let errors = validate_tasks(&[task], &config()).unwrap_err();
+        assert_eq!(
+            errors,
+            vec![
+                TaskError::TargetMismatch {
+                    task: 0,
+                    node: 2,
+                    port: "force".to_string(),
+                    kind: MessageKind::Joint,
+                },
+                TaskError::UnknownPort {
+                    task: 0,
+                    node: 1,
+                    port: "track".to_string(),
+                },
+                TaskError::PortMismatch {
+                    task: 0,
+                    edge: (1, 2),
+                    from_port: "out".to_string(),
+                    to_port: "force".to_string(),
+                },
+            ]
+        );
+    }
+}
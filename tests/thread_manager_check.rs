@@ -0,0 +1,113 @@
+#[cfg(test)]
+mod tests {
+    use std::{sync::mpsc, time::Duration};
+
+    use manager::{Schedule, ThreadManager};
+    use node::{NodeBehavior, NodeState};
+
+    struct SpinNode {
+        name: String,
+        panic: bool,
+    }
+
+    /// 每隔一个周期执行一次超出周期的 update
+    struct OverrunNode {
+        cycle: usize,
+    }
+
+    impl NodeBehavior for OverrunNode {
+        fn update(&mut self) {
+            self.cycle += 1;
+            if self.cycle.is_multiple_of(2) {
+                std::thread::sleep(Duration::from_millis(8));
+            }
+        }
+        fn period(&self) -> Duration {
+            Duration::from_millis(5)
+        }
+        fn node_name(&self) -> String {
+            "overrun".to_string()
+        }
+    }
+
+    impl NodeBehavior for SpinNode {
+        fn update(&mut self) {
+            if self.panic {
+                panic!("{} panicked", self.name);
+            }
+        }
+        fn state(&mut self) -> NodeState {
+            NodeState::Running
+        }
+        fn period(&self) -> Duration {
+            Duration::from_millis(1)
+        }
+        fn node_name(&self) -> String {
+            self.name.clone()
+        }
+    }
+
+    #[test]
+    fn stop_and_join_all() {
+        let (sender, _receiver) = mpsc::channel();
+        let mut thread_manager = ThreadManager::new(sender);
+        thread_manager.add_node(Box::new(SpinNode {
+            name: "spin".to_string(),
+            panic: false,
+        }));
+        thread_manager.add_node(Box::new(SpinNode {
+            name: "panic".to_string(),
+            panic: true,
+        }));
+
+        std::thread::sleep(Duration::from_millis(20));
+        thread_manager.stop_all();
+        let report = thread_manager.join_all(Duration::from_secs(1));
+
+        assert_eq!(report.finished, vec!["spin".to_string()]);
+        assert_eq!(report.panicked, vec!["panic".to_string()]);
+        assert!(report.timeout.is_empty());
+        assert!(!report.is_clean());
+    }
+
+    #[test]
+    fn deadline_metrics() {
+        let (sender, _receiver) = mpsc::channel();
+        let mut thread_manager = ThreadManager::new(sender);
+        thread_manager.add_node(Box::new(OverrunNode { cycle: 0 }));
+
+        std::thread::sleep(Duration::from_millis(100));
+        thread_manager.stop_all();
+        assert!(thread_manager.join_all(Duration::from_secs(1)).is_clean());
+
+        let metrics = thread_manager.metrics();
+        assert_eq!(metrics.len(), 1);
+        let (name, metrics) = &metrics[0];
+        assert_eq!(name, "overrun");
+        assert!(metrics.cycles > 2);
+        assert!(metrics.overruns > 0);
+        assert!(metrics.skipped >= metrics.overruns);
+        assert!(metrics.wcet >= Duration::from_millis(8));
+        assert_eq!(metrics.jitter_histogram.iter().sum::<u64>(), metrics.cycles);
+    }
+
+    #[test]
+    fn schedule_from_params() {
+        let params = serde_json::json!({ "period": 0.001, "priority": 80, "cpu_affinity": [2, 3] });
+        let schedule = Schedule::from_params(&params).unwrap();
+        assert_eq!(schedule.priority, Some(80));
+        assert_eq!(schedule.cpu_affinity, vec![2, 3]);
+
+        assert!(
+            Schedule::from_params(&serde_json::json!({ "period": 0.001 }))
+                .unwrap()
+                .is_default()
+        );
+        assert!(
+            Schedule::from_params(&serde_json::Value::Null)
+                .unwrap()
+                .is_default()
+        );
+        assert!(Schedule::from_params(&serde_json::json!({ "priority": "high" })).is_err());
+    }
+}
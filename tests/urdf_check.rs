@@ -0,0 +1,149 @@
+#[cfg(test)]
+mod tests {
+    use message::Pose;
+    use nalgebra as na;
+    use robot::{
+        DPanda, DRobot, DSeriseRobot, Robot, RobotConfig, RobotError, RobotType, from_config,
+    };
+
+    const L1: f64 = 1.0;
+    const LC1: f64 = 0.5;
+    const LC2: f64 = 0.4;
+    const M1: f64 = 2.0;
+    const M2: f64 = 1.5;
+    const I1: f64 = 0.1;
+    const I2: f64 = 0.05;
+
+    /// 绕 y 轴转动的平面二连杆，关节轴与基座 z 轴不重合，连杆使用圆柱体作为碰撞几何体
+    const TWO_LINK: &str = r#"
+        <robot name="two_link">
+          <link name="base"/>
+          <link name="link1">
+            <inertial>
+              <origin xyz="0.5 0 0"/>
+              <mass value="2.0"/>
+              <inertia ixx="0.01" ixy="0" ixz="0" iyy="0.1" iyz="0" izz="0.01"/>
+            </inertial>
+            <collision>
+              <origin xyz="0.5 0 0" rpy="0 1.5707963267948966 0"/>
+              <geometry><cylinder radius="0.05" length="1.0"/></geometry>
+            </collision>
+          </link>
+          <link name="link2">
+            <inertial>
+              <origin xyz="0.4 0 0"/>
+              <mass value="1.5"/>
+              <inertia ixx="0.01" ixy="0" ixz="0" iyy="0.05" iyz="0" izz="0.01"/>
+            </inertial>
+            <collision>
+              <geometry><mesh filename="link2.stl"/></geometry>
+            </collision>
+          </link>
+          <link name="tool"/>
+          <joint name="joint1" type="revolute">
+            <origin xyz="0 0 0.2"/>
+            <parent link="base"/>
+            <child link="link1"/>
+            <axis xyz="0 1 0"/>
+            <limit lower="-1.5" upper="1.5" effort="30" velocity="2"/>
+          </joint>
+          <joint name="joint2" type="continuous">
+            <origin xyz="1.0 0 0"/>
+            <parent link="link1"/>
+            <child link="link2"/>
+            <axis xyz="0 1 0"/>
+          </joint>
+          <joint name="tool_joint" type="fixed">
+            <origin xyz="0.8 0 0"/>
+            <parent link="link2"/>
+            <child link="tool"/>
+          </joint>
+        </robot>
+    "#;
+
+    #[test]
+    fn panda_from_urdf() {
+        let base = Pose::from_parts(
+            na::Translation3::new(0.3, -0.2, 0.1),
+            na::UnitQuaternion::from_euler_angles(0.2, -0.1, 0.7),
+        );
+        let config = RobotConfig {
+            name: "panda_urdf".to_string(),
+            robot_type: "panda".to_string(),
+            base_pose: base,
+            urdf_path: Some("example/panda.urdf".to_string()),
+            tool_pose: None,
+            allowed_collision: None,
+        };
+        let RobotType::DSeriseRobot(robot) = from_config(&config).unwrap() else {
+            panic!("urdf robot should be a DSeriseRobot");
+        };
+        let robot = robot.read().unwrap();
+        let panda = DPanda::new_panda("panda_1".to_string(), base);
+
+        assert_eq!(robot.dof(), 7);
+        assert_eq!(robot.q_min_bound(), panda.q_min_bound());
+        assert_eq!(robot.q_max_bound(), panda.q_max_bound());
+        assert_eq!(robot.q_dot_bound(), panda.q_dot_bound());
+        assert_eq!(robot.tau_bound(), panda.tau_bound());
+        assert_eq!(robot.capsules().len(), 7);
+
+        let q = na::dvector![0.1, -0.6, 0.3, -2.0, 0.2, 1.5, 0.7];
+        let pose = robot.cul_end_pose(&q);
+        let expected = panda.cul_end_pose(&q);
+        assert!((pose.translation.vector - expected.translation.vector).norm() < 1e-9);
+        assert!(pose.rotation.angle_to(&expected.rotation) < 1e-9);
+        assert!((robot.cul_mass_matrix(&q) - panda.cul_mass_matrix(&q)).amax() < 1e-9);
+        assert!((robot.cul_gravity(&q) - panda.cul_gravity(&q)).amax() < 1e-9);
+    }
+
+    #[test]
+    fn two_link_from_urdf() {
+        let robot =
+            DSeriseRobot::from_urdf("two_link".to_string(), TWO_LINK, Pose::identity()).unwrap();
+        assert_eq!(robot.dof(), 2);
+        assert_eq!(
+            robot.q_min_bound(),
+            na::dvector![-1.5, -std::f64::consts::PI]
+        );
+        assert_eq!(robot.q_dot_bound(), na::dvector![2.0, f64::INFINITY]);
+
+        // 末端为固定关节连接的 tool 连杆
+        let q = na::dvector![0.3, 0.7];
+        let position = robot.cul_end_pose(&q).translation.vector;
+        let expected = na::Vector3::new(
+            L1 * q[0].cos() + 0.8 * (q[0] + q[1]).cos(),
+            0.0,
+            0.2 - L1 * q[0].sin() - 0.8 * (q[0] + q[1]).sin(),
+        );
+        assert!((position - expected).norm() < 1e-9);
+
+        let c2 = q[1].cos();
+        let m11 = M1 * LC1 * LC1 + I1 + M2 * (L1 * L1 + LC2 * LC2 + 2.0 * L1 * LC2 * c2) + I2;
+        let m12 = M2 * (LC2 * LC2 + L1 * LC2 * c2) + I2;
+        let m22 = M2 * LC2 * LC2 + I2;
+        let expected = na::dmatrix![m11, m12; m12, m22];
+        assert!((robot.cul_mass_matrix(&q) - expected).amax() < 1e-9);
+
+        // 圆柱体转换为等长的胶囊体，只有网格的连杆沿关节连线生成胶囊体
+        let capsules = robot.params.capsules;
+        assert!((capsules[0].params.0 - 0.05).abs() < 1e-9);
+        assert!((capsules[0].params.1 - 1.0).abs() < 1e-9);
+        assert!((capsules[1].params.1 - 0.8).abs() < 1e-9);
+    }
+
+    #[test]
+    fn invalid_urdf() {
+        // 转动关节之间的移动关节不受支持
+        let prismatic = TWO_LINK.replace(r#"type="revolute""#, r#"type="prismatic""#);
+        let result = DSeriseRobot::from_urdf("two_link".to_string(), &prismatic, Pose::identity());
+        assert!(matches!(result, Err(RobotError::InvalidUrdf(_))));
+
+        let result = DSeriseRobot::from_file(
+            "two_link".to_string(),
+            "example/missing.urdf",
+            Pose::identity(),
+        );
+        assert!(matches!(result, Err(RobotError::ReadFile(_))));
+    }
+}